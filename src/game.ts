@@ -1,18 +1,80 @@
 import init, { GameEngine } from "./wasm/particle_system.js"
 import { SoundManager } from "./sound.js"
 
+const SAVE_KEY = "pew-pew-save-state"
+// Designer tuning, a GameConfig as JSON, see wasm/src/config.rs
+const CONFIG_KEY = "pew-pew-game-config"
+// Difficulty preset for new games: easy, normal, hard or nightmare
+const DIFFICULTY_KEY = "pew-pew-difficulty"
+
+// Render schema version this renderer was written against
+const RENDER_SCHEMA_VERSION = 1
+
+interface RenderSchema {
+  version: number
+  channels: { id: number; name: string; stride: number; fields: string[] }[]
+}
+
+// Where a channel lives and the offset of each field within an entity,
+// built from the engine's schema so new fields don't break the renderer
+interface ChannelLayout {
+  id: number
+  stride: number
+  field: Record<string, number>
+}
+
+interface ChannelView extends ChannelLayout {
+  data: Float32Array
+  count: number
+}
+
+// Boss health bar, drawn over the whole canvas
+interface BossBar {
+  health: number
+  maxHealth: number
+  nextPhaseHealth: number
+  phase: number
+  phaseCount: number
+}
+
+// Event kinds from the engine's drain_events, see wasm/src/events.rs
+const EVENT_ENEMY_KILLED = 0
+const EVENT_BLACK_HOLE_OPENED = 4
+const EVENT_BLACK_HOLE_COLLAPSED = 5
+const EVENT_BOSS_PHASE_CHANGED = 10
+const EVENT_BOSS_PART_DESTROYED = 11
+const EVENT_BOSS_DEFEATED = 12
+const ENEMY_TYPE_TANK = 2
+// Enemy ai_state ids, see AiState in wasm/src/ai.rs
+const AI_STATE_NAMES = ["enter", "attack", "evade", "retreat", "flee"]
+
+// Frames longer than this many seconds pause the game
+const AUTO_PAUSE_FRAME_TIME = 0.25
+
 class SpaceShooterGame {
   private canvas: HTMLCanvasElement
   private ctx: CanvasRenderingContext2D
   private gameEngine: GameEngine | null = null
+  private wasmMemory: WebAssembly.Memory | null = null
+  private channelLayouts: Map<string, ChannelLayout> = new Map()
+  // Last seen health per enemy id slot, for hit flashes
+  private enemyHits: Map<
+    number,
+    { generation: number; health: number; hitTime: number }
+  > = new Map()
   private animationId: number | null = null
   private lastTime: number = 0
   private keys: Set<string> = new Set()
   private isShooting: boolean = false
   private isMovingForward: boolean = false
+  private blackHoleRequested: boolean = false
   private soundManager: SoundManager
-  private lastExplosionCount: number = 0
-  private lastBlackHoleCount: number = 0
+  // Stress mode overlay: bullet target and smoothed frame/update times in ms
+  private stressBullets: number = 0
+  private frameTime: number = 0
+  private updateTime: number = 0
+  // Label enemies with their AI state
+  private showAiStates: boolean = false
 
   constructor() {
     this.canvas = document.getElementById("canvas") as HTMLCanvasElement
@@ -27,10 +89,9 @@ class SpaceShooterGame {
     const resize = () => {
       this.canvas.width = window.innerWidth
       this.canvas.height = window.innerHeight
-      if (this.gameEngine) {
-        // Note: We'll need to recreate the game engine on resize
-        // For now, we'll just update the canvas size
-      }
+      // The game keeps running at its logical resolution, only the view
+      // transform follows the canvas
+      this.gameEngine?.resize(this.canvas.width, this.canvas.height)
     }
 
     window.addEventListener("resize", resize)
@@ -49,6 +110,9 @@ class SpaceShooterGame {
         e.preventDefault()
         this.activateBlackHole()
       }
+      if ((e.key === "p" || e.key === "P" || e.key === "Escape") && !e.repeat) {
+        this.togglePause()
+      }
 
       // Resume audio context on first user interaction
       this.soundManager.resumeAudio()
@@ -69,20 +133,148 @@ class SpaceShooterGame {
 
   private async init(): Promise<void> {
     try {
-      await init()
-      this.gameEngine = GameEngine.new(this.canvas.width, this.canvas.height)
+      const wasm = await init()
+      this.wasmMemory = wasm.memory
+      this.gameEngine = this.createEngine()
+      // Simulate in fixed ticks and blend between them when rendering
+      this.gameEngine.set_fixed_timestep(true)
+      // Coming back to a backgrounded tab pauses instead of jumping ahead
+      this.gameEngine.set_auto_pause(AUTO_PAUSE_FRAME_TIME)
+      this.loadRenderSchema()
+      this.restoreGame()
+      window.addEventListener("pagehide", () => this.saveGame())
       this.startGameLoop()
     } catch (error) {
       console.error("Failed to initialize WebAssembly game:", error)
     }
   }
 
+  // New game with the saved tuning config, else the saved difficulty
+  // preset, else the defaults. Saved settings that no longer validate
+  // are skipped.
+  private createEngine(): GameEngine {
+    const config = localStorage.getItem(CONFIG_KEY)
+    if (config) {
+      try {
+        return GameEngine.new_with_config(
+          this.canvas.width,
+          this.canvas.height,
+          config
+        )
+      } catch (error) {
+        console.error("Ignoring saved game config:", error)
+      }
+    }
+    const difficulty = localStorage.getItem(DIFFICULTY_KEY)
+    if (difficulty) {
+      try {
+        return GameEngine.new_with_difficulty(
+          this.canvas.width,
+          this.canvas.height,
+          difficulty
+        )
+      } catch (error) {
+        console.error("Ignoring saved difficulty:", error)
+      }
+    }
+    return GameEngine.new(this.canvas.width, this.canvas.height)
+  }
+
+  // Start a new game at a difficulty preset, kept for later games
+  public setDifficulty(difficulty: string): void {
+    if (!this.gameEngine) return
+
+    let engine: GameEngine
+    try {
+      engine = GameEngine.new_with_difficulty(
+        this.canvas.width,
+        this.canvas.height,
+        difficulty
+      )
+    } catch (error) {
+      console.error("Invalid difficulty:", error)
+      return
+    }
+
+    localStorage.setItem(DIFFICULTY_KEY, difficulty)
+    // A saved config would override the preset on the next load
+    localStorage.removeItem(CONFIG_KEY)
+    this.useEngine(engine)
+  }
+
+  // Start a new game tuned by a GameConfig in JSON, only the changed
+  // fields are needed. null goes back to the defaults.
+  public setGameConfig(config: string | null): void {
+    if (!this.gameEngine) return
+
+    let engine: GameEngine
+    try {
+      engine =
+        config === null
+          ? GameEngine.new(this.canvas.width, this.canvas.height)
+          : GameEngine.new_with_config(
+              this.canvas.width,
+              this.canvas.height,
+              config
+            )
+    } catch (error) {
+      console.error("Invalid game config:", error)
+      return
+    }
+
+    if (config === null) {
+      localStorage.removeItem(CONFIG_KEY)
+    } else {
+      localStorage.setItem(CONFIG_KEY, config)
+    }
+    this.useEngine(engine)
+  }
+
+  // Swap in a freshly created game
+  private useEngine(engine: GameEngine): void {
+    engine.set_fixed_timestep(true)
+    engine.set_auto_pause(AUTO_PAUSE_FRAME_TIME)
+    if (this.gameEngine !== engine) {
+      this.gameEngine?.free()
+      this.gameEngine = engine
+    }
+    this.enemyHits.clear()
+    this.stressBullets = 0
+
+    const gameOverElement = document.getElementById("gameOver") as HTMLElement
+    gameOverElement.style.display = "none"
+  }
+
+  // Restart playing a scripted stage timeline in JSON, null goes back to
+  // random spawning. Kept for the session only.
+  public loadStage(stage: string | null): void {
+    if (!this.gameEngine) return
+
+    try {
+      if (stage === null) {
+        this.gameEngine.clear_stage()
+      } else {
+        this.gameEngine.load_stage(stage)
+      }
+    } catch (error) {
+      console.error("Invalid stage:", error)
+      return
+    }
+    this.useEngine(this.gameEngine)
+  }
+
+  // The tuning in use, every field included
+  public getGameConfig(): string {
+    return this.gameEngine ? this.gameEngine.get_config() : ""
+  }
+
   private startGameLoop(): void {
     const gameLoop = (currentTime: number) => {
       this.animationId = requestAnimationFrame(gameLoop)
 
       const deltaTime = (currentTime - this.lastTime) / 1000
       this.lastTime = currentTime
+      this.frameTime += (deltaTime * 1000 - this.frameTime) * 0.1
 
       this.update(deltaTime)
       this.render()
@@ -132,15 +324,23 @@ class SpaceShooterGame {
     // Store movement state for warp core effect
     this.isMovingForward = dy < 0
 
-    this.gameEngine.move_player(dx, dy)
-
-    if (this.isShooting) {
-      this.gameEngine.shoot()
-      this.soundManager.playLaserSound()
+    // Queue this frame's input, the engine applies it on the next tick.
+    // Nothing is queued while paused, so presses don't pile up for resume.
+    if (!this.gameEngine.is_paused()) {
+      this.gameEngine.queue_input(dx, dy, this.isShooting, this.blackHoleRequested)
+      if (this.isShooting) {
+        this.soundManager.playLaserSound()
+      }
     }
+    this.blackHoleRequested = false
 
     // Update game engine
+    const updateStart = performance.now()
     this.gameEngine.update(deltaTime)
+    this.updateTime += (performance.now() - updateStart - this.updateTime) * 0.1
+
+    // React to what happened during the update
+    this.handleEvents()
 
     // Update UI
     this.updateUI()
@@ -151,6 +351,37 @@ class SpaceShooterGame {
     }
   }
 
+  private handleEvents(): void {
+    if (!this.gameEngine) return
+
+    const events = this.gameEngine.drain_events()
+    const stride = this.gameEngine.get_event_stride()
+
+    for (let i = 0; i + stride <= events.length; i += stride) {
+      const kind = events[i]
+      switch (kind) {
+        case EVENT_ENEMY_KILLED:
+          if (events[i + 3] === ENEMY_TYPE_TANK) {
+            this.soundManager.playExplosionSound("tank")
+          }
+          break
+        case EVENT_BLACK_HOLE_OPENED:
+          this.soundManager.playBlackHoleActivation()
+          break
+        case EVENT_BLACK_HOLE_COLLAPSED:
+        case EVENT_BOSS_DEFEATED:
+          this.soundManager.playExplosionSound("blackhole")
+          break
+        case EVENT_BOSS_PART_DESTROYED:
+          this.soundManager.playExplosionSound("tank")
+          break
+        case EVENT_BOSS_PHASE_CHANGED:
+          this.soundManager.playBlackHoleActivation()
+          break
+      }
+    }
+  }
+
   private updateUI(): void {
     if (!this.gameEngine) return
 
@@ -165,7 +396,8 @@ class SpaceShooterGame {
       .toString()
 
     // Update health bar
-    const healthPercent = (this.gameEngine.get_health() / 100.0) * 100
+    const healthPercent =
+      (this.gameEngine.get_health() / this.gameEngine.get_max_health()) * 100
     const healthFill = document.getElementById("healthFill") as HTMLElement
     healthFill.style.width = `${healthPercent}%`
 
@@ -204,6 +436,7 @@ class SpaceShooterGame {
     const shieldLevel = this.gameEngine.get_shield_level()
     const shieldActive = this.gameEngine.is_shield_active()
     const shieldTimer = this.gameEngine.get_shield_timer()
+    const maxShieldLevel = this.gameEngine.get_max_shield_level()
 
     const shieldBar = document.getElementById("shieldBar") as HTMLElement
     const shieldFill = document.getElementById("shieldFill") as HTMLElement
@@ -212,7 +445,7 @@ class SpaceShooterGame {
     if (shieldBar && shieldFill && shieldText) {
       if (shieldActive && shieldLevel > 0) {
         shieldBar.style.display = "block"
-        const shieldPercent = (shieldLevel / 3.0) * 100
+        const shieldPercent = (shieldLevel / maxShieldLevel) * 100
         shieldFill.style.width = `${shieldPercent}%`
 
         // Change color based on shield level
@@ -224,7 +457,7 @@ class SpaceShooterGame {
           shieldFill.style.backgroundColor = "#9c27b0" // Purple
         }
 
-        shieldText.textContent = `SHIELD: ${shieldLevel}/3 (${shieldTimer.toFixed(
+        shieldText.textContent = `SHIELD: ${shieldLevel}/${maxShieldLevel} (${shieldTimer.toFixed(
           1
         )}s)`
       } else {
@@ -233,13 +466,69 @@ class SpaceShooterGame {
     }
   }
 
+  private togglePause(): void {
+    if (!this.gameEngine) return
+
+    if (this.gameEngine.is_paused()) {
+      this.gameEngine.resume()
+    } else {
+      this.gameEngine.pause()
+    }
+  }
+
+  // Slow motion below 1, e.g. setTimeScale(0.25)
+  public setTimeScale(scale: number): void {
+    this.gameEngine?.set_time_scale(scale)
+  }
+
   private activateBlackHole(): void {
     if (this.gameEngine) {
-      this.gameEngine.activate_black_hole()
-      this.soundManager.playBlackHoleActivation()
+      this.blackHoleRequested = true
     }
   }
 
+  private loadRenderSchema(): void {
+    if (!this.gameEngine) return
+
+    const schema: RenderSchema = JSON.parse(this.gameEngine.get_render_schema())
+    if (schema.version !== RENDER_SCHEMA_VERSION) {
+      console.warn(
+        `Engine render schema v${schema.version}, renderer expects v${RENDER_SCHEMA_VERSION}`
+      )
+    }
+
+    this.channelLayouts.clear()
+    for (const channel of schema.channels) {
+      const field: Record<string, number> = {}
+      channel.fields.forEach((name, offset) => {
+        field[name] = offset
+      })
+      this.channelLayouts.set(channel.name, {
+        id: channel.id,
+        stride: channel.stride,
+        field,
+      })
+    }
+  }
+
+  // View of one render channel. Points straight into wasm memory, so it is
+  // only valid until the next engine call that may allocate
+  private readChannel(name: string): ChannelView | null {
+    const layout = this.channelLayouts.get(name)
+    if (!this.gameEngine || !layout) return null
+
+    const count = this.gameEngine.get_channel_count(layout.id)
+    const data = this.wasmMemory
+      ? new Float32Array(
+          this.wasmMemory.buffer,
+          this.gameEngine.get_channel_ptr(layout.id),
+          count * layout.stride
+        )
+      : this.gameEngine.get_channel_data(layout.id)
+
+    return { ...layout, data, count }
+  }
+
   private render(): void {
     if (!this.gameEngine) return
 
@@ -249,66 +538,67 @@ class SpaceShooterGame {
     // Draw starfield background
     this.drawStarfield()
 
-    try {
-      // Get game data from WebAssembly
-      const gameData = this.gameEngine.get_game_data()
-      let dataIndex = 0
-
-      // Safety check for minimum data length
-      if (gameData.length < 7) {
-        console.error("Game data too short:", gameData.length)
-        return
-      }
-
-      // Parse metadata: [player_count, enemy_count, player_bullet_count, enemy_bullet_count, power_up_count, explosion_count, black_hole_count]
-      const playerCount = Math.floor(gameData[dataIndex++])
-      const enemyCount = Math.floor(gameData[dataIndex++])
-      const playerBulletCount = Math.floor(gameData[dataIndex++])
-      const enemyBulletCount = Math.floor(gameData[dataIndex++])
-      const powerUpCount = Math.floor(gameData[dataIndex++])
-      const explosionCount = Math.floor(gameData[dataIndex++])
-      const blackHoleCount = Math.floor(gameData[dataIndex++])
-
-      // Check for new explosions and play sounds
-      if (explosionCount > this.lastExplosionCount) {
-        // New explosion detected - play tank explosion sound
-        this.soundManager.playExplosionSound("tank")
-      }
-      this.lastExplosionCount = explosionCount
+    // Everything from the engine is in world coordinates, scaled and
+    // letterboxed onto the canvas
+    const view = this.gameEngine.get_view_transform()
+    this.ctx.save()
+    this.ctx.setTransform(view[0], view[1], view[2], view[3], view[4], view[5])
+    this.ctx.beginPath()
+    this.ctx.rect(
+      0,
+      0,
+      this.gameEngine.get_logical_width(),
+      this.gameEngine.get_logical_height()
+    )
+    this.ctx.clip()
 
-      // Check for new black holes and play sounds
-      if (blackHoleCount > this.lastBlackHoleCount) {
-        // New black hole detected - play black hole activation sound
-        this.soundManager.playBlackHoleActivation()
-      }
-      this.lastBlackHoleCount = blackHoleCount
+    let bossBar: BossBar | null = null
+    try {
+      this.gameEngine.update_render_data()
+
+      // Blend between the previous and current simulation tick on high
+      // refresh rate monitors
+      const alpha = this.gameEngine.get_interpolation_alpha()
+      const lerp = (prev: number, current: number): number =>
+        prev + (current - prev) * alpha
+
+      const player = this.readChannel("player")
+      const enemies = this.readChannel("enemies")
+      const playerBullets = this.readChannel("player_bullets")
+      const enemyBullets = this.readChannel("enemy_bullets")
+      const powerUps = this.readChannel("power_ups")
+      const explosions = this.readChannel("explosions")
+      const blackHoles = this.readChannel("black_holes")
+      const boss = this.readChannel("boss")
+      const bossParts = this.readChannel("boss_parts")
+      const mines = this.readChannel("mines")
 
-      // Safety check for reasonable counts
       if (
-        enemyCount > 100 ||
-        playerBulletCount > 100 ||
-        enemyBulletCount > 100 ||
-        powerUpCount > 50 ||
-        explosionCount > 20
+        !player ||
+        !enemies ||
+        !playerBullets ||
+        !enemyBullets ||
+        !powerUps ||
+        !explosions ||
+        !blackHoles ||
+        !boss ||
+        !bossParts ||
+        !mines
       ) {
-        console.error("Unreasonable object counts:", {
-          enemyCount,
-          playerBulletCount,
-          enemyBulletCount,
-          powerUpCount,
-          explosionCount,
-        })
+        console.error("Render schema is missing channels")
         return
       }
 
-      // Draw player (6 values: x, y, size, health, power_level, growth_level)
-      if (dataIndex + 5 < gameData.length) {
-        const playerX = gameData[dataIndex++]
-        const playerY = gameData[dataIndex++]
-        const playerSize = gameData[dataIndex++]
-        const playerHealth = gameData[dataIndex++]
-        const playerPowerLevel = gameData[dataIndex++]
-        const playerGrowthLevel = gameData[dataIndex++]
+      // Draw player
+      let playerX = 0
+      let playerY = 0
+      let playerSize = 0
+      if (player.count > 0) {
+        const d = player.data
+        const f = player.field
+        playerX = lerp(d[f.prev_x], d[f.x])
+        playerY = lerp(d[f.prev_y], d[f.y])
+        playerSize = d[f.size]
 
         // Safety check for player position
         if (playerX >= 0 && playerY >= 0 && playerSize > 0) {
@@ -316,126 +606,181 @@ class SpaceShooterGame {
             playerX,
             playerY,
             playerSize,
-            playerHealth,
-            playerPowerLevel,
-            playerGrowthLevel
+            d[f.health],
+            d[f.power_level],
+            d[f.growth_level]
           )
         }
       }
 
-      // Draw enemies (5 values each: x, y, size, health, type)
-      for (let i = 0; i < enemyCount && dataIndex + 4 < gameData.length; i++) {
-        const x = gameData[dataIndex++]
-        const y = gameData[dataIndex++]
-        const size = gameData[dataIndex++]
-        const health = gameData[dataIndex++]
-        const enemyType = gameData[dataIndex++]
+      // Draw enemies
+      const now = performance.now()
+      for (let i = 0; i < enemies.count; i++) {
+        const o = i * enemies.stride
+        const d = enemies.data
+        const f = enemies.field
+        const x = lerp(d[o + f.prev_x], d[o + f.x])
+        const y = lerp(d[o + f.prev_y], d[o + f.y])
+        const size = d[o + f.size]
+        const health = d[o + f.health]
+
+        // Follow the enemy across frames by id, a new generation in the
+        // same slot is a different enemy
+        const generation = d[o + f.generation]
+        let hit = this.enemyHits.get(d[o + f.id])
+        if (!hit || hit.generation !== generation) {
+          hit = { generation, health, hitTime: -Infinity }
+          this.enemyHits.set(d[o + f.id], hit)
+        } else if (health < hit.health) {
+          hit.hitTime = now
+        }
+        hit.health = health
 
         // Safety check for enemy position
         if (x >= 0 && y >= 0 && size > 0) {
-          this.drawEnemy(x, y, size, health, enemyType)
-        }
-      }
+          // Sniper sight under the ship
+          if (f.laser !== undefined && d[o + f.laser] > 0) {
+            this.drawLaser(x, y, d[o + f.laser_angle], d[o + f.laser_charge])
+          }
 
-      // Draw player bullets (4 values each: x, y, size, is_enemy)
-      for (
-        let i = 0;
-        i < playerBulletCount && dataIndex + 3 < gameData.length;
-        i++
-      ) {
-        const x = gameData[dataIndex++]
-        const y = gameData[dataIndex++]
-        const size = gameData[dataIndex++]
-        const isEnemy = gameData[dataIndex++]
+          this.drawEnemy(x, y, size, health, d[o + f.type])
+
+          // Hit flash
+          const flash = 1 - (now - hit.hitTime) / 120
+          if (flash > 0) {
+            this.ctx.save()
+            this.ctx.globalAlpha = flash * 0.6
+            this.ctx.fillStyle = "#ffffff"
+            this.ctx.beginPath()
+            this.ctx.arc(x, y, size, 0, Math.PI * 2)
+            this.ctx.fill()
+            this.ctx.restore()
+          }
 
-        // Safety check for bullet position
-        if (x >= 0 && y >= 0 && size > 0) {
-          this.drawBullet(x, y, size, isEnemy === 1.0)
+          if (this.showAiStates && f.ai_state !== undefined) {
+            this.drawAiState(x, y, size, d[o + f.ai_state])
+          }
         }
       }
 
-      // Draw enemy bullets (4 values each: x, y, size, is_enemy)
-      for (
-        let i = 0;
-        i < enemyBulletCount && dataIndex + 3 < gameData.length;
-        i++
-      ) {
-        const x = gameData[dataIndex++]
-        const y = gameData[dataIndex++]
-        const size = gameData[dataIndex++]
-        const isEnemy = gameData[dataIndex++]
+      // Draw the boss, parts in front of the core
+      if (boss.count > 0) {
+        const d = boss.data
+        const f = boss.field
+        this.drawBoss(
+          lerp(d[f.prev_x], d[f.x]),
+          lerp(d[f.prev_y], d[f.y]),
+          d[f.size],
+          d[f.phase]
+        )
+        bossBar = {
+          health: d[f.health],
+          maxHealth: d[f.max_health],
+          nextPhaseHealth: d[f.next_phase_health],
+          phase: d[f.phase],
+          phaseCount: d[f.phase_count],
+        }
+      }
+      for (let i = 0; i < bossParts.count; i++) {
+        const o = i * bossParts.stride
+        const d = bossParts.data
+        const f = bossParts.field
+        this.drawBossPart(
+          lerp(d[o + f.prev_x], d[o + f.x]),
+          lerp(d[o + f.prev_y], d[o + f.y]),
+          d[o + f.size],
+          d[o + f.health] / d[o + f.max_health]
+        )
+      }
 
-        // Safety check for bullet position
-        if (x >= 0 && y >= 0 && size > 0) {
-          this.drawBullet(x, y, size, isEnemy === 1.0)
+      // Draw player and enemy bullets
+      for (const [bullets, isEnemy] of [
+        [playerBullets, false],
+        [enemyBullets, true],
+      ] as const) {
+        for (let i = 0; i < bullets.count; i++) {
+          const o = i * bullets.stride
+          const d = bullets.data
+          const f = bullets.field
+          const x = lerp(d[o + f.prev_x], d[o + f.x])
+          const y = lerp(d[o + f.prev_y], d[o + f.y])
+          const size = d[o + f.size]
+
+          // Safety check for bullet position
+          if (x >= 0 && y >= 0 && size > 0) {
+            this.drawBullet(x, y, size, isEnemy)
+          }
         }
       }
 
-      // Draw power-ups (4 values each: x, y, size, type)
-      for (
-        let i = 0;
-        i < powerUpCount && dataIndex + 3 < gameData.length;
-        i++
-      ) {
-        const x = gameData[dataIndex++]
-        const y = gameData[dataIndex++]
-        const size = gameData[dataIndex++]
-        const powerType = gameData[dataIndex++]
+      // Draw mines
+      for (let i = 0; i < mines.count; i++) {
+        const o = i * mines.stride
+        const d = mines.data
+        const f = mines.field
+        this.drawMine(d[o + f.x], d[o + f.y], d[o + f.size], d[o + f.life_ratio])
+      }
+
+      // Draw power-ups
+      for (let i = 0; i < powerUps.count; i++) {
+        const o = i * powerUps.stride
+        const d = powerUps.data
+        const f = powerUps.field
+        const x = lerp(d[o + f.prev_x], d[o + f.x])
+        const y = lerp(d[o + f.prev_y], d[o + f.y])
+        const size = d[o + f.size]
 
         // Safety check for power-up position
         if (x >= 0 && y >= 0 && size > 0) {
-          this.drawPowerUp(x, y, size, powerType)
+          this.drawPowerUp(x, y, size, d[o + f.type])
         }
       }
 
-      // Draw explosions (4 values each: x, y, size, life_ratio)
-      for (
-        let i = 0;
-        i < explosionCount && dataIndex + 3 < gameData.length;
-        i++
-      ) {
-        const x = gameData[dataIndex++]
-        const y = gameData[dataIndex++]
-        const size = gameData[dataIndex++]
-        const lifeRatio = gameData[dataIndex++]
+      // Draw explosions
+      for (let i = 0; i < explosions.count; i++) {
+        const o = i * explosions.stride
+        const d = explosions.data
+        const f = explosions.field
+        const x = d[o + f.x]
+        const y = d[o + f.y]
+        const size = d[o + f.size]
 
         // Safety check for explosion position
         if (x >= 0 && y >= 0 && size > 0) {
-          this.drawExplosion(x, y, size, lifeRatio)
+          this.drawExplosion(x, y, size, d[o + f.life_ratio])
         }
       }
 
-      // Draw black holes (5 values each: x, y, size, life_ratio, pull_radius)
-      for (
-        let i = 0;
-        i < blackHoleCount && dataIndex + 4 < gameData.length;
-        i++
-      ) {
-        const x = gameData[dataIndex++]
-        const y = gameData[dataIndex++]
-        const size = gameData[dataIndex++]
-        const lifeRatio = gameData[dataIndex++]
-        const pullRadius = gameData[dataIndex++]
+      // Draw black holes
+      for (let i = 0; i < blackHoles.count; i++) {
+        const o = i * blackHoles.stride
+        const d = blackHoles.data
+        const f = blackHoles.field
+        const x = d[o + f.x]
+        const y = d[o + f.y]
+        const size = d[o + f.size]
 
         // Safety check for black hole position
         if (x >= 0 && y >= 0 && size > 0) {
-          this.drawBlackHole(x, y, size, lifeRatio, pullRadius)
+          this.drawBlackHole(
+            x,
+            y,
+            size,
+            d[o + f.life_ratio],
+            d[o + f.pull_radius]
+          )
         }
       }
 
-      // Read shield data using the GameEngine methods instead of parsing from array
-      try {
-        const shieldLevel = this.gameEngine.get_shield_level()
-        const shieldActive = this.gameEngine.is_shield_active()
-        const shieldTimer = this.gameEngine.get_shield_timer()
+      // Shield state comes with the player channel
+      if (player.count > 0) {
+        const shieldLevel = player.data[player.field.shield_level]
+        const shieldActive = player.data[player.field.shield_active] > 0
+        const shieldTimer = player.data[player.field.shield_timer]
 
         // Draw shield if active
         if (shieldActive && shieldLevel > 0) {
           const time = Date.now() * 0.001
-          // Get player position for shield rendering (player data starts at index 7)
-          const playerX = gameData[7] // Player x position
-          const playerY = gameData[8] // Player y position
-          const playerSize = gameData[9] // Player size
 
           if (playerX >= 0 && playerY >= 0 && playerSize > 0) {
             this.drawEnergyShield(
@@ -458,12 +803,22 @@ class SpaceShooterGame {
             )
           }
         }
-      } catch (error) {
-        console.error("Error reading shield data:", error)
       }
+
     } catch (error) {
       console.error("Error in render function:", error)
     }
+    this.ctx.restore()
+
+    if (bossBar) {
+      this.drawBossHealthBar(bossBar)
+    }
+    if (this.gameEngine.is_paused()) {
+      this.drawPauseOverlay()
+    }
+    if (this.stressBullets > 0) {
+      this.drawStressOverlay()
+    }
   }
 
   private drawStarfield(): void {
@@ -1520,6 +1875,21 @@ class SpaceShooterGame {
       case 2: // Heavy Tank
         this.drawHeavyTank(size, time, health)
         break
+      case 3:
+        this.drawSplitter(size, time)
+        break
+      case 4:
+        this.drawCarrier(size, time)
+        break
+      case 5:
+        this.drawSniper(size)
+        break
+      case 6:
+        this.drawMineLayer(size, time)
+        break
+      case 7:
+        this.drawHealer(size, time)
+        break
       default:
         this.drawBasicFighter(size, time)
     }
@@ -1561,6 +1931,94 @@ class SpaceShooterGame {
     }
   }
 
+  // Armoured core that glows hotter with each phase
+  private drawBoss(x: number, y: number, size: number, phase: number): void {
+    const time = Date.now() * 0.001
+    const colors = ["#7b1fa2", "#c62828", "#ff6f00"]
+    const glow = colors[Math.min(phase, colors.length - 1)]
+
+    this.ctx.save()
+    this.ctx.translate(x, y)
+
+    const hull = this.ctx.createRadialGradient(0, 0, size * 0.2, 0, 0, size)
+    hull.addColorStop(0, "#eeeeee")
+    hull.addColorStop(0.3, glow)
+    hull.addColorStop(1, "#212121")
+    this.ctx.fillStyle = hull
+    this.ctx.beginPath()
+    for (let i = 0; i < 8; i++) {
+      const angle = (i / 8) * Math.PI * 2 + Math.PI / 8
+      this.ctx.lineTo(Math.cos(angle) * size, Math.sin(angle) * size)
+    }
+    this.ctx.closePath()
+    this.ctx.fill()
+
+    // Pulsing eye
+    this.ctx.shadowColor = glow
+    this.ctx.shadowBlur = 20 + Math.sin(time * 4) * 8
+    this.ctx.fillStyle = glow
+    this.ctx.beginPath()
+    this.ctx.arc(0, size * 0.2, size * 0.25, 0, Math.PI * 2)
+    this.ctx.fill()
+
+    this.ctx.restore()
+  }
+
+  private drawBossPart(
+    x: number,
+    y: number,
+    size: number,
+    healthRatio: number
+  ): void {
+    this.ctx.save()
+    this.ctx.translate(x, y)
+
+    this.ctx.fillStyle = "#424242"
+    this.ctx.strokeStyle = healthRatio > 0.5 ? "#90a4ae" : "#ff5252"
+    this.ctx.lineWidth = 3
+    this.ctx.beginPath()
+    this.ctx.arc(0, 0, size, 0, Math.PI * 2)
+    this.ctx.fill()
+    this.ctx.stroke()
+
+    // Barrel
+    this.ctx.fillStyle = "#90a4ae"
+    this.ctx.fillRect(-size * 0.2, 0, size * 0.4, size * 1.2)
+
+    this.ctx.restore()
+  }
+
+  // Across the top of the canvas, with marks where later phases start
+  private drawBossHealthBar(bar: BossBar): void {
+    const width = this.canvas.width * 0.6
+    const height = 14
+    const x = (this.canvas.width - width) / 2
+    const y = 20
+    const ratio = Math.max(0, bar.health / bar.maxHealth)
+
+    this.ctx.save()
+    this.ctx.fillStyle = "rgba(0, 0, 0, 0.7)"
+    this.ctx.fillRect(x - 2, y - 2, width + 4, height + 4)
+    this.ctx.fillStyle = "#d32f2f"
+    this.ctx.fillRect(x, y, width * ratio, height)
+
+    if (bar.nextPhaseHealth > 0) {
+      const mark = x + width * (bar.nextPhaseHealth / bar.maxHealth)
+      this.ctx.fillStyle = "#ffffff"
+      this.ctx.fillRect(mark - 1, y, 2, height)
+    }
+
+    this.ctx.fillStyle = "#ffffff"
+    this.ctx.font = "bold 12px Arial"
+    this.ctx.textAlign = "center"
+    this.ctx.fillText(
+      `BOSS  phase ${bar.phase + 1}/${bar.phaseCount}`,
+      this.canvas.width / 2,
+      y + height + 16
+    )
+    this.ctx.restore()
+  }
+
   private drawBasicFighter(size: number, time: number): void {
     // Basic enemy fighter - sleek and aggressive
     const hullGradient = this.ctx.createLinearGradient(0, -size, 0, size)
@@ -2021,12 +2479,252 @@ class SpaceShooterGame {
   public restart(): void {
     if (this.gameEngine) {
       this.gameEngine.reset()
+      this.gameEngine.resume()
+      this.enemyHits.clear()
     }
 
     const gameOverElement = document.getElementById("gameOver") as HTMLElement
     gameOverElement.style.display = "none"
   }
 
+  // Keep the current run in localStorage so it survives a page reload
+  private saveGame(): void {
+    if (!this.gameEngine || this.gameEngine.is_game_over()) {
+      localStorage.removeItem(SAVE_KEY)
+      return
+    }
+
+    const bytes = this.gameEngine.save_state()
+    let binary = ""
+    for (let i = 0; i < bytes.length; i++) {
+      binary += String.fromCharCode(bytes[i])
+    }
+    localStorage.setItem(SAVE_KEY, btoa(binary))
+  }
+
+  private restoreGame(): void {
+    const saved = localStorage.getItem(SAVE_KEY)
+    if (!saved || !this.gameEngine) return
+
+    try {
+      const bytes = Uint8Array.from(atob(saved), (c) => c.charCodeAt(0))
+      this.gameEngine.load_state(bytes)
+    } catch (error) {
+      // Saves from an older engine version are rejected, start fresh
+      console.warn("Discarding saved game:", error)
+      localStorage.removeItem(SAVE_KEY)
+    }
+  }
+
+  // Flood the screen with bullets to check the engine holds 60 fps,
+  // 0 turns it off
+  public setStressMode(bullets: number): void {
+    if (!this.gameEngine) return
+
+    this.stressBullets = bullets
+    this.gameEngine.set_stress_mode(bullets)
+  }
+
+  public setShowAiStates(show: boolean): void {
+    this.showAiStates = show
+  }
+
+  // Cracked pod, showing the pieces it breaks into
+  private drawSplitter(size: number, time: number): void {
+    this.ctx.fillStyle = "#8bc34a"
+    this.ctx.strokeStyle = "#33691e"
+    this.ctx.lineWidth = 2
+    for (let i = 0; i < 3; i++) {
+      const angle = (i / 3) * Math.PI * 2 + time
+      this.ctx.beginPath()
+      this.ctx.arc(
+        Math.cos(angle) * size * 0.4,
+        Math.sin(angle) * size * 0.4,
+        size * 0.55,
+        0,
+        Math.PI * 2
+      )
+      this.ctx.fill()
+      this.ctx.stroke()
+    }
+  }
+
+  // Wide hull with a launch bay that lights up
+  private drawCarrier(size: number, time: number): void {
+    this.ctx.fillStyle = "#546e7a"
+    this.ctx.strokeStyle = "#263238"
+    this.ctx.lineWidth = 3
+    this.ctx.beginPath()
+    this.ctx.moveTo(-size, -size * 0.5)
+    this.ctx.lineTo(size, -size * 0.5)
+    this.ctx.lineTo(size * 0.7, size * 0.6)
+    this.ctx.lineTo(-size * 0.7, size * 0.6)
+    this.ctx.closePath()
+    this.ctx.fill()
+    this.ctx.stroke()
+
+    this.ctx.fillStyle = `rgba(255, 193, 7, ${0.5 + Math.sin(time * 3) * 0.3})`
+    this.ctx.fillRect(-size * 0.4, size * 0.2, size * 0.8, size * 0.3)
+  }
+
+  private drawSniper(size: number): void {
+    this.ctx.fillStyle = "#b0bec5"
+    this.ctx.beginPath()
+    this.ctx.moveTo(0, size * 1.3)
+    this.ctx.lineTo(size * 0.6, -size * 0.8)
+    this.ctx.lineTo(-size * 0.6, -size * 0.8)
+    this.ctx.closePath()
+    this.ctx.fill()
+
+    this.ctx.fillStyle = "#ff1744"
+    this.ctx.beginPath()
+    this.ctx.arc(0, 0, size * 0.25, 0, Math.PI * 2)
+    this.ctx.fill()
+  }
+
+  private drawMineLayer(size: number, time: number): void {
+    this.ctx.rotate(time)
+    this.ctx.fillStyle = "#795548"
+    this.ctx.fillRect(-size * 0.7, -size * 0.7, size * 1.4, size * 1.4)
+    this.ctx.fillStyle = "#ffeb3b"
+    this.ctx.beginPath()
+    this.ctx.arc(0, 0, size * 0.35, 0, Math.PI * 2)
+    this.ctx.fill()
+  }
+
+  // Cross with a soft pulsing halo
+  private drawHealer(size: number, time: number): void {
+    this.ctx.fillStyle = `rgba(105, 240, 174, ${0.15 + Math.sin(time * 2) * 0.1})`
+    this.ctx.beginPath()
+    this.ctx.arc(0, 0, size * 2, 0, Math.PI * 2)
+    this.ctx.fill()
+
+    this.ctx.fillStyle = "#ffffff"
+    this.ctx.fillRect(-size, -size * 0.3, size * 2, size * 0.6)
+    this.ctx.fillRect(-size * 0.3, -size, size * 0.6, size * 2)
+    this.ctx.fillStyle = "#00c853"
+    this.ctx.fillRect(-size * 0.8, -size * 0.15, size * 1.6, size * 0.3)
+    this.ctx.fillRect(-size * 0.15, -size * 0.8, size * 0.3, size * 1.6)
+  }
+
+  // Thin while it charges, thickening as the sniper gets ready to fire.
+  // angle is from straight down towards +x.
+  private drawLaser(x: number, y: number, angle: number, charge: number): void {
+    const length = Math.hypot(this.canvas.width, this.canvas.height)
+    this.ctx.save()
+    this.ctx.strokeStyle = `rgba(255, 23, 68, ${0.3 + charge * 0.6})`
+    this.ctx.lineWidth = 1 + charge * 2
+    this.ctx.setLineDash(charge < 1 ? [8, 6] : [])
+    this.ctx.beginPath()
+    this.ctx.moveTo(x, y)
+    this.ctx.lineTo(x + Math.sin(angle) * length, y + Math.cos(angle) * length)
+    this.ctx.stroke()
+    this.ctx.restore()
+  }
+
+  // Blinks faster as it runs out
+  private drawMine(x: number, y: number, size: number, lifeRatio: number): void {
+    const blink = Math.sin(Date.now() * 0.001 * (4 + (1 - lifeRatio) * 12)) > 0
+    this.ctx.save()
+    this.ctx.fillStyle = "#37474f"
+    this.ctx.strokeStyle = blink ? "#ff5252" : "#90a4ae"
+    this.ctx.lineWidth = 2
+    this.ctx.beginPath()
+    this.ctx.arc(x, y, size, 0, Math.PI * 2)
+    this.ctx.fill()
+    this.ctx.stroke()
+    for (let i = 0; i < 4; i++) {
+      const angle = (i / 4) * Math.PI * 2
+      this.ctx.beginPath()
+      this.ctx.moveTo(x + Math.cos(angle) * size, y + Math.sin(angle) * size)
+      this.ctx.lineTo(
+        x + Math.cos(angle) * size * 1.5,
+        y + Math.sin(angle) * size * 1.5
+      )
+      this.ctx.stroke()
+    }
+    this.ctx.restore()
+  }
+
+  private drawAiState(x: number, y: number, size: number, state: number): void {
+    this.ctx.save()
+    this.ctx.font = "10px monospace"
+    this.ctx.textAlign = "center"
+    this.ctx.fillStyle = "#00ff88"
+    this.ctx.fillText(AI_STATE_NAMES[state] ?? `${state}`, x, y - size - 6)
+    this.ctx.restore()
+  }
+
+  private drawPauseOverlay(): void {
+    this.ctx.save()
+    this.ctx.fillStyle = "rgba(0, 0, 0, 0.5)"
+    this.ctx.fillRect(0, 0, this.canvas.width, this.canvas.height)
+    this.ctx.fillStyle = "#ffffff"
+    this.ctx.font = "bold 48px Arial"
+    this.ctx.textAlign = "center"
+    this.ctx.fillText("PAUSED", this.canvas.width / 2, this.canvas.height / 2)
+    this.ctx.font = "16px Arial"
+    this.ctx.fillText(
+      "Press P to resume",
+      this.canvas.width / 2,
+      this.canvas.height / 2 + 36
+    )
+    this.ctx.restore()
+  }
+
+  private drawStressOverlay(): void {
+    const fps = this.frameTime > 0 ? 1000 / this.frameTime : 0
+    const bullets = this.channelLayouts.get("player_bullets")
+    const count =
+      bullets && this.gameEngine
+        ? this.gameEngine.get_channel_count(bullets.id)
+        : 0
+
+    this.ctx.save()
+    this.ctx.fillStyle = fps >= 58 ? "#00ff00" : "#ff5252"
+    this.ctx.font = "bold 14px monospace"
+    this.ctx.textAlign = "left"
+    this.ctx.fillText(
+      `STRESS ${count} bullets | ${fps.toFixed(0)} fps | update ${this.updateTime.toFixed(2)} ms`,
+      10,
+      this.canvas.height - 20
+    )
+    this.ctx.restore()
+  }
+
+  // Download the current run as a replay file, for attaching to bug reports
+  public exportReplay(): void {
+    if (!this.gameEngine) return
+
+    const bytes = this.gameEngine.export_replay()
+    const blob = new Blob([bytes], { type: "application/octet-stream" })
+    const url = URL.createObjectURL(blob)
+    const link = document.createElement("a")
+    link.href = url
+    link.download = `replay-${this.gameEngine.get_seed()}.ppmr`
+    link.click()
+    URL.revokeObjectURL(url)
+  }
+
+  // Play back a replay file produced by exportReplay
+  public loadReplay(bytes: Uint8Array): void {
+    try {
+      const replayEngine = GameEngine.from_replay(bytes)
+      replayEngine.resize(this.canvas.width, this.canvas.height)
+      replayEngine.set_auto_pause(AUTO_PAUSE_FRAME_TIME)
+      if (this.gameEngine) {
+        this.gameEngine.free()
+      }
+      this.gameEngine = replayEngine
+      this.stressBullets = 0
+
+      const gameOverElement = document.getElementById("gameOver") as HTMLElement
+      gameOverElement.style.display = "none"
+    } catch (error) {
+      console.error("Failed to load replay:", error)
+    }
+  }
+
   public destroy(): void {
     if (this.animationId) {
       cancelAnimationFrame(this.animationId)
@@ -2042,3 +2740,46 @@ const game = new SpaceShooterGame()
 ;(window as any).restartGame = () => {
   game.restart()
 }
+
+// Replay helpers for QA, callable from the browser console
+;(window as any).exportReplay = () => {
+  game.exportReplay()
+}
+;(window as any).loadReplay = (bytes: Uint8Array) => {
+  game.loadReplay(bytes)
+}
+
+// Tuning without rebuilding the wasm, e.g.
+// setGameConfig('{"black_hole": {"cooldown": 3}}'), setGameConfig(null)
+// for the defaults, and getGameConfig() for every field
+;(window as any).setGameConfig = (config: string | null) => {
+  game.setGameConfig(config)
+}
+;(window as any).getGameConfig = () => game.getGameConfig()
+
+// Difficulty preset for new games: easy, normal, hard or nightmare
+;(window as any).setDifficulty = (difficulty: string) => {
+  game.setDifficulty(difficulty)
+}
+
+// Scripted waves, e.g.
+// loadStage('{"steps": [{"spawn": {"enemy": "basic", "count": 5, "x": 0.2, "spacing": 60}}]}')
+// and loadStage(null) for random spawning
+;(window as any).loadStage = (stage: string | null) => {
+  game.loadStage(stage)
+}
+
+// Slow motion, e.g. setTimeScale(0.25) and setTimeScale(1) for normal speed
+;(window as any).setTimeScale = (scale: number) => {
+  game.setTimeScale(scale)
+}
+
+// Debug labels with each enemy's AI state, showAiStates(false) to hide
+;(window as any).showAiStates = (show: boolean = true) => {
+  game.setShowAiStates(show)
+}
+
+// Collision stress test, e.g. stressTest(5000) and stressTest(0) to stop
+;(window as any).stressTest = (bullets: number) => {
+  game.setStressMode(bullets)
+}
@@ -0,0 +1,193 @@
+use serde::{Deserialize, Serialize};
+
+use crate::config::ConfigError;
+use crate::entities::{EnemyType, Explosion};
+use crate::events::DamageSource;
+use crate::movement::Movement;
+use crate::sim::Simulation;
+
+// Something an enemy type does besides moving and firing, set with
+// enemies.<type>.ability, e.g. { "launch": { "enemy": "fast",
+// "interval": 3 } } or { "heal": { "radius": 120, "rate": 10 } }
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum Ability {
+    // Breaks into count enemies of another type when shot down, fanning
+    // out sideways at up to spread pixels per second
+    Split { into: EnemyType, count: u32, spread: f32 },
+    // Launches an enemy every interval seconds, moving the given way or
+    // else as its type does
+    Launch {
+        enemy: EnemyType,
+        interval: f32,
+        #[serde(default)]
+        movement: Option<Movement>,
+    },
+    // Holds a laser sight on the player for warning seconds, then fires
+    // one shot down it. Replaces the type's normal fire.
+    Snipe { warning: f32, cooldown: f32, bullet_speed: f32 },
+    // Drops a mine every interval seconds that stays put for life seconds
+    // and hurts the player on contact
+    LayMines { interval: f32, size: f32, damage: f32, life: f32 },
+    // Restores rate health a second to other enemies within radius
+    Heal { radius: f32, rate: f32 },
+}
+
+impl Ability {
+    // Checks everything but path numbers, which need the config's paths
+    pub fn validate(&self, field: &str) -> Result<(), ConfigError> {
+        let check = |name: &str, value: f32, ok: bool, reason| {
+            if value.is_finite() && ok {
+                Ok(())
+            } else {
+                Err(ConfigError::Invalid(format!("{}.{}", field, name), reason))
+            }
+        };
+        match *self {
+            Ability::Split { count, spread, .. } => {
+                if count == 0 {
+                    return Err(ConfigError::Invalid(format!("{}.split.count", field), "must be at least 1"));
+                }
+                check("split.spread", spread, spread >= 0.0, "must be zero or more")
+            }
+            Ability::Launch { interval, movement, .. } => {
+                check("launch.interval", interval, interval > 0.0, "must be a positive number")?;
+                match movement {
+                    Some(movement) => movement.validate(&format!("{}.launch.movement", field)),
+                    None => Ok(()),
+                }
+            }
+            Ability::Snipe { warning, cooldown, bullet_speed } => {
+                check("snipe.warning", warning, warning >= 0.0, "must be zero or more")?;
+                check("snipe.cooldown", cooldown, cooldown > 0.0, "must be a positive number")?;
+                check("snipe.bullet_speed", bullet_speed, bullet_speed > 0.0, "must be a positive number")
+            }
+            Ability::LayMines { interval, size, damage, life } => {
+                check("lay_mines.interval", interval, interval > 0.0, "must be a positive number")?;
+                check("lay_mines.size", size, size > 0.0, "must be a positive number")?;
+                check("lay_mines.damage", damage, damage >= 0.0, "must be zero or more")?;
+                check("lay_mines.life", life, life > 0.0, "must be a positive number")
+            }
+            Ability::Heal { radius, rate } => {
+                check("heal.radius", radius, radius > 0.0, "must be a positive number")?;
+                check("heal.rate", rate, rate >= 0.0, "must be zero or more")
+            }
+        }
+    }
+
+    // Seconds from spawning to the first launch, mine or sniper lock
+    pub fn first_use(&self) -> f32 {
+        match *self {
+            Ability::Launch { interval, .. } | Ability::LayMines { interval, .. } => interval,
+            Ability::Snipe { cooldown, .. } => cooldown,
+            Ability::Split { .. } | Ability::Heal { .. } => 0.0,
+        }
+    }
+}
+
+impl Simulation {
+    // Spawns the pieces of every splitter shot down this tick
+    pub(crate) fn split_enemies(&mut self) {
+        for i in 0..self.splits.len() {
+            let (enemy_type, x, y) = self.splits[i];
+            let Some(Ability::Split { into, count, spread }) = self.config.enemy(enemy_type).ability else {
+                continue;
+            };
+            let speed = self.config.enemy(into).speed;
+            for piece in 0..count {
+                let side = if count > 1 { piece as f32 / (count - 1) as f32 * 2.0 - 1.0 } else { 0.0 };
+                let size = self.config.enemy(into).size;
+                if self.spawn_enemy_at(into, x + side * size, y, Movement::Straight).is_none() {
+                    break;
+                }
+                // Spawned enemies go on the end of the pool
+                if let Some(enemy) = self.enemies.last_mut() {
+                    enemy.vx = side * spread;
+                    enemy.vy = speed;
+                }
+            }
+        }
+        self.splits.clear();
+    }
+
+    // Launches the drones queued by carriers this tick
+    pub(crate) fn launch_drones(&mut self) {
+        for i in 0..self.launches.len() {
+            let (carrier, x, y) = self.launches[i];
+            let Some(Ability::Launch { enemy, movement, .. }) = self.config.enemy(carrier).ability else {
+                continue;
+            };
+            let movement = movement
+                .or(self.config.enemy(enemy).movement)
+                .unwrap_or(Movement::Straight);
+            self.spawn_enemy_at(enemy, x, y, movement);
+        }
+        self.launches.clear();
+    }
+
+    // Healers top up everyone else in reach, never past max health
+    pub fn update_healers(&mut self, delta_time: f32) {
+        self.healers.clear();
+        for enemy in &self.enemies {
+            if let Some(Ability::Heal { radius, rate }) = self.config.enemy(enemy.enemy_type).ability {
+                self.healers.push((enemy.id, enemy.x, enemy.y, radius, rate));
+            }
+        }
+        if self.healers.is_empty() {
+            return;
+        }
+
+        // Only enemies near a healer can be healed
+        self.enemy_grid.rebuild(
+            self.width,
+            self.height,
+            self.enemies.iter().map(|enemy| (enemy.x, enemy.y, enemy.size)),
+        );
+        for i in 0..self.healers.len() {
+            let (healer, x, y, radius, rate) = self.healers[i];
+            self.enemy_grid.query(x, y, radius, &mut self.candidates);
+            for &enemy_idx in &self.candidates {
+                let enemy = &mut self.enemies[enemy_idx as usize];
+                let (dx, dy) = (enemy.x - x, enemy.y - y);
+                if enemy.id != healer && dx * dx + dy * dy < radius * radius {
+                    enemy.health = (enemy.health + rate * delta_time).min(enemy.max_health);
+                }
+            }
+        }
+    }
+
+    pub fn update_mines(&mut self, delta_time: f32) {
+        for mine in &mut self.mines {
+            mine.life -= delta_time;
+        }
+    }
+
+    // Mines go off when the player touches them, the shield soaking up
+    // the blast like a bullet
+    pub fn check_mine_collisions(&mut self) {
+        self.dead.clear();
+        for (i, mine) in self.mines.iter().enumerate() {
+            let (dx, dy) = (mine.x - self.player.x, mine.y - self.player.y);
+            let reach = mine.size + self.player.size;
+            if dx * dx + dy * dy < reach * reach {
+                self.dead.push(i);
+            }
+        }
+
+        for i in 0..self.dead.len() {
+            let mine = &self.mines[self.dead[i]];
+            let (id, x, y, size, damage) = (mine.id, mine.x, mine.y, mine.size, mine.damage);
+            self.explosions.push(Explosion {
+                x,
+                y,
+                size: size * 3.0,
+                life: 0.5,
+                max_life: 0.5,
+            });
+            self.hit_player(id, DamageSource::Mine, x, y, damage, 1);
+        }
+
+        self.dead.reverse();
+        self.mines.remove_descending(&self.dead, |mine| self.ids.free(mine.id));
+    }
+}
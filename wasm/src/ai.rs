@@ -0,0 +1,241 @@
+use serde::{Deserialize, Serialize};
+
+use crate::config::ConfigError;
+use crate::entities::Enemy;
+use crate::movement::Surroundings;
+
+// What an enemy with AI is doing. Enemy types without AI stay in Attack.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AiState {
+    // Following its movement in from the edge, holding fire
+    #[default]
+    Enter,
+    // Following its movement and firing
+    Attack,
+    // Sidestepping a player bullet, holding fire
+    Evade,
+    // Backing away from a player who came too close, still firing
+    Retreat,
+    // Running from the player for good once badly hurt
+    Flee,
+}
+
+impl AiState {
+    pub const ALL: [AiState; 5] = [
+        AiState::Enter,
+        AiState::Attack,
+        AiState::Evade,
+        AiState::Retreat,
+        AiState::Flee,
+    ];
+
+    // Stable id for render channels and saves
+    pub fn id(self) -> u8 {
+        self as u8
+    }
+
+    pub fn from_id(id: u8) -> Option<AiState> {
+        AiState::ALL.get(id as usize).copied()
+    }
+}
+
+// A type's behaviour as a state machine. Every reaction is optional, and
+// an enemy checks them in order of urgency each tick: flee, evade,
+// retreat, then attack once it has entered. e.g.
+//
+// { "enter_y": 120, "evade": { "range": 150, "speed": 120 },
+//   "flee": { "health": 0.3, "speed": 150 } }
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AiConfig {
+    // Starts attacking once this far down the screen
+    pub enter_y: f32,
+    #[serde(default)]
+    pub evade: Option<Reaction>,
+    #[serde(default)]
+    pub retreat: Option<Reaction>,
+    #[serde(default)]
+    pub flee: Option<FleeConfig>,
+}
+
+// Evade: a player bullet is heading up at it from within range pixels.
+// Retreat: the player is within range pixels, until they are a quarter
+// further off again.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Reaction {
+    pub range: f32,
+    // Pixels per second on top of its movement
+    pub speed: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FleeConfig {
+    // Share of max health it flees at
+    pub health: f32,
+    // Pixels per second straight away from the player
+    pub speed: f32,
+}
+
+// What the enemy does this tick
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Orders {
+    // Follow its movement, otherwise its velocity is left alone
+    pub steer: bool,
+    pub fire: bool,
+    // Velocity added for this tick only, so the movement carries on
+    // unchanged afterwards
+    pub push_x: f32,
+    pub push_y: f32,
+}
+
+impl Orders {
+    pub const ATTACK: Orders = Orders {
+        steer: true,
+        fire: true,
+        push_x: 0.0,
+        push_y: 0.0,
+    };
+}
+
+impl AiConfig {
+    pub fn validate(&self, field: &str) -> Result<(), ConfigError> {
+        if !self.enter_y.is_finite() {
+            return Err(ConfigError::Invalid(format!("{}.enter_y", field), "must be a number"));
+        }
+        for (name, reaction) in [("evade", self.evade), ("retreat", self.retreat)] {
+            let Some(reaction) = reaction else {
+                continue;
+            };
+            if !(reaction.range.is_finite() && reaction.range > 0.0) {
+                return Err(ConfigError::Invalid(format!("{}.{}.range", field, name), "must be a positive number"));
+            }
+            if !(reaction.speed.is_finite() && reaction.speed >= 0.0) {
+                return Err(ConfigError::Invalid(format!("{}.{}.speed", field, name), "must be zero or more"));
+            }
+        }
+        if let Some(flee) = self.flee {
+            if !(0.0..=1.0).contains(&flee.health) {
+                return Err(ConfigError::Invalid(format!("{}.flee.health", field), "must be between 0 and 1"));
+            }
+            if !(flee.speed.is_finite() && flee.speed > 0.0) {
+                return Err(ConfigError::Invalid(format!("{}.flee.speed", field), "must be a positive number"));
+            }
+        }
+        Ok(())
+    }
+
+    // Moves the enemy to its state for this tick and says what to do in it.
+    // threat is the x of the bullet to dodge, from threat() when the type
+    // evades.
+    pub fn think(&self, enemy: &mut Enemy, around: &Surroundings, threat: Option<f32>, delta_time: f32) -> Orders {
+        let state = self.next_state(enemy, around, threat);
+        if state != enemy.ai {
+            enemy.ai = state;
+            enemy.ai_time = 0.0;
+        }
+        enemy.ai_time += delta_time;
+
+        let (dx, dy) = (enemy.x - around.player_x, enemy.y - around.player_y);
+        let distance = (dx * dx + dy * dy).sqrt().max(1.0);
+        match state {
+            AiState::Enter => Orders {
+                fire: false,
+                ..Orders::ATTACK
+            },
+            AiState::Attack => Orders::ATTACK,
+            AiState::Evade => {
+                let speed = self.evade.map_or(0.0, |evade| evade.speed);
+                let away = match threat {
+                    Some(bullet_x) if bullet_x < enemy.x => 1.0,
+                    _ => -1.0,
+                };
+                // Dodge the other way when against the edge
+                let side = if (away < 0.0 && enemy.x < enemy.size * 2.0)
+                    || (away > 0.0 && enemy.x > around.width - enemy.size * 2.0)
+                {
+                    -away
+                } else {
+                    away
+                };
+                Orders {
+                    fire: false,
+                    push_x: side * speed,
+                    ..Orders::ATTACK
+                }
+            }
+            AiState::Retreat => {
+                let speed = self.retreat.map_or(0.0, |retreat| retreat.speed);
+                Orders {
+                    push_x: dx / distance * speed,
+                    push_y: dy / distance * speed,
+                    ..Orders::ATTACK
+                }
+            }
+            AiState::Flee => {
+                let speed = self.flee.map_or(0.0, |flee| flee.speed);
+                enemy.vx = dx / distance * speed;
+                enemy.vy = dy / distance * speed;
+                Orders {
+                    steer: false,
+                    fire: false,
+                    push_x: 0.0,
+                    push_y: 0.0,
+                }
+            }
+        }
+    }
+
+    fn next_state(&self, enemy: &Enemy, around: &Surroundings, threat: Option<f32>) -> AiState {
+        if enemy.ai == AiState::Flee {
+            return AiState::Flee;
+        }
+        if let Some(flee) = self.flee {
+            if enemy.health <= flee.health * enemy.max_health {
+                return AiState::Flee;
+            }
+        }
+        if enemy.ai == AiState::Enter && enemy.y < self.enter_y {
+            return AiState::Enter;
+        }
+        if self.evade.is_some() && threat.is_some() {
+            return AiState::Evade;
+        }
+        if let Some(retreat) = self.retreat {
+            let (dx, dy) = (enemy.x - around.player_x, enemy.y - around.player_y);
+            let range = if enemy.ai == AiState::Retreat {
+                retreat.range * 1.25
+            } else {
+                retreat.range
+            };
+            if dx * dx + dy * dy < range * range {
+                return AiState::Retreat;
+            }
+        }
+        AiState::Attack
+    }
+}
+
+// x of the nearest player bullet heading up into the enemy from below,
+// within range pixels. Only bullets the grid puts near the strip below
+// the enemy are looked at.
+pub fn threat(enemy: &Enemy, around: &Surroundings, range: f32, candidates: &mut Vec<u32>) -> Option<f32> {
+    let half = range / 2.0;
+    around
+        .bullet_grid
+        .query(enemy.x, enemy.y + half, half.max(enemy.size), candidates);
+    let bullets = around.bullets;
+    candidates
+        .iter()
+        .map(|&i| i as usize)
+        .filter(|&i| {
+            let below = bullets.y[i] - enemy.y;
+            bullets.vy[i] < 0.0
+                && below > 0.0
+                && below < range
+                && (bullets.x[i] - enemy.x).abs() < enemy.size + bullets.size[i]
+        })
+        .min_by(|&a, &b| bullets.y[a].total_cmp(&bullets.y[b]))
+        .map(|i| bullets.x[i])
+}
@@ -0,0 +1,308 @@
+use serde::{Deserialize, Serialize};
+
+use crate::config::BossPhaseConfig;
+use crate::entities::Explosion;
+use crate::events::{DamageSource, GameEvent};
+use crate::ids::EntityId;
+use crate::pattern::{Emitter, Gun};
+use crate::sim::Simulation;
+
+// How a boss moves during a phase, once it has flown in
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum BossMovement {
+    // Holds still
+    #[default]
+    Hover,
+    // Side to side across the screen
+    Sweep { speed: f32 },
+    // Figure of eight around where the phase started, width and height
+    // are how far it swings each way
+    Weave { speed: f32, width: f32, height: f32 },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Boss {
+    pub id: EntityId,
+    pub x: f32,
+    pub y: f32,
+    pub prev_x: f32,
+    pub prev_y: f32,
+    pub vx: f32,
+    pub vy: f32,
+    // The core, the boss is beaten when its health runs out
+    pub size: f32,
+    pub health: f32,
+    pub max_health: f32,
+    // Index into config.boss.phases
+    pub phase: usize,
+    // Seconds since the phase started
+    pub phase_time: f32,
+    // Fires the phase's pattern from the core
+    pub emitter: Emitter,
+    // Flying in from the top, no attacks yet
+    pub entering: bool,
+    // Destroyed parts stay in the list with no health, so part indices
+    // don't change during the fight
+    pub parts: Vec<BossPart>,
+}
+
+// A destructible hit zone fixed to the boss, with its own health and
+// gun. Destroyed parts stop firing.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BossPart {
+    // Offset from the boss centre
+    pub offset_x: f32,
+    pub offset_y: f32,
+    pub size: f32,
+    pub health: f32,
+    pub max_health: f32,
+    pub emitter: Emitter,
+}
+
+impl BossPart {
+    pub fn is_destroyed(&self) -> bool {
+        self.health <= 0.0
+    }
+}
+
+impl Boss {
+    pub fn part_position(&self, part: &BossPart) -> (f32, f32) {
+        (self.x + part.offset_x, self.y + part.offset_y)
+    }
+
+    // Half the width of the core and its parts
+    pub fn half_width(&self) -> f32 {
+        self.parts
+            .iter()
+            .map(|part| part.offset_x.abs() + part.size)
+            .fold(self.size, f32::max)
+    }
+
+    // Phase for the current health: the last one whose threshold it has
+    // fallen to. Phases never go back.
+    fn phase_for_health(&self, phases: &[BossPhaseConfig]) -> usize {
+        let ratio = self.health / self.max_health;
+        let phase = phases.iter().rposition(|phase| ratio <= phase.health).unwrap_or(0);
+        phase.max(self.phase)
+    }
+
+    // Absolute health the next phase starts at, 0 in the last phase
+    pub fn next_phase_health(&self, phases: &[BossPhaseConfig]) -> f32 {
+        phases.get(self.phase + 1).map_or(0.0, |phase| phase.health * self.max_health)
+    }
+}
+
+impl Simulation {
+    // Boss for the current level, flying in above the middle of the
+    // screen. Does nothing while one is already fighting.
+    pub fn spawn_boss(&mut self) -> Option<EntityId> {
+        if self.boss.is_some() {
+            return None;
+        }
+
+        let config = &self.config.boss;
+        let fire_rate = self.config.difficulty.fire_rate;
+        let health = config.health
+            * (1.0 + config.health_growth * self.bosses_defeated as f32)
+            * self.config.difficulty.enemy_health;
+        let parts = config
+            .parts
+            .iter()
+            .map(|part| BossPart {
+                offset_x: part.x,
+                offset_y: part.y,
+                size: part.size,
+                health: part.health * self.config.difficulty.enemy_health,
+                max_health: part.health * self.config.difficulty.enemy_health,
+                emitter: Emitter::new(&part.pattern, fire_rate),
+            })
+            .collect();
+
+        let id = self.ids.alloc();
+        let (x, y) = (self.width / 2.0, -config.size * 2.0);
+        self.boss = Some(Boss {
+            id,
+            x,
+            y,
+            prev_x: x,
+            prev_y: y,
+            vx: 0.0,
+            vy: config.entry_speed,
+            size: config.size,
+            health,
+            max_health: health,
+            phase: 0,
+            phase_time: 0.0,
+            emitter: Emitter::new(&config.phases[0].pattern, fire_rate),
+            entering: true,
+            parts,
+        });
+        self.events.push(GameEvent::BossSpawned { id, x, y });
+        Some(id)
+    }
+
+    pub fn update_boss(&mut self, delta_time: f32) {
+        let Some(boss) = &mut self.boss else {
+            return;
+        };
+        let config = &self.config.boss;
+        let phase = &config.phases[boss.phase];
+
+        if boss.entering {
+            boss.y += config.entry_speed * delta_time;
+            if boss.y >= config.entry_y {
+                boss.y = config.entry_y;
+                boss.vy = 0.0;
+                boss.entering = false;
+            }
+            return;
+        }
+
+        boss.phase_time += delta_time;
+        match phase.movement {
+            BossMovement::Hover => {
+                boss.vx = 0.0;
+                boss.vy = 0.0;
+            }
+            BossMovement::Sweep { speed } => {
+                boss.vx = if boss.vx < 0.0 { -speed } else { speed };
+                boss.vy = 0.0;
+            }
+            // Velocity of x = sin(t), y = sin(2t) scaled to the swing
+            BossMovement::Weave { speed, width, height } => {
+                let t = boss.phase_time * speed;
+                boss.vx = t.cos() * speed * width;
+                boss.vy = (2.0 * t).cos() * 2.0 * speed * height;
+            }
+        }
+        boss.x += boss.vx * delta_time;
+        boss.y += boss.vy * delta_time;
+
+        // Turn round at the edges, parts included
+        let margin = boss.half_width();
+        if boss.x < margin {
+            boss.x = margin;
+            boss.vx = boss.vx.abs();
+        } else if boss.x > self.width - margin {
+            boss.x = self.width - margin;
+            boss.vx = -boss.vx.abs();
+        }
+        boss.y = boss.y.clamp(boss.size.min(self.height / 2.0), self.height / 2.0);
+
+        let fire_rate = self.config.difficulty.fire_rate;
+        let mut gun = Gun::at_player(
+            &self.player,
+            &self.config,
+            self.level,
+            &mut self.enemy_bullets,
+            &mut self.ids,
+            &mut self.directions,
+            &mut self.rng,
+        );
+        if boss.emitter.update(&phase.pattern, delta_time, fire_rate) {
+            gun.fire(&phase.pattern, boss.emitter.rotation, boss.x, boss.y + boss.size);
+        }
+        for (part, config) in boss.parts.iter_mut().zip(&config.parts) {
+            if part.is_destroyed() || !part.emitter.update(&config.pattern, delta_time, fire_rate) {
+                continue;
+            }
+            let (x, y) = (boss.x + part.offset_x, boss.y + part.offset_y + part.size);
+            gun.fire(&config.pattern, part.emitter.rotation, x, y);
+        }
+    }
+
+    // Player bullets against the boss hit zones, parts first since they
+    // sit in front of the core, and the core ramming the player
+    pub fn check_boss_collisions(&mut self) {
+        let Some(boss) = &mut self.boss else {
+            return;
+        };
+
+        self.dead.clear();
+        for (bullet_idx, bullet) in self.bullets.iter().enumerate() {
+            let overlaps = |x: f32, y: f32, size: f32| {
+                let (dx, dy) = (bullet.x - x, bullet.y - y);
+                let reach = bullet.size + size;
+                dx * dx + dy * dy < reach * reach
+            };
+
+            let (boss_x, boss_y) = (boss.x, boss.y);
+            let part = boss.parts.iter_mut().enumerate().find(|(_, part)| {
+                !part.is_destroyed() && overlaps(boss_x + part.offset_x, boss_y + part.offset_y, part.size)
+            });
+            if let Some((index, part)) = part {
+                self.dead.push(bullet_idx);
+                part.health -= bullet.damage;
+                if part.is_destroyed() {
+                    let (x, y) = (boss_x + part.offset_x, boss_y + part.offset_y);
+                    let score = self.config.boss.parts.get(index).map_or(0, |part| part.score);
+                    self.score += score;
+                    self.events.push(GameEvent::BossPartDestroyed {
+                        id: boss.id,
+                        part: index as u32,
+                        x,
+                        y,
+                        score,
+                    });
+                    self.explosions.push(Explosion {
+                        x,
+                        y,
+                        size: part.size * 2.0,
+                        life: 1.0,
+                        max_life: 1.0,
+                    });
+                }
+            } else if overlaps(boss.x, boss.y, boss.size) {
+                self.dead.push(bullet_idx);
+                boss.health -= bullet.damage;
+            }
+        }
+        self.dead.reverse();
+        self.bullets.remove_descending(&self.dead, |bullet| self.ids.free(bullet.id));
+
+        let phases = &self.config.boss.phases;
+        let phase = boss.phase_for_health(phases);
+        if phase != boss.phase {
+            boss.phase = phase;
+            boss.phase_time = 0.0;
+            boss.emitter = Emitter::new(&phases[phase].pattern, self.config.difficulty.fire_rate);
+            self.events.push(GameEvent::BossPhaseChanged {
+                id: boss.id,
+                phase: phase as u32,
+                x: boss.x,
+                y: boss.y,
+            });
+        }
+
+        if boss.health <= 0.0 {
+            let score = self.config.boss.score;
+            self.score += score;
+            self.events.push(GameEvent::BossDefeated {
+                id: boss.id,
+                x: boss.x,
+                y: boss.y,
+                score,
+            });
+            self.explosions.push(Explosion {
+                x: boss.x,
+                y: boss.y,
+                size: boss.half_width() * 2.0,
+                life: 2.0,
+                max_life: 2.0,
+            });
+            self.ids.free(boss.id);
+            self.boss = None;
+            self.bosses_defeated += 1;
+            return;
+        }
+
+        let (dx, dy) = (boss.x - self.player.x, boss.y - self.player.y);
+        let reach = boss.size + self.player.size;
+        if dx * dx + dy * dy < reach * reach {
+            let (id, x, y) = (boss.id, boss.x, boss.y);
+            let damage = self.config.boss.contact_damage * self.config.difficulty.damage;
+            self.hit_player(id, DamageSource::EnemyCollision, x, y, damage, 1);
+        }
+    }
+}
@@ -0,0 +1,260 @@
+use crate::entities::Bullet;
+use crate::ids::EntityId;
+use crate::pool::Storage;
+
+// Struct-of-arrays bullet storage. Each field is its own contiguous
+// column, so movement and circle tests run over plain f32 slices, four
+// lanes at a time with the simd feature. Same fixed capacity and
+// swap-remove semantics as Pool.
+#[derive(Clone)]
+pub struct BulletPool {
+    pub x: Vec<f32>,
+    pub y: Vec<f32>,
+    pub prev_x: Vec<f32>,
+    pub prev_y: Vec<f32>,
+    pub vx: Vec<f32>,
+    pub vy: Vec<f32>,
+    pub size: Vec<f32>,
+    pub damage: Vec<f32>,
+    pub id: Vec<EntityId>,
+    limit: usize,
+}
+
+impl BulletPool {
+    pub fn new(limit: usize) -> BulletPool {
+        BulletPool {
+            x: Vec::with_capacity(limit),
+            y: Vec::with_capacity(limit),
+            prev_x: Vec::with_capacity(limit),
+            prev_y: Vec::with_capacity(limit),
+            vx: Vec::with_capacity(limit),
+            vy: Vec::with_capacity(limit),
+            size: Vec::with_capacity(limit),
+            damage: Vec::with_capacity(limit),
+            id: Vec::with_capacity(limit),
+            limit,
+        }
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    pub fn len(&self) -> usize {
+        self.x.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.x.is_empty()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len() >= self.limit
+    }
+
+    // Returns false and drops the bullet when the pool is full
+    pub fn push(&mut self, bullet: Bullet) -> bool {
+        if self.is_full() {
+            return false;
+        }
+        self.x.push(bullet.x);
+        self.y.push(bullet.y);
+        self.prev_x.push(bullet.prev_x);
+        self.prev_y.push(bullet.prev_y);
+        self.vx.push(bullet.vx);
+        self.vy.push(bullet.vy);
+        self.size.push(bullet.size);
+        self.damage.push(bullet.damage);
+        self.id.push(bullet.id);
+        true
+    }
+
+    // Gather one bullet back into a struct
+    pub fn get(&self, index: usize) -> Bullet {
+        Bullet {
+            id: self.id[index],
+            x: self.x[index],
+            y: self.y[index],
+            prev_x: self.prev_x[index],
+            prev_y: self.prev_y[index],
+            vx: self.vx[index],
+            vy: self.vy[index],
+            size: self.size[index],
+            damage: self.damage[index],
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Bullet> + '_ {
+        (0..self.len()).map(move |i| self.get(i))
+    }
+
+    pub fn swap_remove(&mut self, index: usize) -> Bullet {
+        let bullet = self.get(index);
+        self.x.swap_remove(index);
+        self.y.swap_remove(index);
+        self.prev_x.swap_remove(index);
+        self.prev_y.swap_remove(index);
+        self.vx.swap_remove(index);
+        self.vy.swap_remove(index);
+        self.size.swap_remove(index);
+        self.damage.swap_remove(index);
+        self.id.swap_remove(index);
+        bullet
+    }
+
+    // Indices must be in descending order, see Pool::remove_descending
+    pub fn remove_descending(&mut self, indices: &[usize], mut removed: impl FnMut(Bullet)) {
+        for &index in indices {
+            if index < self.len() {
+                removed(self.swap_remove(index));
+            }
+        }
+    }
+
+    // Keep bullets with min_y < y < max_y, the rest go to removed
+    pub fn retain_between(&mut self, min_y: f32, max_y: f32, mut removed: impl FnMut(Bullet)) {
+        let mut i = 0;
+        while i < self.len() {
+            if self.y[i] > min_y && self.y[i] < max_y {
+                i += 1;
+            } else {
+                removed(self.swap_remove(i));
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.x.clear();
+        self.y.clear();
+        self.prev_x.clear();
+        self.prev_y.clear();
+        self.vx.clear();
+        self.vy.clear();
+        self.size.clear();
+        self.damage.clear();
+        self.id.clear();
+    }
+
+    pub fn store_previous_positions(&mut self) {
+        self.prev_x.copy_from_slice(&self.x);
+        self.prev_y.copy_from_slice(&self.y);
+    }
+
+    pub fn integrate(&mut self, delta_time: f32) {
+        kernels::integrate(&mut self.x, &self.vx, delta_time);
+        kernels::integrate(&mut self.y, &self.vy, delta_time);
+    }
+
+    // Indices of bullets overlapping the circle, ascending, into out
+    pub fn overlapping(&self, x: f32, y: f32, radius: f32, out: &mut Vec<usize>) {
+        out.clear();
+        kernels::overlapping(&self.x, &self.y, &self.size, x, y, radius, out);
+    }
+}
+
+impl Storage<Bullet> for BulletPool {
+    fn is_full(&self) -> bool {
+        BulletPool::is_full(self)
+    }
+
+    fn push(&mut self, bullet: Bullet) -> bool {
+        BulletPool::push(self, bullet)
+    }
+}
+
+// Both kernels do the same f32 operations in the same order, so SIMD and
+// scalar builds produce bit-identical games and share replays. Both are
+// public so tests can hold them to that on wasm32.
+#[cfg(all(feature = "simd", target_arch = "wasm32", not(target_feature = "simd128")))]
+compile_error!("the simd feature needs RUSTFLAGS=\"-C target-feature=+simd128\" on wasm32");
+
+#[cfg(all(feature = "simd", target_arch = "wasm32", target_feature = "simd128"))]
+pub mod simd {
+    use core::arch::wasm32::*;
+
+    pub fn integrate(position: &mut [f32], velocity: &[f32], delta_time: f32) {
+        let len = position.len().min(velocity.len());
+        let lanes = len - len % 4;
+        let dt = f32x4_splat(delta_time);
+
+        for i in (0..lanes).step_by(4) {
+            // Safety: i + 4 <= lanes <= len of both slices, and v128 loads
+            // and stores don't need alignment
+            unsafe {
+                let p = position.as_mut_ptr().add(i) as *mut v128;
+                let v = v128_load(velocity.as_ptr().add(i) as *const v128);
+                v128_store(p, f32x4_add(v128_load(p), f32x4_mul(v, dt)));
+            }
+        }
+
+        for (p, v) in position[lanes..len].iter_mut().zip(&velocity[lanes..len]) {
+            *p += v * delta_time;
+        }
+    }
+
+    pub fn overlapping(xs: &[f32], ys: &[f32], sizes: &[f32], x: f32, y: f32, radius: f32, out: &mut Vec<usize>) {
+        let len = xs.len().min(ys.len()).min(sizes.len());
+        let lanes = len - len % 4;
+        let cx = f32x4_splat(x);
+        let cy = f32x4_splat(y);
+        let r = f32x4_splat(radius);
+
+        for i in (0..lanes).step_by(4) {
+            // Safety: i + 4 <= lanes <= len of every slice
+            let hits = unsafe {
+                let dx = f32x4_sub(v128_load(xs.as_ptr().add(i) as *const v128), cx);
+                let dy = f32x4_sub(v128_load(ys.as_ptr().add(i) as *const v128), cy);
+                let reach = f32x4_add(v128_load(sizes.as_ptr().add(i) as *const v128), r);
+                let distance_sq = f32x4_add(f32x4_mul(dx, dx), f32x4_mul(dy, dy));
+                i32x4_bitmask(f32x4_lt(distance_sq, f32x4_mul(reach, reach)))
+            };
+            for lane in 0..4 {
+                if hits & (1 << lane) != 0 {
+                    out.push(i + lane);
+                }
+            }
+        }
+
+        super::scalar::overlapping_from(lanes, xs, ys, sizes, x, y, radius, out);
+    }
+}
+
+#[cfg(all(feature = "simd", target_arch = "wasm32", target_feature = "simd128"))]
+use simd as kernels;
+#[cfg(not(all(feature = "simd", target_arch = "wasm32", target_feature = "simd128")))]
+use scalar as kernels;
+
+pub mod scalar {
+    pub fn integrate(position: &mut [f32], velocity: &[f32], delta_time: f32) {
+        for (p, v) in position.iter_mut().zip(velocity) {
+            *p += v * delta_time;
+        }
+    }
+
+    pub fn overlapping(xs: &[f32], ys: &[f32], sizes: &[f32], x: f32, y: f32, radius: f32, out: &mut Vec<usize>) {
+        overlapping_from(0, xs, ys, sizes, x, y, radius, out);
+    }
+
+    // Also finishes the tail the SIMD kernel leaves over
+    #[allow(clippy::too_many_arguments)]
+    pub fn overlapping_from(
+        start: usize,
+        xs: &[f32],
+        ys: &[f32],
+        sizes: &[f32],
+        x: f32,
+        y: f32,
+        radius: f32,
+        out: &mut Vec<usize>,
+    ) {
+        let len = xs.len().min(ys.len()).min(sizes.len());
+        for i in start..len {
+            let dx = xs[i] - x;
+            let dy = ys[i] - y;
+            let reach = sizes[i] + radius;
+            if dx * dx + dy * dy < reach * reach {
+                out.push(i);
+            }
+        }
+    }
+}
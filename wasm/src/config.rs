@@ -0,0 +1,967 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::ability::Ability;
+use crate::ai::AiConfig;
+use crate::boss::BossMovement;
+use crate::difficulty::Difficulty;
+use crate::entities::EnemyType;
+use crate::formation::Squadron;
+use crate::movement::{Movement, Path};
+use crate::pattern::{Aim, Pattern, Shape};
+use crate::sim::MAX_BOSS_PARTS;
+use crate::stage::{SpawnGroup, Stage, Step};
+
+// Gameplay tuning. Defaults match the original hard-coded values, except
+// that the newer features are on: fast enemies aim at the player, tanks
+// lead their shots, a squadron comes every 30 seconds, 15% of enemies are
+// special and a boss arrives every 5 levels. Config files are merged over
+// the defaults, so they only need the fields they change. Unknown fields
+// are rejected to catch typos.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GameConfig {
+    pub player: PlayerConfig,
+    pub weapon: WeaponConfig,
+    pub enemies: EnemiesConfig,
+    pub enemy_fire: EnemyFireConfig,
+    pub spawn: SpawnConfig,
+    pub power_ups: PowerUpConfig,
+    pub shield: ShieldConfig,
+    pub black_hole: BlackHoleConfig,
+    pub boss: BossConfig,
+    pub difficulty: DifficultyConfig,
+    pub dynamic_difficulty: DynamicDifficultyConfig,
+    // Curves for Movement::Path, by position in the list
+    pub paths: Vec<Path>,
+    // Squadron sent in every spawn.squadron_interval seconds during
+    // random spawning
+    pub squadron: Squadron,
+    // Scripted stage to play instead of random enemy spawning
+    pub stage: Option<Stage>,
+    // Score needed per level, level n ends at n * points_per_level
+    pub points_per_level: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PlayerConfig {
+    pub speed: f32,
+    pub max_health: f32,
+    pub size: f32,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WeaponConfig {
+    // Seconds between shots
+    pub cooldown: f32,
+    pub bullet_speed: f32,
+    pub bullet_size: f32,
+    // Bullet damage is damage_per_level * power level
+    pub damage_per_level: f32,
+    pub max_level: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EnemiesConfig {
+    pub basic: EnemyConfig,
+    pub fast: EnemyConfig,
+    pub tank: EnemyConfig,
+    pub splitter: EnemyConfig,
+    pub carrier: EnemyConfig,
+    pub sniper: EnemyConfig,
+    pub mine_layer: EnemyConfig,
+    pub healer: EnemyConfig,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EnemyConfig {
+    pub size: f32,
+    pub health: f32,
+    pub speed: f32,
+    pub score: u32,
+    // Damage when it rams an unshielded player
+    pub contact_damage: f32,
+    // How enemy_fire's single shot is aimed
+    pub aim: Aim,
+    // Overrides the spawner's movement, drift for random spawns and
+    // straight down in stages. Stage groups can override it in turn.
+    pub movement: Option<Movement>,
+    // Without AI an enemy just follows its movement and fires
+    pub ai: Option<AiConfig>,
+    // Fires this pattern instead of enemy_fire's single shot
+    pub pattern: Option<Pattern>,
+    pub ability: Option<Ability>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EnemyFireConfig {
+    // Chance per tick that an enemy off cooldown fires
+    pub chance: f32,
+    pub cooldown: f32,
+    pub bullet_speed: f32,
+    pub bullet_size: f32,
+    pub bullet_damage: f32,
+    // Degrees aimed single shots can miss by either way at level 0.
+    // Every spread, patterns included, is divided by
+    // 1 + level * spread_level_factor as levels go up.
+    pub spread: f32,
+    pub spread_level_factor: f32,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SpawnConfig {
+    // Seconds between enemies at level 0, divided by
+    // 1 + level * interval_level_factor as levels go up
+    pub enemy_interval: f32,
+    pub interval_level_factor: f32,
+    pub tank_chance: f32,
+    // Chance for a non-tank enemy to be fast
+    pub fast_chance: f32,
+    // Chance for an enemy to be one of the special archetypes instead,
+    // each as likely as the others
+    pub special_chance: f32,
+    // Seconds between random squadrons, 0 for none. A squadron still in
+    // play holds off the next.
+    pub squadron_interval: f32,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PowerUpConfig {
+    // Seconds between power-ups
+    pub interval: f32,
+    pub speed: f32,
+    pub size: f32,
+    pub health_chance: f32,
+    // Chance for a non-health power-up to be a weapon upgrade
+    pub weapon_chance: f32,
+    pub heal: f32,
+    pub max_health_bonus: f32,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ShieldConfig {
+    // Seconds a shield lasts after pickup
+    pub duration: f32,
+    pub max_level: u32,
+    // Shield levels used up blocking a ramming tank
+    pub tank_cost: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BlackHoleConfig {
+    pub size: f32,
+    pub duration: f32,
+    pub pull_radius: f32,
+    pub pull_force: f32,
+    // How far in front of the player it opens
+    pub distance: f32,
+    pub cooldown: f32,
+    pub score_per_enemy: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BossConfig {
+    // A boss flies in on reaching every nth level, 0 turns bosses off
+    pub every_levels: u32,
+    pub size: f32,
+    pub health: f32,
+    // Extra health for each boss already beaten, as a share of health
+    pub health_growth: f32,
+    pub score: u32,
+    pub contact_damage: f32,
+    // Speed flying in, and the height it stops at
+    pub entry_speed: f32,
+    pub entry_y: f32,
+    pub parts: Vec<BossPartConfig>,
+    // In order, the first one starts the fight
+    pub phases: Vec<BossPhaseConfig>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BossPartConfig {
+    // Offset from the boss centre
+    pub x: f32,
+    pub y: f32,
+    pub size: f32,
+    pub health: f32,
+    pub score: u32,
+    pub pattern: Pattern,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BossPhaseConfig {
+    // Starts once the boss is down to this share of its health, 1 for
+    // the first phase
+    pub health: f32,
+    pub movement: BossMovement,
+    // Fired from the core
+    pub pattern: Pattern,
+}
+
+// Multipliers over the enemy stats above, set by the Difficulty presets
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DifficultyConfig {
+    pub enemy_health: f32,
+    pub bullet_speed: f32,
+    // Scales both the chance to fire and how soon an enemy can fire again
+    pub fire_rate: f32,
+    // Enemy bullet and ramming damage
+    pub damage: f32,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DynamicDifficultyConfig {
+    pub enabled: bool,
+    // Seconds of play the adjustment looks back over
+    pub window: f32,
+    pub min_factor: f32,
+    pub max_factor: f32,
+    // Factor change per second at a pressure of 1
+    pub adjust_rate: f32,
+    // Average health share and share of spawned enemies killed that
+    // leave the factor where it is
+    pub target_health: f32,
+    pub target_kill_share: f32,
+}
+
+impl Default for GameConfig {
+    fn default() -> GameConfig {
+        GameConfig {
+            player: PlayerConfig::default(),
+            weapon: WeaponConfig::default(),
+            enemies: EnemiesConfig::default(),
+            enemy_fire: EnemyFireConfig::default(),
+            spawn: SpawnConfig::default(),
+            power_ups: PowerUpConfig::default(),
+            shield: ShieldConfig::default(),
+            black_hole: BlackHoleConfig::default(),
+            boss: BossConfig::default(),
+            difficulty: DifficultyConfig::default(),
+            dynamic_difficulty: DynamicDifficultyConfig::default(),
+            paths: default_paths(),
+            squadron: Squadron {
+                enemy: EnemyType::Basic,
+                rows: 2,
+                columns: 5,
+                spacing: 50.0,
+                x: 0.5,
+                y: 80.0,
+                entry_x: 0.1,
+                path: Some(0),
+                interval: 0.25,
+                join_speed: 200.0,
+                dive_interval: 3.0,
+                dive_speed: 250.0,
+                sway: 30.0,
+            },
+            stage: None,
+            points_per_level: 1000,
+        }
+    }
+}
+
+// Two swoops across the screen, one each way: down, round and out the
+// far side
+fn default_paths() -> Vec<Path> {
+    let swoop = |side: f32| Path {
+        points: [[0.0, 0.0], [0.0, 250.0], [100.0, 350.0], [300.0, 350.0], [500.0, 350.0], [600.0, 250.0], [700.0, 150.0]]
+            .map(|[x, y]| [x * side, y])
+            .to_vec(),
+        duration: 5.0,
+    };
+    vec![swoop(1.0), swoop(-1.0)]
+}
+
+impl Default for PlayerConfig {
+    fn default() -> PlayerConfig {
+        PlayerConfig {
+            speed: 200.0,
+            max_health: 100.0,
+            size: 20.0,
+        }
+    }
+}
+
+impl Default for WeaponConfig {
+    fn default() -> WeaponConfig {
+        WeaponConfig {
+            cooldown: 0.2,
+            bullet_speed: 300.0,
+            bullet_size: 8.0,
+            damage_per_level: 25.0,
+            max_level: 3,
+        }
+    }
+}
+
+impl Default for EnemiesConfig {
+    fn default() -> EnemiesConfig {
+        EnemiesConfig {
+            basic: EnemyConfig {
+                size: 15.0,
+                health: 20.0,
+                speed: 50.0,
+                score: 100,
+                contact_damage: 20.0,
+                aim: Aim::Down,
+                movement: None,
+                ai: None,
+                pattern: None,
+                ability: None,
+            },
+            fast: EnemyConfig {
+                size: 12.0,
+                health: 15.0,
+                speed: 100.0,
+                score: 150,
+                contact_damage: 20.0,
+                aim: Aim::Player,
+                movement: None,
+                ai: None,
+                pattern: None,
+                ability: None,
+            },
+            tank: EnemyConfig {
+                size: 25.0,
+                health: 50.0,
+                speed: 30.0,
+                score: 300,
+                contact_damage: 20.0,
+                aim: Aim::Lead,
+                movement: None,
+                ai: None,
+                pattern: None,
+                ability: None,
+            },
+            splitter: EnemyConfig {
+                size: 22.0,
+                health: 40.0,
+                speed: 40.0,
+                score: 250,
+                contact_damage: 20.0,
+                aim: Aim::Down,
+                movement: None,
+                ai: None,
+                pattern: None,
+                ability: Some(Ability::Split {
+                    into: EnemyType::Fast,
+                    count: 3,
+                    spread: 60.0,
+                }),
+            },
+            carrier: EnemyConfig {
+                size: 30.0,
+                health: 120.0,
+                speed: 25.0,
+                score: 500,
+                contact_damage: 30.0,
+                aim: Aim::Down,
+                movement: Some(Movement::Strafe {
+                    y: 90.0,
+                    speed: 40.0,
+                    hold: 12.0,
+                }),
+                ai: None,
+                pattern: None,
+                ability: Some(Ability::Launch {
+                    enemy: EnemyType::Fast,
+                    interval: 3.0,
+                    movement: Some(Movement::Dive {
+                        speed: 220.0,
+                        delay: 0.5,
+                    }),
+                }),
+            },
+            sniper: EnemyConfig {
+                size: 14.0,
+                health: 20.0,
+                speed: 60.0,
+                score: 350,
+                contact_damage: 20.0,
+                aim: Aim::Player,
+                movement: Some(Movement::Strafe {
+                    y: 70.0,
+                    speed: 50.0,
+                    hold: 10.0,
+                }),
+                ai: None,
+                pattern: None,
+                ability: Some(Ability::Snipe {
+                    warning: 1.0,
+                    cooldown: 2.5,
+                    bullet_speed: 450.0,
+                }),
+            },
+            mine_layer: EnemyConfig {
+                size: 18.0,
+                health: 30.0,
+                speed: 45.0,
+                score: 300,
+                contact_damage: 20.0,
+                aim: Aim::Down,
+                movement: Some(Movement::Sine {
+                    amplitude: 120.0,
+                    frequency: 0.2,
+                }),
+                ai: None,
+                pattern: None,
+                ability: Some(Ability::LayMines {
+                    interval: 1.5,
+                    size: 10.0,
+                    damage: 15.0,
+                    life: 12.0,
+                }),
+            },
+            healer: EnemyConfig {
+                size: 16.0,
+                health: 25.0,
+                speed: 40.0,
+                score: 400,
+                contact_damage: 20.0,
+                aim: Aim::Down,
+                movement: None,
+                ai: None,
+                pattern: None,
+                ability: Some(Ability::Heal {
+                    radius: 120.0,
+                    rate: 8.0,
+                }),
+            },
+        }
+    }
+}
+
+impl Default for EnemyFireConfig {
+    fn default() -> EnemyFireConfig {
+        EnemyFireConfig {
+            chance: 0.01,
+            cooldown: 2.0,
+            bullet_speed: 150.0,
+            bullet_size: 5.0,
+            bullet_damage: 10.0,
+            spread: 12.0,
+            spread_level_factor: 0.25,
+        }
+    }
+}
+
+impl EnemyFireConfig {
+    // The plain shot for types without a pattern
+    pub fn single_shot(&self, aim: Aim) -> Pattern {
+        Pattern {
+            shape: Shape::Single,
+            aim,
+            spin: 0.0,
+            spread: self.spread,
+            burst: 1,
+            burst_interval: 0.0,
+            cooldown: self.cooldown,
+            bullet_speed: self.bullet_speed,
+        }
+    }
+
+    // Multiplier on spreads at a level
+    pub fn spread_scale(&self, level: u32) -> f32 {
+        1.0 / (1.0 + level as f32 * self.spread_level_factor)
+    }
+}
+
+impl Default for SpawnConfig {
+    fn default() -> SpawnConfig {
+        SpawnConfig {
+            enemy_interval: 1.0,
+            interval_level_factor: 0.2,
+            tank_chance: 0.1,
+            fast_chance: 0.3,
+            special_chance: 0.15,
+            squadron_interval: 30.0,
+        }
+    }
+}
+
+impl Default for PowerUpConfig {
+    fn default() -> PowerUpConfig {
+        PowerUpConfig {
+            interval: 5.0,
+            speed: 80.0,
+            size: 15.0,
+            health_chance: 0.4,
+            weapon_chance: 0.7,
+            heal: 30.0,
+            max_health_bonus: 20.0,
+        }
+    }
+}
+
+impl Default for ShieldConfig {
+    fn default() -> ShieldConfig {
+        ShieldConfig {
+            duration: 10.0,
+            max_level: 3,
+            tank_cost: 2,
+        }
+    }
+}
+
+impl Default for BlackHoleConfig {
+    fn default() -> BlackHoleConfig {
+        BlackHoleConfig {
+            size: 30.0,
+            duration: 3.0,
+            pull_radius: 150.0,
+            pull_force: 200.0,
+            distance: 200.0,
+            cooldown: 10.0,
+            score_per_enemy: 200,
+        }
+    }
+}
+
+impl Default for BossConfig {
+    fn default() -> BossConfig {
+        // Turrets fire bursts at the player
+        let turret = |x| BossPartConfig {
+            x,
+            y: 10.0,
+            size: 20.0,
+            health: 150.0,
+            score: 250,
+            pattern: Pattern {
+                shape: Shape::Single,
+                aim: Aim::Player,
+                spin: 0.0,
+                spread: 6.0,
+                burst: 3,
+                burst_interval: 0.12,
+                cooldown: 2.0,
+                bullet_speed: 180.0,
+            },
+        };
+        let pattern = |shape, spin, cooldown, bullet_speed| Pattern {
+            shape,
+            aim: Aim::Down,
+            spin,
+            spread: 0.0,
+            burst: 1,
+            burst_interval: 0.0,
+            cooldown,
+            bullet_speed,
+        };
+        BossConfig {
+            every_levels: 5,
+            size: 60.0,
+            health: 1500.0,
+            health_growth: 0.5,
+            score: 2000,
+            contact_damage: 20.0,
+            entry_speed: 60.0,
+            entry_y: 140.0,
+            parts: vec![turret(-80.0), turret(80.0)],
+            phases: vec![
+                BossPhaseConfig {
+                    health: 1.0,
+                    movement: BossMovement::Sweep { speed: 80.0 },
+                    pattern: pattern(Shape::Fan { count: 5, angle: 60.0 }, 0.0, 1.5, 150.0),
+                },
+                BossPhaseConfig {
+                    health: 0.6,
+                    movement: BossMovement::Sweep { speed: 140.0 },
+                    pattern: pattern(Shape::Ring { count: 12 }, 20.0, 1.2, 120.0),
+                },
+                // Four armed spiral
+                BossPhaseConfig {
+                    health: 0.3,
+                    movement: BossMovement::Weave {
+                        speed: 1.5,
+                        width: 250.0,
+                        height: 40.0,
+                    },
+                    pattern: pattern(Shape::Ring { count: 4 }, 90.0, 0.15, 160.0),
+                },
+            ],
+        }
+    }
+}
+
+impl Default for DifficultyConfig {
+    fn default() -> DifficultyConfig {
+        Difficulty::Normal.multipliers()
+    }
+}
+
+impl Default for DynamicDifficultyConfig {
+    fn default() -> DynamicDifficultyConfig {
+        DynamicDifficultyConfig {
+            enabled: false,
+            window: 10.0,
+            min_factor: 0.5,
+            max_factor: 2.0,
+            adjust_rate: 0.1,
+            target_health: 0.6,
+            target_kill_share: 0.5,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ConfigError {
+    Parse(String),
+    // Dotted path of the field, and what is wrong with it
+    Invalid(String, &'static str),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Parse(message) => write!(f, "could not parse game config: {}", message),
+            ConfigError::Invalid(field, reason) => write!(f, "invalid game config: {} {}", field, reason),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl GameConfig {
+    pub fn from_json(json: &str) -> Result<GameConfig, ConfigError> {
+        let overrides = serde_json::from_str(json).map_err(|e| ConfigError::Parse(e.to_string()))?;
+        GameConfig::from_overrides(overrides)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("config serializes")
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn from_ron(ron: &str) -> Result<GameConfig, ConfigError> {
+        // Through ron's own Value, which reads (field: value) structs as maps
+        let overrides: ron::Value = ron::from_str(ron).map_err(|e| ConfigError::Parse(e.to_string()))?;
+        let overrides = overrides.into_rust().map_err(|e| ConfigError::Parse(e.to_string()))?;
+        GameConfig::from_overrides(overrides)
+    }
+
+    // Merge field by field so a partial entry, like a tank with only its
+    // health set, keeps the tank defaults for the rest
+    fn from_overrides(overrides: Value) -> Result<GameConfig, ConfigError> {
+        let mut merged = serde_json::to_value(GameConfig::default()).expect("config serializes");
+        merge(&mut merged, overrides);
+        let config: GameConfig = serde_json::from_value(merged).map_err(|e| ConfigError::Parse(e.to_string()))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    // Same config with a preset's difficulty multipliers
+    pub fn with_difficulty(self, difficulty: Difficulty) -> GameConfig {
+        GameConfig {
+            difficulty: difficulty.multipliers(),
+            ..self
+        }
+    }
+
+    pub fn enemy(&self, enemy_type: EnemyType) -> &EnemyConfig {
+        match enemy_type {
+            EnemyType::Basic => &self.enemies.basic,
+            EnemyType::Fast => &self.enemies.fast,
+            EnemyType::Tank => &self.enemies.tank,
+            EnemyType::Splitter => &self.enemies.splitter,
+            EnemyType::Carrier => &self.enemies.carrier,
+            EnemyType::Sniper => &self.enemies.sniper,
+            EnemyType::MineLayer => &self.enemies.mine_layer,
+            EnemyType::Healer => &self.enemies.healer,
+        }
+    }
+
+    // Seconds between enemy spawns at a level
+    pub fn enemy_spawn_interval(&self, level: u32) -> f32 {
+        self.spawn.enemy_interval / (1.0 + level as f32 * self.spawn.interval_level_factor)
+    }
+
+    // Rejects values the simulation can't run with, like zero sizes,
+    // negative timers or chances outside 0..=1. Reports the first one.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        positive("player.speed", self.player.speed)?;
+        positive("player.max_health", self.player.max_health)?;
+        positive("player.size", self.player.size)?;
+
+        positive("weapon.cooldown", self.weapon.cooldown)?;
+        positive("weapon.bullet_speed", self.weapon.bullet_speed)?;
+        positive("weapon.bullet_size", self.weapon.bullet_size)?;
+        positive("weapon.damage_per_level", self.weapon.damage_per_level)?;
+        at_least_one("weapon.max_level", self.weapon.max_level)?;
+
+        for (name, enemy) in [
+            ("basic", &self.enemies.basic),
+            ("fast", &self.enemies.fast),
+            ("tank", &self.enemies.tank),
+            ("splitter", &self.enemies.splitter),
+            ("carrier", &self.enemies.carrier),
+            ("sniper", &self.enemies.sniper),
+            ("mine_layer", &self.enemies.mine_layer),
+            ("healer", &self.enemies.healer),
+        ] {
+            positive(&format!("enemies.{}.size", name), enemy.size)?;
+            positive(&format!("enemies.{}.health", name), enemy.health)?;
+            positive(&format!("enemies.{}.speed", name), enemy.speed)?;
+            non_negative(&format!("enemies.{}.contact_damage", name), enemy.contact_damage)?;
+            if let Some(movement) = &enemy.movement {
+                let field = format!("enemies.{}.movement", name);
+                movement.validate(&field)?;
+                self.validate_path(&field, movement)?;
+            }
+            if let Some(ai) = &enemy.ai {
+                ai.validate(&format!("enemies.{}.ai", name))?;
+            }
+            if let Some(pattern) = &enemy.pattern {
+                validate_pattern(&format!("enemies.{}.pattern", name), pattern)?;
+            }
+            if let Some(ability) = &enemy.ability {
+                let field = format!("enemies.{}.ability", name);
+                ability.validate(&field)?;
+                if let Ability::Launch {
+                    movement: Some(movement),
+                    ..
+                } = ability
+                {
+                    self.validate_path(&format!("{}.launch.movement", field), movement)?;
+                }
+            }
+        }
+
+        chance("enemy_fire.chance", self.enemy_fire.chance)?;
+        non_negative("enemy_fire.cooldown", self.enemy_fire.cooldown)?;
+        positive("enemy_fire.bullet_speed", self.enemy_fire.bullet_speed)?;
+        positive("enemy_fire.bullet_size", self.enemy_fire.bullet_size)?;
+        non_negative("enemy_fire.bullet_damage", self.enemy_fire.bullet_damage)?;
+        non_negative("enemy_fire.spread", self.enemy_fire.spread)?;
+        non_negative("enemy_fire.spread_level_factor", self.enemy_fire.spread_level_factor)?;
+
+        positive("spawn.enemy_interval", self.spawn.enemy_interval)?;
+        non_negative("spawn.interval_level_factor", self.spawn.interval_level_factor)?;
+        chance("spawn.tank_chance", self.spawn.tank_chance)?;
+        chance("spawn.fast_chance", self.spawn.fast_chance)?;
+        chance("spawn.special_chance", self.spawn.special_chance)?;
+        non_negative("spawn.squadron_interval", self.spawn.squadron_interval)?;
+
+        positive("power_ups.interval", self.power_ups.interval)?;
+        positive("power_ups.speed", self.power_ups.speed)?;
+        positive("power_ups.size", self.power_ups.size)?;
+        chance("power_ups.health_chance", self.power_ups.health_chance)?;
+        chance("power_ups.weapon_chance", self.power_ups.weapon_chance)?;
+        non_negative("power_ups.heal", self.power_ups.heal)?;
+        non_negative("power_ups.max_health_bonus", self.power_ups.max_health_bonus)?;
+
+        positive("shield.duration", self.shield.duration)?;
+        at_least_one("shield.max_level", self.shield.max_level)?;
+        at_least_one("shield.tank_cost", self.shield.tank_cost)?;
+
+        positive("black_hole.size", self.black_hole.size)?;
+        positive("black_hole.duration", self.black_hole.duration)?;
+        positive("black_hole.pull_radius", self.black_hole.pull_radius)?;
+        non_negative("black_hole.pull_force", self.black_hole.pull_force)?;
+        non_negative("black_hole.distance", self.black_hole.distance)?;
+        non_negative("black_hole.cooldown", self.black_hole.cooldown)?;
+
+        validate_boss(&self.boss)?;
+
+        positive("difficulty.enemy_health", self.difficulty.enemy_health)?;
+        positive("difficulty.bullet_speed", self.difficulty.bullet_speed)?;
+        positive("difficulty.fire_rate", self.difficulty.fire_rate)?;
+        non_negative("difficulty.damage", self.difficulty.damage)?;
+
+        let dynamic = &self.dynamic_difficulty;
+        positive("dynamic_difficulty.window", dynamic.window)?;
+        positive("dynamic_difficulty.min_factor", dynamic.min_factor)?;
+        positive("dynamic_difficulty.max_factor", dynamic.max_factor)?;
+        if dynamic.max_factor < dynamic.min_factor {
+            return Err(ConfigError::Invalid(
+                "dynamic_difficulty.max_factor".to_string(),
+                "must not be below min_factor",
+            ));
+        }
+        non_negative("dynamic_difficulty.adjust_rate", dynamic.adjust_rate)?;
+        chance("dynamic_difficulty.target_health", dynamic.target_health)?;
+        non_negative("dynamic_difficulty.target_kill_share", dynamic.target_kill_share)?;
+
+        for (i, path) in self.paths.iter().enumerate() {
+            path.validate(&format!("paths[{}]", i))?;
+        }
+        self.squadron.validate("squadron")?;
+        self.validate_squadron_path("squadron", &self.squadron)?;
+
+        if let Some(stage) = &self.stage {
+            stage.validate("stage")?;
+            for (i, step) in stage.steps.iter().enumerate() {
+                match step {
+                    Step::Spawn(SpawnGroup {
+                        movement: Some(movement),
+                        ..
+                    }) => self.validate_path(&format!("stage.steps[{}].spawn.movement", i), movement)?,
+                    Step::Squadron(squadron) => {
+                        self.validate_squadron_path(&format!("stage.steps[{}].squadron", i), squadron)?
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        at_least_one("points_per_level", self.points_per_level)?;
+        Ok(())
+    }
+
+    // Path movements must name one of the paths
+    fn validate_path(&self, field: &str, movement: &Movement) -> Result<(), ConfigError> {
+        match movement {
+            Movement::Path(path) if *path as usize >= self.paths.len() => {
+                Err(ConfigError::Invalid(format!("{}.path", field), "must be the number of a path in paths"))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn validate_squadron_path(&self, field: &str, squadron: &Squadron) -> Result<(), ConfigError> {
+        match squadron.path {
+            Some(path) => self.validate_path(field, &Movement::Path(path)),
+            None => Ok(()),
+        }
+    }
+}
+
+fn validate_boss(boss: &BossConfig) -> Result<(), ConfigError> {
+    positive("boss.size", boss.size)?;
+    positive("boss.health", boss.health)?;
+    non_negative("boss.health_growth", boss.health_growth)?;
+    non_negative("boss.contact_damage", boss.contact_damage)?;
+    positive("boss.entry_speed", boss.entry_speed)?;
+    non_negative("boss.entry_y", boss.entry_y)?;
+
+    if boss.parts.len() > MAX_BOSS_PARTS {
+        return Err(ConfigError::Invalid("boss.parts".to_string(), "must have at most 32 parts"));
+    }
+    for (i, part) in boss.parts.iter().enumerate() {
+        finite(&format!("boss.parts[{}].x", i), part.x)?;
+        finite(&format!("boss.parts[{}].y", i), part.y)?;
+        positive(&format!("boss.parts[{}].size", i), part.size)?;
+        positive(&format!("boss.parts[{}].health", i), part.health)?;
+        validate_pattern(&format!("boss.parts[{}].pattern", i), &part.pattern)?;
+    }
+
+    if boss.phases.is_empty() {
+        return Err(ConfigError::Invalid("boss.phases".to_string(), "must have at least one phase"));
+    }
+    let mut previous = f32::INFINITY;
+    for (i, phase) in boss.phases.iter().enumerate() {
+        let field = |name: &str| format!("boss.phases[{}].{}", i, name);
+        if i == 0 && phase.health != 1.0 {
+            return Err(ConfigError::Invalid(field("health"), "must be 1 for the first phase"));
+        }
+        if !(phase.health > 0.0 && phase.health < previous) {
+            return Err(ConfigError::Invalid(field("health"), "must be above 0 and below the phase before"));
+        }
+        previous = phase.health;
+
+        match phase.movement {
+            BossMovement::Hover => {}
+            BossMovement::Sweep { speed } => positive(&field("movement.sweep.speed"), speed)?,
+            BossMovement::Weave { speed, width, height } => {
+                positive(&field("movement.weave.speed"), speed)?;
+                non_negative(&field("movement.weave.width"), width)?;
+                non_negative(&field("movement.weave.height"), height)?;
+            }
+        }
+
+        validate_pattern(&field("pattern"), &phase.pattern)?;
+    }
+    Ok(())
+}
+
+fn validate_pattern(field: &str, pattern: &Pattern) -> Result<(), ConfigError> {
+    match pattern.shape {
+        Shape::Single => {}
+        Shape::Fan { count, angle } => {
+            at_least_one(&format!("{}.shape.fan.count", field), count)?;
+            non_negative(&format!("{}.shape.fan.angle", field), angle)?;
+        }
+        Shape::Ring { count } => at_least_one(&format!("{}.shape.ring.count", field), count)?,
+    }
+    finite(&format!("{}.spin", field), pattern.spin)?;
+    non_negative(&format!("{}.spread", field), pattern.spread)?;
+    at_least_one(&format!("{}.burst", field), pattern.burst)?;
+    non_negative(&format!("{}.burst_interval", field), pattern.burst_interval)?;
+    positive(&format!("{}.cooldown", field), pattern.cooldown)?;
+    positive(&format!("{}.bullet_speed", field), pattern.bullet_speed)
+}
+
+// Objects are merged key by key, except that switching an enum to another
+// variant, e.g. { "sine": {...} } over { "strafe": {...} }, replaces it
+fn merge(base: &mut Value, overrides: Value) {
+    match (base, overrides) {
+        (Value::Object(base), Value::Object(overrides))
+            if !(base.len() == 1 && overrides.len() == 1 && base.keys().ne(overrides.keys())) =>
+        {
+            for (key, value) in overrides {
+                match base.get_mut(&key) {
+                    Some(field) => merge(field, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overrides) => *base = overrides,
+    }
+}
+
+fn positive(field: &str, value: f32) -> Result<(), ConfigError> {
+    if value.is_finite() && value > 0.0 {
+        Ok(())
+    } else {
+        Err(ConfigError::Invalid(field.to_string(), "must be a positive number"))
+    }
+}
+
+fn finite(field: &str, value: f32) -> Result<(), ConfigError> {
+    if value.is_finite() {
+        Ok(())
+    } else {
+        Err(ConfigError::Invalid(field.to_string(), "must be a number"))
+    }
+}
+
+fn non_negative(field: &str, value: f32) -> Result<(), ConfigError> {
+    if value.is_finite() && value >= 0.0 {
+        Ok(())
+    } else {
+        Err(ConfigError::Invalid(field.to_string(), "must be zero or more"))
+    }
+}
+
+fn chance(field: &str, value: f32) -> Result<(), ConfigError> {
+    if (0.0..=1.0).contains(&value) {
+        Ok(())
+    } else {
+        Err(ConfigError::Invalid(field.to_string(), "must be between 0 and 1"))
+    }
+}
+
+fn at_least_one(field: &str, value: u32) -> Result<(), ConfigError> {
+    if value >= 1 {
+        Ok(())
+    } else {
+        Err(ConfigError::Invalid(field.to_string(), "must be at least 1"))
+    }
+}
@@ -0,0 +1,133 @@
+use std::collections::VecDeque;
+use std::fmt;
+
+use crate::config::{DifficultyConfig, DynamicDifficultyConfig};
+
+// Seconds of play per dynamic difficulty sample
+pub const SAMPLE_INTERVAL: f32 = 1.0;
+
+// Difficulty presets, picked when a game is created. Each one is just a
+// set of DifficultyConfig multipliers, so a config can fine-tune them.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+    Nightmare,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct UnknownDifficulty(pub String);
+
+impl fmt::Display for UnknownDifficulty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unknown difficulty \"{}\", expected easy, normal, hard or nightmare", self.0)
+    }
+}
+
+impl std::error::Error for UnknownDifficulty {}
+
+impl Difficulty {
+    pub fn from_name(name: &str) -> Result<Difficulty, UnknownDifficulty> {
+        match name.to_ascii_lowercase().as_str() {
+            "easy" => Ok(Difficulty::Easy),
+            "normal" => Ok(Difficulty::Normal),
+            "hard" => Ok(Difficulty::Hard),
+            "nightmare" => Ok(Difficulty::Nightmare),
+            _ => Err(UnknownDifficulty(name.to_string())),
+        }
+    }
+
+    pub fn multipliers(self) -> DifficultyConfig {
+        let (enemy_health, bullet_speed, fire_rate, damage) = match self {
+            Difficulty::Easy => (0.75, 0.8, 0.6, 0.5),
+            Difficulty::Normal => (1.0, 1.0, 1.0, 1.0),
+            Difficulty::Hard => (1.5, 1.25, 1.5, 1.5),
+            Difficulty::Nightmare => (2.0, 1.5, 2.0, 2.0),
+        };
+        DifficultyConfig {
+            enemy_health,
+            bullet_speed,
+            fire_rate,
+            damage,
+        }
+    }
+}
+
+// One second of play: player health as a share of max health at the end
+// of it, and enemies killed during it
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct DifficultySample {
+    pub health: f32,
+    pub kills: u32,
+}
+
+// Dynamic difficulty adjustment. Watches player health and kill rate
+// over a sliding window and nudges spawn pressure, as a factor enemy
+// spawn rates are multiplied by. Stays at 1 unless enabled in the config.
+#[derive(Clone, PartialEq, Debug)]
+pub struct DynamicDifficulty {
+    pub factor: f32,
+    pub(crate) sample_timer: f32,
+    // Player's kill count when the current sample started
+    pub(crate) sample_kills: u32,
+    pub(crate) samples: VecDeque<DifficultySample>,
+}
+
+impl Default for DynamicDifficulty {
+    fn default() -> DynamicDifficulty {
+        DynamicDifficulty {
+            factor: 1.0,
+            sample_timer: 0.0,
+            sample_kills: 0,
+            samples: VecDeque::new(),
+        }
+    }
+}
+
+impl DynamicDifficulty {
+    pub fn samples(&self) -> impl Iterator<Item = &DifficultySample> {
+        self.samples.iter()
+    }
+
+    // Called every tick. spawn_rate is enemies spawned per second right
+    // now, to judge how many of them the player keeps up with.
+    pub fn update(
+        &mut self,
+        config: &DynamicDifficultyConfig,
+        delta_time: f32,
+        health: f32,
+        kills: u32,
+        spawn_rate: f32,
+    ) {
+        if !config.enabled {
+            return;
+        }
+
+        self.sample_timer += delta_time;
+        if self.sample_timer < SAMPLE_INTERVAL {
+            return;
+        }
+        self.sample_timer -= SAMPLE_INTERVAL;
+
+        self.samples.push_back(DifficultySample {
+            health,
+            kills: kills.saturating_sub(self.sample_kills),
+        });
+        self.sample_kills = kills;
+        let window = (config.window / SAMPLE_INTERVAL).ceil().max(1.0) as usize;
+        while self.samples.len() > window {
+            self.samples.pop_front();
+        }
+
+        let count = self.samples.len() as f32;
+        let health = self.samples.iter().map(|s| s.health).sum::<f32>() / count;
+        let kill_rate = self.samples.iter().map(|s| s.kills).sum::<u32>() as f32 / (count * SAMPLE_INTERVAL);
+        let kill_share = kill_rate / spawn_rate.max(f32::EPSILON);
+
+        // Healthy and keeping up pushes harder, struggling eases off
+        let pressure = (health - config.target_health) + (kill_share - config.target_kill_share);
+        self.factor = (self.factor + pressure * config.adjust_rate * SAMPLE_INTERVAL)
+            .clamp(config.min_factor, config.max_factor);
+    }
+}
@@ -0,0 +1,190 @@
+use serde::{Deserialize, Serialize};
+
+use crate::ai::AiState;
+use crate::config::PlayerConfig;
+use crate::formation::Squad;
+use crate::ids::EntityId;
+use crate::movement::Movement;
+use crate::pattern::Emitter;
+
+#[derive(Clone)]
+pub struct Player {
+    pub x: f32,
+    pub y: f32,
+    // Position at the start of the last tick, for render interpolation
+    pub prev_x: f32,
+    pub prev_y: f32,
+    pub vx: f32,
+    pub vy: f32,
+    pub health: f32,
+    pub max_health: f32,
+    pub size: f32,
+    pub shoot_cooldown: f32,
+    pub power_level: u32,
+    pub growth_level: u32,
+    pub enemies_killed: u32,
+    pub black_hole_cooldown: f32,
+}
+
+impl Player {
+    pub fn new(width: f32, height: f32, config: &PlayerConfig) -> Player {
+        let x = width / 2.0;
+        let y = height - 100.0;
+        Player {
+            x,
+            y,
+            prev_x: x,
+            prev_y: y,
+            vx: 0.0,
+            vy: 0.0,
+            health: config.max_health,
+            max_health: config.max_health,
+            size: config.size,
+            shoot_cooldown: 0.0,
+            power_level: 1,
+            growth_level: 0,
+            enemies_killed: 0,
+            black_hole_cooldown: 0.0,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Enemy {
+    pub id: EntityId,
+    pub x: f32,
+    pub y: f32,
+    pub prev_x: f32,
+    pub prev_y: f32,
+    pub vx: f32,
+    pub vy: f32,
+    pub health: f32,
+    pub max_health: f32,
+    pub size: f32,
+    pub enemy_type: EnemyType,
+    pub shoot_cooldown: f32,
+    // Used instead of shoot_cooldown when the type has a bullet pattern
+    pub emitter: Emitter,
+    pub movement: Movement,
+    // Seconds since spawning, and seconds spent on a strafe line
+    pub movement_time: f32,
+    pub held: f32,
+    // AI state and seconds spent in it
+    pub ai: AiState,
+    pub ai_time: f32,
+    // Place in a formation, None once it has dived away or for loners
+    pub squad: Option<Squad>,
+    // Seconds until the type's ability is next used
+    pub ability_timer: f32,
+    // Direction of a sniper's laser sight while it is aiming, in radians
+    // with 0 straight down
+    pub laser: Option<f32>,
+}
+
+#[derive(Clone, Copy, Debug)]
+#[derive(PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EnemyType {
+    Basic,
+    Fast,
+    Tank,
+    Splitter,
+    Carrier,
+    Sniper,
+    MineLayer,
+    Healer,
+}
+
+impl EnemyType {
+    pub const ALL: [EnemyType; 8] = [
+        EnemyType::Basic,
+        EnemyType::Fast,
+        EnemyType::Tank,
+        EnemyType::Splitter,
+        EnemyType::Carrier,
+        EnemyType::Sniper,
+        EnemyType::MineLayer,
+        EnemyType::Healer,
+    ];
+
+    // Archetypes picked by spawn.special_chance in random spawning
+    pub const SPECIAL: [EnemyType; 5] = [
+        EnemyType::Splitter,
+        EnemyType::Carrier,
+        EnemyType::Sniper,
+        EnemyType::MineLayer,
+        EnemyType::Healer,
+    ];
+
+    // Stable id for render channels, events and saves
+    pub fn id(self) -> u8 {
+        self as u8
+    }
+
+    pub fn from_id(id: u8) -> Option<EnemyType> {
+        EnemyType::ALL.get(id as usize).copied()
+    }
+}
+
+#[derive(Clone)]
+pub struct Bullet {
+    pub id: EntityId,
+    pub x: f32,
+    pub y: f32,
+    pub prev_x: f32,
+    pub prev_y: f32,
+    pub vx: f32,
+    pub vy: f32,
+    pub size: f32,
+    pub damage: f32,
+}
+
+#[derive(Clone)]
+pub struct PowerUp {
+    pub id: EntityId,
+    pub x: f32,
+    pub y: f32,
+    pub prev_x: f32,
+    pub prev_y: f32,
+    pub vy: f32,
+    pub size: f32,
+    pub power_type: PowerUpType,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PowerUpType {
+    Health,
+    Weapon,
+    Shield,
+}
+
+// A stationary hazard left by mine layers
+#[derive(Clone)]
+pub struct Mine {
+    pub id: EntityId,
+    pub x: f32,
+    pub y: f32,
+    pub size: f32,
+    pub damage: f32,
+    pub life: f32,
+    pub max_life: f32,
+}
+
+pub struct Explosion {
+    pub x: f32,
+    pub y: f32,
+    pub size: f32,
+    pub life: f32,
+    pub max_life: f32,
+}
+
+pub struct BlackHole {
+    pub id: EntityId,
+    pub x: f32,
+    pub y: f32,
+    pub size: f32,
+    pub life: f32,
+    pub max_life: f32,
+    pub pull_radius: f32,
+    pub consumed_enemies: Vec<(f32, f32)>, // Store positions of consumed enemies
+}
@@ -0,0 +1,101 @@
+use crate::entities::{EnemyType, PowerUpType};
+use crate::ids::EntityId;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum DamageSource {
+    EnemyBullet,
+    EnemyCollision,
+    Mine,
+}
+
+// Something that happened during a tick. Queued by the simulation and
+// drained by whoever reacts to it (audio, UI, analytics). id is the
+// entity the event is about; for hits it is the bullet or enemy that hit.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GameEvent {
+    EnemyKilled { id: EntityId, enemy_type: EnemyType, x: f32, y: f32, score: u32 },
+    PlayerHit { id: EntityId, source: DamageSource, x: f32, y: f32, damage: f32 },
+    ShieldAbsorbed { id: EntityId, source: DamageSource, x: f32, y: f32, shield_level: u32 },
+    PowerUpCollected { id: EntityId, power_type: PowerUpType, x: f32, y: f32 },
+    BlackHoleOpened { id: EntityId, x: f32, y: f32 },
+    BlackHoleCollapsed { id: EntityId, x: f32, y: f32, consumed: u32, score: u32 },
+    LevelUp { level: u32 },
+    GameOver { score: u32 },
+    // Every step of a scripted stage has run
+    StageComplete,
+    BossSpawned { id: EntityId, x: f32, y: f32 },
+    BossPhaseChanged { id: EntityId, phase: u32, x: f32, y: f32 },
+    // part is the index in the boss config's parts
+    BossPartDestroyed { id: EntityId, part: u32, x: f32, y: f32, score: u32 },
+    BossDefeated { id: EntityId, x: f32, y: f32, score: u32 },
+}
+
+// Floats per event in the flat encoding: [kind, x, y, a, b, id, generation].
+// Events not about an entity have an id of -1.
+pub const EVENT_STRIDE: usize = 7;
+
+pub const EVENT_ENEMY_KILLED: f32 = 0.0; // a = enemy type, b = score
+pub const EVENT_PLAYER_HIT: f32 = 1.0; // a = damage source, b = damage
+pub const EVENT_SHIELD_ABSORBED: f32 = 2.0; // a = damage source, b = shield level left
+pub const EVENT_POWER_UP_COLLECTED: f32 = 3.0; // a = power-up type
+pub const EVENT_BLACK_HOLE_OPENED: f32 = 4.0;
+pub const EVENT_BLACK_HOLE_COLLAPSED: f32 = 5.0; // a = enemies consumed, b = score
+pub const EVENT_LEVEL_UP: f32 = 6.0; // a = new level
+pub const EVENT_GAME_OVER: f32 = 7.0; // a = final score
+pub const EVENT_STAGE_COMPLETE: f32 = 8.0;
+pub const EVENT_BOSS_SPAWNED: f32 = 9.0;
+pub const EVENT_BOSS_PHASE_CHANGED: f32 = 10.0; // a = new phase
+pub const EVENT_BOSS_PART_DESTROYED: f32 = 11.0; // a = part, b = score
+pub const EVENT_BOSS_DEFEATED: f32 = 12.0; // b = score
+
+impl GameEvent {
+    pub fn encode(&self, out: &mut Vec<f32>) {
+        let (record, id) = match *self {
+            GameEvent::EnemyKilled { id, enemy_type, x, y, score } => {
+                ([EVENT_ENEMY_KILLED, x, y, enemy_type_id(&enemy_type), score as f32], Some(id))
+            }
+            GameEvent::PlayerHit { id, source, x, y, damage } => {
+                ([EVENT_PLAYER_HIT, x, y, source as u8 as f32, damage], Some(id))
+            }
+            GameEvent::ShieldAbsorbed { id, source, x, y, shield_level } => {
+                ([EVENT_SHIELD_ABSORBED, x, y, source as u8 as f32, shield_level as f32], Some(id))
+            }
+            GameEvent::PowerUpCollected { id, power_type, x, y } => {
+                ([EVENT_POWER_UP_COLLECTED, x, y, power_type_id(&power_type), 0.0], Some(id))
+            }
+            GameEvent::BlackHoleOpened { id, x, y } => ([EVENT_BLACK_HOLE_OPENED, x, y, 0.0, 0.0], Some(id)),
+            GameEvent::BlackHoleCollapsed { id, x, y, consumed, score } => {
+                ([EVENT_BLACK_HOLE_COLLAPSED, x, y, consumed as f32, score as f32], Some(id))
+            }
+            GameEvent::LevelUp { level } => ([EVENT_LEVEL_UP, 0.0, 0.0, level as f32, 0.0], None),
+            GameEvent::GameOver { score } => ([EVENT_GAME_OVER, 0.0, 0.0, score as f32, 0.0], None),
+            GameEvent::StageComplete => ([EVENT_STAGE_COMPLETE, 0.0, 0.0, 0.0, 0.0], None),
+            GameEvent::BossSpawned { id, x, y } => ([EVENT_BOSS_SPAWNED, x, y, 0.0, 0.0], Some(id)),
+            GameEvent::BossPhaseChanged { id, phase, x, y } => {
+                ([EVENT_BOSS_PHASE_CHANGED, x, y, phase as f32, 0.0], Some(id))
+            }
+            GameEvent::BossPartDestroyed { id, part, x, y, score } => {
+                ([EVENT_BOSS_PART_DESTROYED, x, y, part as f32, score as f32], Some(id))
+            }
+            GameEvent::BossDefeated { id, x, y, score } => ([EVENT_BOSS_DEFEATED, x, y, 0.0, score as f32], Some(id)),
+        };
+        out.extend_from_slice(&record);
+        match id {
+            Some(id) => out.extend_from_slice(&[id.index as f32, id.generation as f32]),
+            None => out.extend_from_slice(&[-1.0, 0.0]),
+        }
+    }
+}
+
+// Same ids as the type field in the render channels
+pub fn enemy_type_id(enemy_type: &EnemyType) -> f32 {
+    enemy_type.id() as f32
+}
+
+pub fn power_type_id(power_type: &PowerUpType) -> f32 {
+    match power_type {
+        PowerUpType::Health => 0.0,
+        PowerUpType::Weapon => 1.0,
+        PowerUpType::Shield => 2.0,
+    }
+}
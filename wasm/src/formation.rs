@@ -0,0 +1,352 @@
+use std::f32::consts::PI;
+
+use serde::{Deserialize, Serialize};
+
+use crate::ai::AiState;
+use crate::config::ConfigError;
+use crate::entities::EnemyType;
+use crate::ids::EntityId;
+use crate::movement::Movement;
+use crate::sim::Simulation;
+
+// Seconds for the formation to sway out to one side and back across
+const SWAY_PERIOD: f32 = 4.0;
+
+// A squadron of enemies that flies in one by one along a shared entry,
+// settles into a grid of rows and columns, then sends members off in dive
+// attacks one at a time. Used by stage steps and random squadrons, e.g.
+//
+// { "enemy": "basic", "rows": 2, "columns": 5, "entry_x": 0.1, "path": 0 }
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Squadron {
+    pub enemy: EnemyType,
+    #[serde(default = "two")]
+    pub rows: u32,
+    #[serde(default = "five")]
+    pub columns: u32,
+    // Pixels between neighbouring slots, across and down
+    #[serde(default = "spacing")]
+    pub spacing: f32,
+    // Centre of the grid, x as a share of the screen width, y of the top
+    // row in pixels
+    #[serde(default = "half")]
+    pub x: f32,
+    #[serde(default = "formation_y")]
+    pub y: f32,
+    // Where members come in above the top edge, as a share of the width
+    #[serde(default = "half")]
+    pub entry_x: f32,
+    // Entry along config.paths[n] before heading for their slot,
+    // otherwise straight to it
+    #[serde(default)]
+    pub path: Option<u32>,
+    // Seconds between members coming in
+    #[serde(default = "interval")]
+    pub interval: f32,
+    // Pixels per second from the end of the entry to the slot
+    #[serde(default = "join_speed")]
+    pub join_speed: f32,
+    // Seconds between dive attacks, and their speed
+    #[serde(default = "dive_interval")]
+    pub dive_interval: f32,
+    #[serde(default = "dive_speed")]
+    pub dive_speed: f32,
+    // Pixels the grid sways to each side
+    #[serde(default)]
+    pub sway: f32,
+}
+
+fn two() -> u32 {
+    2
+}
+
+fn five() -> u32 {
+    5
+}
+
+fn spacing() -> f32 {
+    50.0
+}
+
+fn half() -> f32 {
+    0.5
+}
+
+fn formation_y() -> f32 {
+    80.0
+}
+
+fn interval() -> f32 {
+    0.25
+}
+
+fn join_speed() -> f32 {
+    200.0
+}
+
+fn dive_interval() -> f32 {
+    3.0
+}
+
+fn dive_speed() -> f32 {
+    250.0
+}
+
+impl Squadron {
+    // Checks everything but the path number, which needs the config's
+    // paths
+    pub fn validate(&self, field: &str) -> Result<(), ConfigError> {
+        let invalid = |name: &str, reason| Err(ConfigError::Invalid(format!("{}.{}", field, name), reason));
+        if self.rows == 0 {
+            return invalid("rows", "must be at least 1");
+        }
+        if self.columns == 0 {
+            return invalid("columns", "must be at least 1");
+        }
+        if !(0.0..=1.0).contains(&self.x) {
+            return invalid("x", "must be between 0 and 1");
+        }
+        if !(0.0..=1.0).contains(&self.entry_x) {
+            return invalid("entry_x", "must be between 0 and 1");
+        }
+        if !self.y.is_finite() {
+            return invalid("y", "must be a number");
+        }
+        for (name, value) in [
+            ("spacing", self.spacing),
+            ("join_speed", self.join_speed),
+            ("dive_interval", self.dive_interval),
+            ("dive_speed", self.dive_speed),
+        ] {
+            if !(value.is_finite() && value > 0.0) {
+                return invalid(name, "must be a positive number");
+            }
+        }
+        for (name, value) in [("interval", self.interval), ("sway", self.sway)] {
+            if !(value.is_finite() && value >= 0.0) {
+                return invalid(name, "must be zero or more");
+            }
+        }
+        Ok(())
+    }
+
+    pub fn size(&self) -> u32 {
+        self.rows * self.columns
+    }
+}
+
+// A squadron in play. It owns the slots its members fly to, and is gone
+// once every member has spawned and then died or dived away.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Formation {
+    pub id: EntityId,
+    pub spec: Squadron,
+    // Seconds since it started, for the sway
+    pub time: f32,
+    // Members spawned so far, and seconds until the next
+    pub spawned: u32,
+    pub spawn_timer: f32,
+    // Seconds since the last dive
+    pub dive_timer: f32,
+    // Member in each slot, row by row. Empty once it has died or left.
+    pub slots: Vec<Option<EntityId>>,
+    // Slot picked to dive next, waiting for its member to settle in
+    pub release: Option<u32>,
+    // Where each slot is this tick, worked out from the slots still in
+    // use so the grid closes up as members go
+    pub positions: Vec<(f32, f32)>,
+}
+
+// An enemy's place in a formation
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Squad {
+    pub formation: EntityId,
+    pub slot: u32,
+    // Settled into its slot, following it from then on
+    pub holding: bool,
+}
+
+impl Formation {
+    pub fn new(id: EntityId, spec: Squadron) -> Formation {
+        Formation {
+            id,
+            spec,
+            time: 0.0,
+            spawned: 0,
+            spawn_timer: 0.0,
+            dive_timer: 0.0,
+            slots: vec![None; spec.size() as usize],
+            release: None,
+            positions: vec![(0.0, 0.0); spec.size() as usize],
+        }
+    }
+
+    // A slot is in use until its member has spawned and then gone
+    fn in_use(&self, slot: usize) -> bool {
+        slot as u32 >= self.spawned || self.slots[slot].is_some()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.spawned >= self.spec.size() && self.slots.iter().all(Option::is_none)
+    }
+
+    // Lay the slots in use out as a grid centred on the formation: each
+    // row's slots side by side with the gaps closed, and empty rows
+    // dropped so the ones below move up
+    pub fn layout(&mut self, width: f32) {
+        let spec = self.spec;
+        let columns = spec.columns as usize;
+        let sway = spec.sway * (self.time * 2.0 * PI / SWAY_PERIOD).sin();
+        let centre = spec.x * width + sway;
+        let mut row_y = spec.y;
+        for row in 0..spec.rows as usize {
+            let slots = row * columns..(row + 1) * columns;
+            let count = slots.clone().filter(|&slot| self.in_use(slot)).count();
+            if count == 0 {
+                continue;
+            }
+            let mut rank = 0;
+            for slot in slots {
+                if self.in_use(slot) {
+                    let offset = rank as f32 - (count - 1) as f32 / 2.0;
+                    self.positions[slot] = (centre + offset * spec.spacing, row_y);
+                    rank += 1;
+                }
+            }
+            row_y += spec.spacing;
+        }
+    }
+}
+
+impl Simulation {
+    // Start a squadron flying in
+    pub fn spawn_squadron(&mut self, spec: Squadron) -> EntityId {
+        let id = self.ids.alloc();
+        let mut formation = Formation::new(id, spec);
+        formation.layout(self.width);
+        self.formations.push(formation);
+        id
+    }
+
+    // Spawns members, lays out the slots and steers members into them.
+    // Runs before update_enemies, which moves members with the velocity
+    // set here.
+    pub fn update_formations(&mut self, delta_time: f32) {
+        let mut spawns = Vec::new();
+        for formation in &mut self.formations {
+            formation.time += delta_time;
+            formation.spawn_timer -= delta_time;
+            while formation.spawned < formation.spec.size() && formation.spawn_timer <= 0.0 {
+                spawns.push((formation.id, formation.spawned));
+                formation.spawned += 1;
+                formation.spawn_timer += formation.spec.interval;
+            }
+
+            for slot in &mut formation.slots {
+                if slot.is_some_and(|id| !self.ids.is_alive(id)) {
+                    *slot = None;
+                }
+            }
+            if formation.release.is_some_and(|slot| formation.slots[slot as usize].is_none()) {
+                formation.release = None;
+            }
+
+            // Pick a random member to dive next
+            formation.dive_timer += delta_time;
+            if formation.release.is_none() && formation.dive_timer >= formation.spec.dive_interval {
+                let members = formation.slots.iter().filter(|slot| slot.is_some()).count();
+                if members > 0 {
+                    let pick = ((self.rng.next_f32() * members as f32) as usize).min(members - 1);
+                    let slot = formation.slots.iter().enumerate().filter(|(_, slot)| slot.is_some()).nth(pick);
+                    formation.release = slot.map(|(slot, _)| slot as u32);
+                    formation.dive_timer = 0.0;
+                }
+            }
+
+            formation.layout(self.width);
+        }
+
+        for (formation_id, slot) in spawns {
+            let Some(index) = self.formations.iter().position(|f| f.id == formation_id) else {
+                continue;
+            };
+            let spec = self.formations[index].spec;
+            let movement = spec.path.map_or(Movement::Straight, Movement::Path);
+            if let Some(id) = self.spawn_enemy_at(spec.enemy, spec.entry_x * self.width, -50.0, movement) {
+                self.formations[index].slots[slot as usize] = Some(id);
+                // Spawned enemies go on the end of the pool
+                if let Some(enemy) = self.enemies.last_mut() {
+                    enemy.squad = Some(Squad {
+                        formation: formation_id,
+                        slot,
+                        holding: false,
+                    });
+                }
+            }
+        }
+
+        for enemy in &mut self.enemies {
+            let Some(squad) = &mut enemy.squad else {
+                continue;
+            };
+            let Some(formation) = self.formations.iter_mut().find(|f| f.id == squad.formation) else {
+                enemy.squad = None;
+                continue;
+            };
+            let slot = squad.slot as usize;
+
+            // Fleeing members break away for good
+            if enemy.ai == AiState::Flee {
+                formation.slots[slot] = None;
+                enemy.squad = None;
+                continue;
+            }
+
+            if formation.release == Some(squad.slot) && squad.holding {
+                formation.slots[slot] = None;
+                formation.release = None;
+                enemy.squad = None;
+                enemy.movement = Movement::Dive {
+                    speed: formation.spec.dive_speed,
+                    delay: 0.0,
+                };
+                enemy.movement_time = 0.0;
+                continue;
+            }
+
+            // Still flying in along the entry path
+            if let Movement::Path(path) = enemy.movement {
+                if self.config.paths.get(path as usize).is_some_and(|path| enemy.movement_time < path.duration) {
+                    continue;
+                }
+            }
+            enemy.movement = Movement::Straight;
+
+            let (target_x, target_y) = formation.positions[slot];
+            let (dx, dy) = (target_x - enemy.x, target_y - enemy.y);
+            let distance = (dx * dx + dy * dy).sqrt();
+            let step = formation.spec.join_speed * delta_time;
+            if squad.holding || distance <= step {
+                squad.holding = true;
+                // Exactly onto the slot, or staying put on a tick with no time
+                if delta_time > 0.0 {
+                    enemy.vx = dx / delta_time;
+                    enemy.vy = dy / delta_time;
+                }
+            } else {
+                enemy.vx = dx / distance * formation.spec.join_speed;
+                enemy.vy = dy / distance * formation.spec.join_speed;
+            }
+        }
+
+        let ids = &mut self.ids;
+        self.formations.retain(|formation| {
+            let finished = formation.is_finished();
+            if finished {
+                ids.free(formation.id);
+            }
+            !finished
+        });
+    }
+}
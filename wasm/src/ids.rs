@@ -0,0 +1,64 @@
+// Stable handle for an entity across ticks. The index is a slot that is
+// reused once the entity is gone, with the generation bumped so an old
+// id never matches the slot's next occupant.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub struct EntityId {
+    pub index: u32,
+    pub generation: u32,
+}
+
+// Hands out entity ids, one slot space shared by every entity kind so an
+// id is unique on its own
+#[derive(Clone, Default)]
+pub struct IdAllocator {
+    // Current generation of each slot
+    pub(crate) generations: Vec<u32>,
+    // Slots free for reuse, the last one is taken first
+    pub(crate) free: Vec<u32>,
+}
+
+impl IdAllocator {
+    pub fn new() -> IdAllocator {
+        IdAllocator::default()
+    }
+
+    pub fn alloc(&mut self) -> EntityId {
+        match self.free.pop() {
+            Some(index) => EntityId {
+                index,
+                generation: self.generations[index as usize],
+            },
+            None => {
+                self.generations.push(0);
+                EntityId {
+                    index: self.generations.len() as u32 - 1,
+                    generation: 0,
+                }
+            }
+        }
+    }
+
+    // Stale or unknown ids are ignored, so freeing twice is harmless
+    pub fn free(&mut self, id: EntityId) {
+        if self.is_alive(id) {
+            let generation = &mut self.generations[id.index as usize];
+            *generation = generation.wrapping_add(1);
+            self.free.push(id.index);
+        }
+    }
+
+    // Freeing bumps the slot's generation, so this also rejects ids of
+    // entities that are gone
+    pub fn is_alive(&self, id: EntityId) -> bool {
+        self.generations.get(id.index as usize) == Some(&id.generation)
+    }
+
+    // Number of live ids
+    pub fn len(&self) -> usize {
+        self.generations.len() - self.free.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
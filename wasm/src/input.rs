@@ -0,0 +1,11 @@
+// Player input for a single simulation tick.
+// Movement and fire are held state: the latest frame stays in effect
+// until a newer one is applied. Ability buttons are presses and only
+// fire on the tick their frame is applied.
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct InputFrame {
+    pub move_x: f32,
+    pub move_y: f32,
+    pub fire: bool,
+    pub black_hole: bool,
+}
@@ -1,863 +1,364 @@
 use wasm_bindgen::prelude::*;
 use js_sys::Float32Array;
-use std::collections::HashSet;
 
+pub mod ability;
+pub mod ai;
+pub mod boss;
+pub mod bullets;
+mod bytes;
+pub mod config;
+pub mod difficulty;
+pub mod entities;
+pub mod events;
+pub mod formation;
+pub mod ids;
+pub mod input;
+pub mod movement;
+pub mod pattern;
+pub mod pool;
+pub mod render;
+pub mod replay;
+pub mod rng;
+pub mod sim;
+pub mod spatial;
+pub mod stage;
+pub mod state;
+pub mod viewport;
+
+pub use config::GameConfig;
+pub use events::GameEvent;
+pub use ids::EntityId;
+pub use input::InputFrame;
+pub use replay::Replay;
+pub use sim::Simulation;
+
+use events::EVENT_STRIDE;
+use difficulty::Difficulty;
+use stage::Stage;
+use render::{RenderData, CHANNEL_COUNT, RENDER_SCHEMA_VERSION};
+use viewport::{ViewTransform, LOGICAL_HEIGHT, LOGICAL_WIDTH};
+
+// Thin wasm-bindgen wrapper around the native Simulation
 #[wasm_bindgen]
 pub struct GameEngine {
-    player: Player,
-    enemies: Vec<Enemy>,
-    bullets: Vec<Bullet>,
-    enemy_bullets: Vec<Bullet>,
-    power_ups: Vec<PowerUp>,
-    explosions: Vec<Explosion>,
-    black_holes: Vec<BlackHole>,
-    score: u32,
-    level: u32,
-    game_time: f32,
-    enemy_spawn_timer: f32,
-    power_up_spawn_timer: f32,
-    width: f32,
-    height: f32,
-    game_over: bool,
-    // Shield system fields
-    shield_level: u32,
-    shield_active: bool,
-    shield_timer: f32,
+    sim: Simulation,
+    // Reused every frame by update_render_data
+    render_data: RenderData,
+    // Canvas size in pixels, only used for the view transform
+    screen_width: f32,
+    screen_height: f32,
 }
 
-#[derive(Clone)]
-struct Player {
-    x: f32,
-    y: f32,
-    vx: f32,
-    vy: f32,
-    health: f32,
-    max_health: f32,
-    size: f32,
-    shoot_cooldown: f32,
-    power_level: u32,
-    growth_level: u32,
-    enemies_killed: u32,
-    black_hole_cooldown: f32,
-}
-
-#[derive(Clone)]
-struct Enemy {
-    x: f32,
-    y: f32,
-    vx: f32,
-    vy: f32,
-    health: f32,
-    size: f32,
-    enemy_type: EnemyType,
-    shoot_cooldown: f32,
-}
-
-#[derive(Clone)]
-#[derive(PartialEq)]
-enum EnemyType {
-    Basic,
-    Fast,
-    Tank,
-}
-
-#[derive(Clone)]
-struct Bullet {
-    x: f32,
-    y: f32,
-    vx: f32,
-    vy: f32,
-    size: f32,
-    damage: f32,
-}
-
-#[derive(Clone)]
-struct PowerUp {
-    x: f32,
-    y: f32,
-    vy: f32,
-    size: f32,
-    power_type: PowerUpType,
-}
-
-#[derive(Clone)]
-enum PowerUpType {
-    Health,
-    Weapon,
-    Shield,
-}
+impl GameEngine {
+    fn from_sim(sim: Simulation, screen_width: f32, screen_height: f32) -> GameEngine {
+        let mut engine = GameEngine {
+            screen_width: sim.width,
+            screen_height: sim.height,
+            sim,
+            render_data: RenderData::with_full_capacity(),
+        };
+        engine.resize(screen_width, screen_height);
+        engine
+    }
 
-struct Explosion {
-    x: f32,
-    y: f32,
-    size: f32,
-    life: f32,
-    max_life: f32,
-}
+    // Browser runs get a fresh seed every time
+    fn random_seed() -> u64 {
+        (js_sys::Math::random() * u32::MAX as f64) as u64 | (((js_sys::Math::random() * u32::MAX as f64) as u64) << 32)
+    }
 
-struct BlackHole {
-    x: f32,
-    y: f32,
-    size: f32,
-    life: f32,
-    max_life: f32,
-    pull_radius: f32,
-    consumed_enemies: Vec<(f32, f32)>, // Store positions of consumed enemies
+    fn view_transform(&self) -> ViewTransform {
+        ViewTransform::fit(self.sim.width, self.sim.height, self.screen_width, self.screen_height)
+    }
 }
 
 #[wasm_bindgen]
 impl GameEngine {
+    // width and height are the canvas size, the game itself always runs
+    // at the logical resolution
     pub fn new(width: f32, height: f32) -> GameEngine {
-        let player =         Player {
-            x: width / 2.0,
-            y: height - 100.0,
-            vx: 0.0,
-            vy: 0.0,
-            health: 100.0,
-            max_health: 100.0,
-            size: 20.0,
-            shoot_cooldown: 0.0,
-            power_level: 1,
-            growth_level: 0,
-            enemies_killed: 0,
-            black_hole_cooldown: 0.0,
-        };
-
-        GameEngine {
-            player,
-            enemies: Vec::new(),
-            bullets: Vec::new(),
-            enemy_bullets: Vec::new(),
-            power_ups: Vec::new(),
-            explosions: Vec::new(),
-            black_holes: Vec::new(),
-            score: 0,
-            level: 1,
-            game_time: 0.0,
-            enemy_spawn_timer: 0.0,
-            power_up_spawn_timer: 0.0,
-            width,
-            height,
-            game_over: false,
-            // Initialize shield system
-            shield_level: 0,
-            shield_active: false,
-            shield_timer: 0.0,
-        }
+        GameEngine::new_with_seed(width, height, GameEngine::random_seed())
     }
 
-    pub fn update(&mut self, delta_time: f32) {
-        if self.game_over {
-            return;
-        }
-
-        self.game_time += delta_time;
-        self.enemy_spawn_timer += delta_time;
-        self.power_up_spawn_timer += delta_time;
-
-        // Update player
-        self.update_player(delta_time);
-
-        // Spawn enemies
-        if self.enemy_spawn_timer >= 1.0 / (1.0 + self.level as f32 * 0.2) {
-            self.spawn_enemy();
-            self.enemy_spawn_timer = 0.0;
-        }
-
-        // Spawn power-ups
-        if self.power_up_spawn_timer >= 5.0 {
-            self.spawn_power_up();
-            self.power_up_spawn_timer = 0.0;
-        }
-
-        // Update enemies
-        self.update_enemies(delta_time);
-
-        // Update bullets
-        self.update_bullets(delta_time);
-
-        // Update power-ups
-        self.update_power_ups(delta_time);
-
-        // Update explosions
-        self.update_explosions(delta_time);
-
-        // Update black holes
-        self.update_black_holes(delta_time);
-
-        // Update shield system
-        self.update_shield(delta_time);
-
-        // Check collisions
-        self.check_collisions();
-
-        // Clean up off-screen objects
-        self.cleanup();
-
-        // Level up
-        if self.score >= self.level * 1000 {
-            self.level += 1;
-        }
+    pub fn new_with_seed(width: f32, height: f32, seed: u64) -> GameEngine {
+        GameEngine::from_sim(Simulation::new(LOGICAL_WIDTH, LOGICAL_HEIGHT, seed), width, height)
     }
 
-    fn update_player(&mut self, delta_time: f32) {
-        // Update position
-        self.player.x += self.player.vx * delta_time * 200.0;
-        self.player.y += self.player.vy * delta_time * 200.0;
-
-        // Keep player in bounds
-        self.player.x = self.player.x.clamp(self.player.size, self.width - self.player.size);
-        self.player.y = self.player.y.clamp(self.player.size, self.height - self.player.size);
-
-        // Update shoot cooldown
-        if self.player.shoot_cooldown > 0.0 {
-            self.player.shoot_cooldown -= delta_time;
-        }
-
-        // Update black hole cooldown
-        if self.player.black_hole_cooldown > 0.0 {
-            self.player.black_hole_cooldown -= delta_time;
-        }
+    // New game at a difficulty preset: easy, normal, hard or nightmare
+    pub fn new_with_difficulty(width: f32, height: f32, difficulty: &str) -> Result<GameEngine, JsValue> {
+        let difficulty = Difficulty::from_name(difficulty).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        let config = GameConfig::default().with_difficulty(difficulty);
+        let sim = Simulation::with_config(LOGICAL_WIDTH, LOGICAL_HEIGHT, GameEngine::random_seed(), config);
+        Ok(GameEngine::from_sim(sim, width, height))
     }
 
-    fn spawn_enemy(&mut self) {
-        let enemy_type = if js_sys::Math::random() < 0.1 {
-            EnemyType::Tank
-        } else if js_sys::Math::random() < 0.3 {
-            EnemyType::Fast
-        } else {
-            EnemyType::Basic
-        };
-
-        let (size, health, speed) = match enemy_type {
-            EnemyType::Basic => (15.0, 20.0, 50.0),
-            EnemyType::Fast => (12.0, 15.0, 100.0),
-            EnemyType::Tank => (25.0, 50.0, 30.0),
-        };
-
-        let enemy = Enemy {
-            x: (js_sys::Math::random() as f32) * (self.width - 50.0) + 25.0,
-            y: -50.0,
-            vx: (js_sys::Math::random() as f32 - 0.5) * speed,
-            vy: speed,
-            health,
-            size,
-            enemy_type,
-            shoot_cooldown: 0.0,
-        };
-
-        self.enemies.push(enemy);
+    // New game tuned by a GameConfig in JSON. Fields left out keep their
+    // defaults, a config that doesn't parse or validate is an error
+    // saying which field is wrong.
+    pub fn new_with_config(width: f32, height: f32, config_json: &str) -> Result<GameEngine, JsValue> {
+        let config = GameConfig::from_json(config_json).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        let sim = Simulation::with_config(LOGICAL_WIDTH, LOGICAL_HEIGHT, GameEngine::random_seed(), config);
+        Ok(GameEngine::from_sim(sim, width, height))
     }
 
-    fn spawn_power_up(&mut self) {
-        let power_type = if js_sys::Math::random() < 0.4 {
-            PowerUpType::Health
-        } else if js_sys::Math::random() < 0.7 {
-            PowerUpType::Weapon
-        } else {
-            PowerUpType::Shield
-        };
-
-        let power_up = PowerUp {
-            x: (js_sys::Math::random() as f32) * (self.width - 30.0) + 15.0,
-            y: -30.0,
-            vy: 80.0,
-            size: 15.0,
-            power_type,
+    // Start a new run playing a stage timeline in JSON, see stage.rs for
+    // the format. A stage that doesn't parse or validate is an error and
+    // leaves the current run alone.
+    pub fn load_stage(&mut self, stage_json: &str) -> Result<(), JsValue> {
+        let stage = Stage::from_json(stage_json).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        // Path movements are checked against the current config's paths
+        let config = GameConfig {
+            stage: Some(stage.clone()),
+            ..self.sim.config.clone()
         };
+        config.validate().map_err(|e| JsValue::from_str(&e.to_string()))?;
+        self.sim.start_stage(Some(stage));
+        Ok(())
+    }
 
-        self.power_ups.push(power_up);
-    }
-
-    fn update_enemies(&mut self, delta_time: f32) {
-        for enemy in &mut self.enemies {
-            enemy.x += enemy.vx * delta_time;
-            enemy.y += enemy.vy * delta_time;
-
-            // Enemy shooting
-            if enemy.shoot_cooldown > 0.0 {
-                enemy.shoot_cooldown -= delta_time;
-            } else if js_sys::Math::random() < 0.01 {
-                self.enemy_bullets.push(Bullet {
-                    x: enemy.x,
-                    y: enemy.y + enemy.size,
-                    vx: 0.0,
-                    vy: 150.0,
-                    size: 5.0,
-                    damage: 10.0,
-                });
-                enemy.shoot_cooldown = 2.0;
-            }
-        }
+    // Start a new run with random endless spawning
+    pub fn clear_stage(&mut self) {
+        self.sim.start_stage(None);
     }
 
-    fn update_bullets(&mut self, delta_time: f32) {
-        for bullet in &mut self.bullets {
-            bullet.x += bullet.vx * delta_time;
-            bullet.y += bullet.vy * delta_time;
-        }
+    pub fn is_stage_complete(&self) -> bool {
+        self.sim.stage.complete
+    }
 
-        for bullet in &mut self.enemy_bullets {
-            bullet.x += bullet.vx * delta_time;
-            bullet.y += bullet.vy * delta_time;
-        }
+    // Spawn pressure from dynamic difficulty, 1 when it is off or hasn't
+    // needed to adjust
+    pub fn get_difficulty_factor(&self) -> f32 {
+        self.sim.difficulty.factor
     }
 
-    fn update_power_ups(&mut self, delta_time: f32) {
-        for power_up in &mut self.power_ups {
-            power_up.y += power_up.vy * delta_time;
-        }
+    // The config in use as JSON, every field included
+    pub fn get_config(&self) -> String {
+        self.sim.config.to_json()
     }
 
-    fn update_explosions(&mut self, delta_time: f32) {
-        for explosion in &mut self.explosions {
-            explosion.life -= delta_time;
-        }
+    // Start a frame-exact playback of a replay exported with export_replay.
+    // Replays keep the resolution they were recorded at, call resize with
+    // the canvas size afterwards.
+    pub fn from_replay(bytes: &[u8]) -> Result<GameEngine, JsValue> {
+        let replay = Replay::from_bytes(bytes).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        let sim = Simulation::from_replay(&replay).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        let (width, height) = (sim.width, sim.height);
+        Ok(GameEngine::from_sim(sim, width, height))
     }
 
-    fn update_black_holes(&mut self, delta_time: f32) {
-        for black_hole in &mut self.black_holes {
-            black_hole.life -= delta_time;
-
-            // Track enemies to remove (consumed by black hole)
-            let mut enemies_to_remove = HashSet::new();
-
-            // Pull enemies towards the black hole
-            for (enemy_idx, enemy) in self.enemies.iter_mut().enumerate() {
-                // Safety check: ensure enemy is valid
-                if enemy.health <= 0.0 || enemy.size <= 0.0 {
-                    enemies_to_remove.insert(enemy_idx);
-                    continue;
-                }
-
-                let dx = black_hole.x - enemy.x;
-                let dy = black_hole.y - enemy.y;
-                let distance = (dx * dx + dy * dy).sqrt();
-
-                if distance < black_hole.pull_radius {
-                    // Calculate pull force (stronger when closer)
-                    let pull_force = 200.0 * (1.0 - distance / black_hole.pull_radius);
-                    let normalized_dx = dx / distance;
-                    let normalized_dy = dy / distance;
-
-                    enemy.x += normalized_dx * pull_force * delta_time;
-                    enemy.y += normalized_dy * pull_force * delta_time;
-
-                    // If enemy is very close, consume it
-                    if distance < black_hole.size {
-                        black_hole.consumed_enemies.push((enemy.x, enemy.y));
-                        enemies_to_remove.insert(enemy_idx);
-                    }
-                }
-            }
-
-            // Convert HashSet to sorted Vec for safe removal
-            let mut enemies_to_remove_vec: Vec<usize> = enemies_to_remove.into_iter().collect();
-            enemies_to_remove_vec.sort_unstable_by(|a, b| b.cmp(a)); // Sort in descending order
-
-            // Remove consumed enemies
-            for &idx in &enemies_to_remove_vec {
-                if idx < self.enemies.len() {
-                    self.enemies.remove(idx);
-                }
-            }
-        }
+    pub fn export_replay(&self) -> Vec<u8> {
+        self.sim.replay().to_bytes()
     }
 
-    fn update_shield(&mut self, delta_time: f32) {
-        // Update shield timer
-        if self.shield_timer > 0.0 {
-            self.shield_timer -= delta_time;
-            if self.shield_timer <= 0.0 {
-                self.shield_active = false;
-                self.shield_timer = 0.0;
-            }
-        }
+    pub fn is_replay_finished(&self) -> bool {
+        self.sim.playback_finished()
     }
 
-    fn check_collisions(&mut self) {
-        // Player bullets vs enemies
-        let mut bullets_to_remove = Vec::new();
-        let mut bullet_enemies_to_remove = HashSet::new();
-
-        for (bullet_idx, bullet) in self.bullets.iter().enumerate() {
-            for (enemy_idx, enemy) in self.enemies.iter_mut().enumerate() {
-                // Safety check: ensure enemy is valid
-                if enemy.health <= 0.0 || enemy.size <= 0.0 {
-                    bullet_enemies_to_remove.insert(enemy_idx);
-                    continue;
-                }
-
-                let dx = bullet.x - enemy.x;
-                let dy = bullet.y - enemy.y;
-                let distance = (dx * dx + dy * dy).sqrt();
-
-                if distance < bullet.size + enemy.size {
-                    bullets_to_remove.push(bullet_idx);
-                    enemy.health -= bullet.damage;
-
-                    if enemy.health <= 0.0 {
-                        bullet_enemies_to_remove.insert(enemy_idx);
-                        self.score += match enemy.enemy_type {
-                            EnemyType::Basic => 100,
-                            EnemyType::Fast => 150,
-                            EnemyType::Tank => 300,
-                        };
-
-                        // Track enemies killed and update growth level
-                        self.player.enemies_killed += 1;
-                        let new_growth_level = (self.player.enemies_killed / 10).min(5);
-                        if new_growth_level > self.player.growth_level {
-                            self.player.growth_level = new_growth_level;
-                        }
-
-                        // Create explosion for tank enemies
-                        if enemy.enemy_type == EnemyType::Tank {
-                            self.explosions.push(Explosion {
-                                x: enemy.x,
-                                y: enemy.y,
-                                size: enemy.size * 2.0,
-                                life: 1.0,
-                                max_life: 1.0,
-                            });
-                        }
-                    }
-                    break; // Only hit one enemy per bullet
-                }
-            }
-        }
+    pub fn save_state(&self) -> Vec<u8> {
+        self.sim.save_state()
+    }
 
-                // Enemy bullets vs player
-        let mut enemy_bullets_to_remove = Vec::new();
-        for (bullet_idx, bullet) in self.enemy_bullets.iter().enumerate() {
-            let dx = bullet.x - self.player.x;
-            let dy = bullet.y - self.player.y;
-            let distance = (dx * dx + dy * dy).sqrt();
-
-            if distance < bullet.size + self.player.size {
-                enemy_bullets_to_remove.push(bullet_idx);
-
-                // Check if shield can block the bullet
-                if self.shield_active && self.shield_level > 0 {
-                    // Shield blocks the bullet completely
-                    // Reduce shield level by 1
-                    self.shield_level = self.shield_level.saturating_sub(1);
-                    if self.shield_level == 0 {
-                        self.shield_active = false;
-                        self.shield_timer = 0.0;
-                    }
-                } else {
-                    // No shield, take full damage
-                    self.player.health -= bullet.damage;
-
-                    // Reduce growth level when taking damage
-                    if self.player.growth_level > 0 {
-                        self.player.growth_level = self.player.growth_level.saturating_sub(1);
-                    }
-
-                    if self.player.health <= 0.0 {
-                        self.game_over = true;
-                    }
-                }
-            }
-        }
+    // Old or corrupt saves are rejected with an error and leave the
+    // current game untouched
+    pub fn load_state(&mut self, bytes: &[u8]) -> Result<(), JsValue> {
+        self.sim.load_state(bytes).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
 
-        // Remove bullets that hit the player
-        for &idx in enemy_bullets_to_remove.iter().rev() {
-            if idx < self.enemy_bullets.len() {
-                self.enemy_bullets.remove(idx);
-            }
+    // Canvas resized. Gameplay is unaffected, only the view transform
+    // changes. Empty sizes, e.g. from a minimised window, are ignored.
+    pub fn resize(&mut self, width: f32, height: f32) {
+        if width.is_finite() && height.is_finite() && width > 0.0 && height > 0.0 {
+            self.screen_width = width;
+            self.screen_height = height;
         }
+    }
 
-        // Enemies vs player
-        let mut enemies_to_remove = Vec::new();
-        for (enemy_idx, enemy) in self.enemies.iter().enumerate() {
-            // Safety check: ensure enemy is valid
-            if enemy.health <= 0.0 || enemy.size <= 0.0 {
-                enemies_to_remove.push(enemy_idx);
-                continue;
-            }
-
-            let dx = enemy.x - self.player.x;
-            let dy = enemy.y - self.player.y;
-            let distance = (dx * dx + dy * dy).sqrt();
-
-            if distance < enemy.size + self.player.size {
-                // Check if shield can block the enemy collision
-                if self.shield_active && self.shield_level > 0 {
-                    // Shield blocks the collision completely and destroys the enemy
-                    // For tank enemies, they might require more shield power
-                    let shield_cost = match enemy.enemy_type {
-                        EnemyType::Tank => 2, // Tank enemies cost 2 shield levels
-                        _ => 1, // Other enemies cost 1 shield level
-                    };
-
-                    if self.shield_level >= shield_cost {
-                        self.shield_level = self.shield_level.saturating_sub(shield_cost);
-                        if self.shield_level == 0 {
-                            self.shield_active = false;
-                            self.shield_timer = 0.0;
-                        }
-                        // Mark enemy for removal (shield destroyed it)
-                        enemies_to_remove.push(enemy_idx);
-                    } else {
-                        // Not enough shield power, take damage
-                        self.player.health -= 20.0;
-                        if self.player.growth_level > 0 {
-                            self.player.growth_level = self.player.growth_level.saturating_sub(1);
-                        }
-                        if self.player.health <= 0.0 {
-                            self.game_over = true;
-                        }
-                    }
-                } else {
-                    // No shield, take full damage
-                    self.player.health -= 20.0;
-
-                    // Reduce growth level when taking damage
-                    if self.player.growth_level > 0 {
-                        self.player.growth_level = self.player.growth_level.saturating_sub(1);
-                    }
-
-                    if self.player.health <= 0.0 {
-                        self.game_over = true;
-                    }
-                }
-            }
-        }
+    pub fn get_logical_width(&self) -> f32 {
+        self.sim.width
+    }
 
-        // Remove enemies that were destroyed by shield or are invalid
-        for &idx in enemies_to_remove.iter().rev() {
-            if idx < self.enemies.len() {
-                self.enemies.remove(idx);
-            }
-        }
+    pub fn get_logical_height(&self) -> f32 {
+        self.sim.height
+    }
 
-        // Power-ups vs player
-        let mut power_ups_to_remove = Vec::new();
-        for (power_up_idx, power_up) in self.power_ups.iter().enumerate() {
-            let dx = power_up.x - self.player.x;
-            let dy = power_up.y - self.player.y;
-            let distance = (dx * dx + dy * dy).sqrt();
-
-            if distance < power_up.size + self.player.size {
-                match power_up.power_type {
-                    PowerUpType::Health => {
-                        self.player.max_health += 20.0; // Increase max health
-                        self.player.health = (self.player.health + 30.0).min(self.player.max_health);
-                    }
-                    PowerUpType::Weapon => {
-                        self.player.power_level = (self.player.power_level + 1).min(3);
-                    }
-                    PowerUpType::Shield => {
-                        // Increase shield level (separate from health)
-                        self.shield_level = (self.shield_level + 1).min(3);
-                        self.shield_active = true;
-                        self.shield_timer = 10.0; // 10 seconds duration
-                        // No health bonus - shield is separate system
-                    }
-                }
-                power_ups_to_remove.push(power_up_idx);
-            }
-        }
+    // World to screen transform as [a, b, c, d, e, f], ready for the 2D
+    // context's setTransform. Render data is in world coordinates.
+    pub fn get_view_transform(&self) -> Vec<f32> {
+        let view = self.view_transform();
+        vec![view.scale, 0.0, 0.0, view.scale, view.offset_x, view.offset_y]
+    }
 
-        // Remove collided objects
-        for &idx in bullets_to_remove.iter().rev() {
-            if idx < self.bullets.len() {
-                self.bullets.remove(idx);
-            }
-        }
+    pub fn update(&mut self, delta_time: f32) {
+        self.sim.advance(delta_time);
+    }
 
-        // Convert HashSet to sorted Vec for safe removal
-        let mut bullet_enemies_to_remove_vec: Vec<usize> = bullet_enemies_to_remove.into_iter().collect();
-        bullet_enemies_to_remove_vec.sort_unstable_by(|a, b| b.cmp(a)); // Sort in descending order
+    pub fn set_fixed_timestep(&mut self, enabled: bool) {
+        self.sim.set_fixed_timestep(enabled);
+    }
 
-        for &idx in &bullet_enemies_to_remove_vec {
-            if idx < self.enemies.len() {
-                self.enemies.remove(idx);
-            }
-        }
-        for &idx in power_ups_to_remove.iter().rev() {
-            if idx < self.power_ups.len() {
-                self.power_ups.remove(idx);
-            }
-        }
+    pub fn pause(&mut self) {
+        self.sim.pause();
     }
 
-    fn cleanup(&mut self) {
-        // Remove off-screen bullets
-        self.bullets.retain(|bullet| bullet.y > -50.0 && bullet.y < self.height + 50.0);
-        self.enemy_bullets.retain(|bullet| bullet.y > -50.0 && bullet.y < self.height + 50.0);
+    pub fn resume(&mut self) {
+        self.sim.resume();
+    }
 
-        // Remove off-screen enemies and invalid enemies
-        self.enemies.retain(|enemy| enemy.y < self.height + 100.0 && enemy.health > 0.0 && enemy.size > 0.0);
+    pub fn is_paused(&self) -> bool {
+        self.sim.is_paused()
+    }
 
-        // Remove off-screen power-ups
-        self.power_ups.retain(|power_up| power_up.y < self.height + 50.0);
+    // 1 is normal speed, 0.5 half speed, up to 4
+    pub fn set_time_scale(&mut self, scale: f32) {
+        self.sim.set_time_scale(scale);
+    }
 
-        // Remove dead explosions
-        self.explosions.retain(|explosion| explosion.life > 0.0);
+    pub fn get_time_scale(&self) -> f32 {
+        self.sim.time_scale()
+    }
 
-        // Remove dead black holes and create explosions
-        let mut black_holes_to_remove = Vec::new();
-        for (i, black_hole) in self.black_holes.iter().enumerate() {
-            if black_hole.life <= 0.0 {
-                black_holes_to_remove.push(i);
+    // Pause on frames longer than max_frame_time seconds, 0 turns it off
+    pub fn set_auto_pause(&mut self, max_frame_time: f32) {
+        self.sim.set_auto_pause(max_frame_time);
+    }
 
-                // Create massive explosion when black hole expires
-                let explosion_size = black_hole.size * 3.0 + black_hole.consumed_enemies.len() as f32 * 10.0;
-                self.explosions.push(Explosion {
-                    x: black_hole.x,
-                    y: black_hole.y,
-                    size: explosion_size,
-                    life: 2.0, // Longer explosion
-                    max_life: 2.0,
-                });
+    pub fn get_interpolation_alpha(&self) -> f32 {
+        self.sim.interpolation_alpha()
+    }
 
-                // Add score for consumed enemies
-                self.score += black_hole.consumed_enemies.len() as u32 * 200;
-            }
-        }
+    // Queue input for the next tick to be simulated
+    pub fn queue_input(&mut self, move_x: f32, move_y: f32, fire: bool, black_hole: bool) {
+        let tick = self.sim.tick;
+        self.queue_input_at(tick, move_x, move_y, fire, black_hole);
+    }
 
-        // Remove dead black holes
-        for &idx in black_holes_to_remove.iter().rev() {
-            if idx < self.black_holes.len() {
-                self.black_holes.remove(idx);
-            }
-        }
+    pub fn queue_input_at(&mut self, tick: u32, move_x: f32, move_y: f32, fire: bool, black_hole: bool) {
+        self.sim.queue_input(
+            tick,
+            InputFrame {
+                move_x,
+                move_y,
+                fire,
+                black_hole,
+            },
+        );
     }
 
-    pub fn move_player(&mut self, dx: f32, dy: f32) {
-        self.player.vx = dx;
-        self.player.vy = dy;
-    }
-
-    pub fn shoot(&mut self) {
-        if self.player.shoot_cooldown <= 0.0 {
-            let bullet_speed = 300.0;
-            let bullet_size = 8.0;
-            let bullet_damage = 25.0 * self.player.power_level as f32;
-
-            match self.player.power_level {
-                1 => {
-                    self.bullets.push(Bullet {
-                        x: self.player.x,
-                        y: self.player.y - self.player.size,
-                        vx: 0.0,
-                        vy: -bullet_speed,
-                        size: bullet_size,
-                        damage: bullet_damage,
-                    });
-                }
-                2 => {
-                    self.bullets.push(Bullet {
-                        x: self.player.x - 10.0,
-                        y: self.player.y - self.player.size,
-                        vx: 0.0,
-                        vy: -bullet_speed,
-                        size: bullet_size,
-                        damage: bullet_damage,
-                    });
-                    self.bullets.push(Bullet {
-                        x: self.player.x + 10.0,
-                        y: self.player.y - self.player.size,
-                        vx: 0.0,
-                        vy: -bullet_speed,
-                        size: bullet_size,
-                        damage: bullet_damage,
-                    });
-                }
-                3 => {
-                    for i in -1..=1 {
-                        self.bullets.push(Bullet {
-                            x: self.player.x + i as f32 * 15.0,
-                            y: self.player.y - self.player.size,
-                            vx: i as f32 * 50.0,
-                            vy: -bullet_speed,
-                            size: bullet_size,
-                            damage: bullet_damage,
-                        });
-                    }
-                }
-                _ => {}
-            }
-
-            self.player.shoot_cooldown = 0.2;
-        }
+    // Keep this many player bullets (and bullets / 20 enemies) alive to
+    // profile collisions, 0 turns it off
+    pub fn set_stress_mode(&mut self, bullets: u32) {
+        self.sim.set_stress_mode(bullets);
     }
 
-    pub fn activate_black_hole(&mut self) {
-        if self.player.black_hole_cooldown <= 0.0 {
-            // Calculate target position in front of player
-            let target_distance = 200.0; // Distance in front of player
-            let target_x = self.player.x;
-            let target_y = self.player.y - target_distance; // Shoot upward
-
-            // Create black hole at target position
-            self.black_holes.push(BlackHole {
-                x: target_x,
-                y: target_y,
-                size: 30.0,
-                life: 3.0, // 3 seconds duration
-                max_life: 3.0,
-                pull_radius: 150.0, // Large pull radius
-                consumed_enemies: Vec::new(),
-            });
-
-            self.player.black_hole_cooldown = 10.0; // 10 second cooldown as requested
-        }
+    pub fn get_tick(&self) -> u32 {
+        self.sim.tick
     }
 
-    pub fn get_game_data(&self) -> Float32Array {
-        let mut data = Vec::new();
-
-        // Add metadata: [player_count, enemy_count, player_bullet_count, enemy_bullet_count, power_up_count, explosion_count, black_hole_count]
-        data.push(1.0); // player_count
-        data.push(self.enemies.len() as f32);
-        data.push(self.bullets.len() as f32);
-        data.push(self.enemy_bullets.len() as f32);
-        data.push(self.power_ups.len() as f32);
-        data.push(self.explosions.len() as f32);
-        data.push(self.black_holes.len() as f32);
-
-        // Player data (x, y, size, health, power_level, growth_level)
-        data.push(self.player.x);
-        data.push(self.player.y);
-        data.push(self.player.size);
-        data.push(self.player.health);
-        data.push(self.player.power_level as f32);
-        data.push(self.player.growth_level as f32);
-
-        // Enemies data (x, y, size, health, type)
-        for enemy in &self.enemies {
-            data.push(enemy.x);
-            data.push(enemy.y);
-            data.push(enemy.size);
-            data.push(enemy.health);
-            data.push(match enemy.enemy_type {
-                EnemyType::Basic => 0.0,
-                EnemyType::Fast => 1.0,
-                EnemyType::Tank => 2.0,
-            });
-        }
+    pub fn get_render_schema_version(&self) -> u32 {
+        RENDER_SCHEMA_VERSION
+    }
 
-        // Player bullets data (x, y, size, is_enemy)
-        for bullet in &self.bullets {
-            data.push(bullet.x);
-            data.push(bullet.y);
-            data.push(bullet.size);
-            data.push(0.0); // Player bullet
-        }
+    // Channel ids, names, strides and field names as JSON
+    pub fn get_render_schema(&self) -> String {
+        render::schema_json()
+    }
 
-        // Enemy bullets data (x, y, size, is_enemy)
-        for bullet in &self.enemy_bullets {
-            data.push(bullet.x);
-            data.push(bullet.y);
-            data.push(bullet.size);
-            data.push(1.0); // Enemy bullet
-        }
+    // Refresh every render channel. Read a channel from JS with
+    // new Float32Array(memory.buffer, get_channel_ptr(id), count * stride).
+    // Views are only valid until the next call into the engine that may
+    // allocate, since growing wasm memory detaches them.
+    pub fn update_render_data(&mut self) {
+        self.sim.write_render_data(&mut self.render_data);
+    }
 
-        // Power-ups data (x, y, size, type)
-        for power_up in &self.power_ups {
-            data.push(power_up.x);
-            data.push(power_up.y);
-            data.push(power_up.size);
-            data.push(match power_up.power_type {
-                PowerUpType::Health => 0.0,
-                PowerUpType::Weapon => 1.0,
-                PowerUpType::Shield => 2.0,
-            });
+    pub fn get_channel_ptr(&self, channel: usize) -> *const f32 {
+        match self.render_data.channels.get(channel) {
+            Some(data) => data.as_ptr(),
+            None => std::ptr::null(),
         }
+    }
 
-        // Explosions data (x, y, size, life_ratio)
-        for explosion in &self.explosions {
-            data.push(explosion.x);
-            data.push(explosion.y);
-            data.push(explosion.size);
-            data.push(explosion.life / explosion.max_life);
+    // Number of entities in a channel
+    pub fn get_channel_count(&self, channel: usize) -> usize {
+        if channel < CHANNEL_COUNT {
+            self.render_data.count(channel)
+        } else {
+            0
         }
+    }
 
-        // Black holes data (x, y, size, life_ratio, pull_radius)
-        for black_hole in &self.black_holes {
-            data.push(black_hole.x);
-            data.push(black_hole.y);
-            data.push(black_hole.size);
-            data.push(black_hole.life / black_hole.max_life);
-            data.push(black_hole.pull_radius);
+    // Safe alternative to the pointer API, copies the channel into a new
+    // JS-owned array
+    pub fn get_channel_data(&self, channel: usize) -> Float32Array {
+        match self.render_data.channels.get(channel) {
+            Some(data) => Float32Array::from(data.as_slice()),
+            None => Float32Array::new_with_length(0),
         }
-
-        unsafe { Float32Array::view(&data) }
     }
 
     pub fn get_score(&self) -> u32 {
-        self.score
+        self.sim.score
     }
 
     pub fn get_level(&self) -> u32 {
-        self.level
+        self.sim.level
     }
 
     pub fn get_health(&self) -> f32 {
-        self.player.health
+        self.sim.player.health
+    }
+
+    pub fn get_max_health(&self) -> f32 {
+        self.sim.player.max_health
     }
 
     pub fn is_game_over(&self) -> bool {
-        self.game_over
+        self.sim.game_over
     }
 
     pub fn get_black_hole_cooldown(&self) -> f32 {
-        self.player.black_hole_cooldown
+        self.sim.player.black_hole_cooldown
     }
 
     pub fn get_shield_level(&self) -> u32 {
-        self.shield_level
+        self.sim.shield_level
+    }
+
+    pub fn get_max_shield_level(&self) -> u32 {
+        self.sim.config.shield.max_level
     }
 
     pub fn is_shield_active(&self) -> bool {
-        self.shield_active
+        self.sim.shield_active
     }
 
     pub fn get_shield_timer(&self) -> f32 {
-        self.shield_timer
+        self.sim.shield_timer
+    }
+
+    pub fn get_seed(&self) -> u64 {
+        self.sim.seed()
     }
 
-    pub fn get_explosion_events(&self) -> Vec<f32> {
-        // Return explosion events: [type, x, y] where type: 0=tank, 1=blackhole
-        let events = Vec::new();
+    pub fn get_rng_state(&self) -> u64 {
+        self.sim.rng_state()
+    }
+
+    // Drain all events since the last call, EVENT_STRIDE floats each:
+    // [kind, x, y, a, b], see events.rs for the kinds and fields
+    pub fn drain_events(&mut self) -> Vec<f32> {
+        let events = self.sim.drain_events();
+        let mut data = Vec::with_capacity(events.len() * EVENT_STRIDE);
+        for event in &events {
+            event.encode(&mut data);
+        }
+        data
+    }
 
-        // Check for new tank explosions (we'll track this in the game state)
-        // For now, we'll return empty and handle this differently
-        events
+    pub fn get_event_stride(&self) -> usize {
+        EVENT_STRIDE
     }
 
     pub fn reset(&mut self) {
-        self.player = Player {
-            x: self.width / 2.0,
-            y: self.height - 100.0,
-            vx: 0.0,
-            vy: 0.0,
-            health: 100.0,
-            max_health: 100.0,
-            size: 20.0,
-            shoot_cooldown: 0.0,
-            power_level: 1,
-            growth_level: 0,
-            enemies_killed: 0,
-            black_hole_cooldown: 0.0,
-        };
-        self.enemies.clear();
-        self.bullets.clear();
-        self.enemy_bullets.clear();
-        self.power_ups.clear();
-        self.score = 0;
-        self.level = 1;
-        self.game_time = 0.0;
-        self.enemy_spawn_timer = 0.0;
-        self.power_up_spawn_timer = 0.0;
-        self.game_over = false;
-        // Reset shield system
-        self.shield_level = 0;
-        self.shield_active = false;
-        self.shield_timer = 0.0;
-    }
-}
\ No newline at end of file
+        self.sim.reset();
+    }
+}
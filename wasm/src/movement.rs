@@ -0,0 +1,195 @@
+use std::f32::consts::PI;
+
+use serde::{Deserialize, Serialize};
+
+use crate::bullets::BulletPool;
+use crate::config::ConfigError;
+use crate::entities::Enemy;
+use crate::spatial::SpatialGrid;
+
+// How an enemy moves. Set per enemy type in the config and per spawn
+// group in a stage, e.g. "drift", { "sine": { "amplitude": 40,
+// "frequency": 0.5 } } or { "path": 0 }. Distances are in pixels and
+// speeds in pixels per second.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum Movement {
+    // Straight down at the enemy's speed
+    #[default]
+    Straight,
+    // Down with a random sideways drift, like randomly spawned enemies
+    Drift,
+    // Down while weaving side to side around the spawn point, frequency
+    // in weaves per second
+    Sine { amplitude: f32, frequency: f32 },
+    // Down until delay seconds after spawning, then straight at the
+    // player. Once past the player it keeps going the same way.
+    Dive {
+        speed: f32,
+        #[serde(default)]
+        delay: f32,
+    },
+    // Down to the line at y, then side to side along it for hold
+    // seconds before leaving downwards. A hold of 0 stays for good.
+    Strafe {
+        y: f32,
+        speed: f32,
+        #[serde(default)]
+        hold: f32,
+    },
+    // Along config.paths[n] from the spawn point, then on in the
+    // direction it ends in
+    Path(u32),
+}
+
+// A curve for enemies to follow: cubic bezier segments laid end to end,
+// so the points are start, control, control, end, control, control,
+// end... Points are relative to where the enemy spawns.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Path {
+    pub points: Vec<[f32; 2]>,
+    // Seconds to follow the whole path
+    pub duration: f32,
+}
+
+impl Path {
+    // Position at t from 0 to 1 along the whole path, each segment taking
+    // the same time
+    pub fn point_at(&self, t: f32) -> (f32, f32) {
+        let segments = (self.points.len() - 1) / 3;
+        let t = t.clamp(0.0, 1.0) * segments as f32;
+        let segment = (t as usize).min(segments - 1);
+        let t = t - segment as f32;
+
+        let [p0, p1, p2, p3] = [0, 1, 2, 3].map(|i| self.points[segment * 3 + i]);
+        let u = 1.0 - t;
+        let (a, b, c, d) = (u * u * u, 3.0 * u * u * t, 3.0 * u * t * t, t * t * t);
+        (
+            a * p0[0] + b * p1[0] + c * p2[0] + d * p3[0],
+            a * p0[1] + b * p1[1] + c * p2[1] + d * p3[1],
+        )
+    }
+
+    pub fn validate(&self, field: &str) -> Result<(), ConfigError> {
+        if self.points.len() < 4 || !(self.points.len() - 1).is_multiple_of(3) {
+            return Err(ConfigError::Invalid(
+                format!("{}.points", field),
+                "must be a start point and three more for each segment",
+            ));
+        }
+        if self.points.iter().flatten().any(|v| !v.is_finite()) {
+            return Err(ConfigError::Invalid(format!("{}.points", field), "must be numbers"));
+        }
+        if !(self.duration.is_finite() && self.duration > 0.0) {
+            return Err(ConfigError::Invalid(format!("{}.duration", field), "must be a positive number"));
+        }
+        Ok(())
+    }
+}
+
+impl Movement {
+    // Checks everything but path numbers, which need the config's paths
+    pub fn validate(&self, field: &str) -> Result<(), ConfigError> {
+        let check = |name: &str, value: f32, ok: bool, reason| {
+            if value.is_finite() && ok {
+                Ok(())
+            } else {
+                Err(ConfigError::Invalid(format!("{}.{}", field, name), reason))
+            }
+        };
+        match *self {
+            Movement::Straight | Movement::Drift | Movement::Path(_) => Ok(()),
+            Movement::Sine { amplitude, frequency } => {
+                check("sine.amplitude", amplitude, amplitude >= 0.0, "must be zero or more")?;
+                check("sine.frequency", frequency, frequency > 0.0, "must be a positive number")
+            }
+            Movement::Dive { speed, delay } => {
+                check("dive.speed", speed, speed > 0.0, "must be a positive number")?;
+                check("dive.delay", delay, delay >= 0.0, "must be zero or more")
+            }
+            Movement::Strafe { y, speed, hold } => {
+                check("strafe.y", y, true, "must be a number")?;
+                check("strafe.speed", speed, speed >= 0.0, "must be zero or more")?;
+                check("strafe.hold", hold, hold >= 0.0, "must be zero or more")
+            }
+        }
+    }
+}
+
+// What steering and AI need to know besides the enemy itself
+pub struct Surroundings<'a> {
+    pub paths: &'a [Path],
+    pub player_x: f32,
+    pub player_y: f32,
+    // Player bullets, for enemies that dodge them, and a grid over them
+    // built this tick when any type does
+    pub bullets: &'a BulletPool,
+    pub bullet_grid: &'a SpatialGrid,
+    pub width: f32,
+}
+
+// Sets the enemy's velocity for the coming tick from its movement.
+// speed is its type's speed. Curves are followed by the change in
+// position over the tick, so the ticks add up to the exact curve. A tick
+// with no time keeps the last velocity, which moves nothing.
+pub fn steer(enemy: &mut Enemy, speed: f32, around: &Surroundings, delta_time: f32) {
+    if delta_time <= 0.0 {
+        return;
+    }
+    let t0 = enemy.movement_time;
+    enemy.movement_time += delta_time;
+    let t1 = enemy.movement_time;
+
+    match enemy.movement {
+        Movement::Straight | Movement::Drift => {}
+        Movement::Sine { amplitude, frequency } => {
+            let w = 2.0 * PI * frequency;
+            enemy.vx = amplitude * ((w * t1).sin() - (w * t0).sin()) / delta_time;
+            enemy.vy = speed;
+        }
+        Movement::Dive { speed: dive_speed, delay } => {
+            if t1 < delay || enemy.y >= around.player_y {
+                return;
+            }
+            let (dx, dy) = (around.player_x - enemy.x, around.player_y - enemy.y);
+            let distance = (dx * dx + dy * dy).sqrt().max(1.0);
+            enemy.vx = dx / distance * dive_speed;
+            enemy.vy = dy / distance * dive_speed;
+        }
+        Movement::Strafe { y, speed: strafe_speed, hold } => {
+            if enemy.held == 0.0 && enemy.y < y - 0.01 {
+                // Still coming down, landing on the line exactly
+                enemy.vx = 0.0;
+                enemy.vy = ((y - enemy.y) / delta_time).min(speed);
+            } else if hold > 0.0 && enemy.held >= hold {
+                enemy.vx = 0.0;
+                enemy.vy = speed;
+            } else {
+                enemy.held += delta_time;
+                enemy.vy = 0.0;
+                enemy.vx = if enemy.vx < 0.0 { -strafe_speed } else { strafe_speed };
+                if enemy.x < enemy.size {
+                    enemy.vx = strafe_speed;
+                } else if enemy.x > around.width - enemy.size {
+                    enemy.vx = -strafe_speed;
+                }
+            }
+        }
+        Movement::Path(index) => {
+            let Some(path) = around.paths.get(index as usize) else {
+                return;
+            };
+            // Past the end, carry on at the speed it ended with
+            let (t0, t1) = if t0 < path.duration {
+                (t0, t1)
+            } else {
+                (path.duration - delta_time, path.duration)
+            };
+            let (x0, y0) = path.point_at(t0 / path.duration);
+            let (x1, y1) = path.point_at(t1 / path.duration);
+            enemy.vx = (x1 - x0) / delta_time;
+            enemy.vy = (y1 - y0) / delta_time;
+        }
+    }
+}
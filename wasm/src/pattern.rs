@@ -0,0 +1,244 @@
+use std::f32::consts::PI;
+
+use serde::{Deserialize, Serialize};
+
+use crate::bullets::BulletPool;
+use crate::config::GameConfig;
+use crate::entities::{Bullet, Player};
+use crate::ids::IdAllocator;
+use crate::rng::Rng;
+use crate::sim::spawn;
+
+// A bullet pattern: the shape of one volley and how volleys are aimed,
+// turned and grouped. Shapes and modifiers combine, so a spiral is a
+// single shot that spins, and a rotating ring is a ring that spins. e.g.
+//
+// { "shape": { "fan": { "count": 5, "angle": 60 } }, "aim": "player",
+//   "burst": 3, "burst_interval": 0.1, "cooldown": 2, "bullet_speed": 150 }
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Pattern {
+    pub shape: Shape,
+    #[serde(default)]
+    pub aim: Aim,
+    // Degrees per second the whole pattern turns, positive is from
+    // straight down towards the right
+    #[serde(default)]
+    pub spin: f32,
+    // Degrees an aimed volley can miss by either way, narrowed as levels
+    // go up by enemy_fire.spread_level_factor
+    #[serde(default)]
+    pub spread: f32,
+    // Volleys per burst, and seconds between the volleys of a burst
+    #[serde(default = "one")]
+    pub burst: u32,
+    #[serde(default)]
+    pub burst_interval: f32,
+    // Seconds from the end of one burst to the next
+    pub cooldown: f32,
+    pub bullet_speed: f32,
+}
+
+// Angles are in degrees
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum Shape {
+    Single,
+    // Evenly spread over angle, centred on the aim
+    Fan { count: u32, angle: f32 },
+    // Evenly spread all the way round, starting at the aim
+    Ring { count: u32 },
+}
+
+// Where the middle of a volley points
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Aim {
+    #[default]
+    Down,
+    // At the player's position when the volley is fired
+    Player,
+    // Where the player will be when the bullets get there, if they keep
+    // moving the same way
+    Lead,
+}
+
+fn one() -> u32 {
+    1
+}
+
+impl Pattern {
+    // Direction of every bullet in a volley, in radians with 0 straight
+    // down. aim is the direction of the player, or of where they will be
+    // for leading patterns.
+    pub fn directions(&self, rotation: f32, aim: f32, out: &mut Vec<f32>) {
+        out.clear();
+        let base = match self.aim {
+            Aim::Down => 0.0,
+            Aim::Player | Aim::Lead => aim,
+        } + rotation.to_radians();
+
+        match self.shape {
+            Shape::Single => out.push(base),
+            Shape::Fan { count, angle } => {
+                let spread = angle.to_radians();
+                out.extend((0..count).map(|i| {
+                    let t = if count > 1 { i as f32 / (count - 1) as f32 - 0.5 } else { 0.0 };
+                    base + t * spread
+                }));
+            }
+            Shape::Ring { count } => {
+                out.extend((0..count).map(|i| base + i as f32 * 2.0 * PI / count as f32));
+            }
+        }
+    }
+}
+
+// Firing state of one gun running a pattern
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Emitter {
+    // Seconds until the next volley
+    pub timer: f32,
+    // Volleys left in the burst being fired, 0 between bursts
+    pub burst_left: u32,
+    // Degrees the pattern has turned so far
+    pub rotation: f32,
+}
+
+impl Emitter {
+    // Ready to fire after a full cooldown
+    pub fn new(pattern: &Pattern, fire_rate: f32) -> Emitter {
+        Emitter {
+            timer: pattern.cooldown / fire_rate,
+            ..Emitter::default()
+        }
+    }
+
+    // Returns true when a volley is due this tick. fire_rate scales the
+    // cooldown between bursts.
+    pub fn update(&mut self, pattern: &Pattern, delta_time: f32, fire_rate: f32) -> bool {
+        self.rotation = (self.rotation + pattern.spin * delta_time) % 360.0;
+        self.timer -= delta_time;
+        if self.timer > 0.0 {
+            return false;
+        }
+
+        if self.burst_left == 0 {
+            self.burst_left = pattern.burst;
+        }
+        self.burst_left -= 1;
+        let wait = if self.burst_left > 0 {
+            pattern.burst_interval
+        } else {
+            pattern.cooldown / fire_rate
+        };
+        // Keep the rhythm of fast patterns, but never owe more than a volley
+        self.timer = (self.timer + wait).max(0.0);
+        true
+    }
+}
+
+// Fires patterns into the enemy bullet pool, all at one target
+pub struct Gun<'a> {
+    pub bullets: &'a mut BulletPool,
+    pub ids: &'a mut IdAllocator,
+    pub target_x: f32,
+    pub target_y: f32,
+    // Per second, for leading patterns
+    pub target_vx: f32,
+    pub target_vy: f32,
+    // Bullet speeds are multiplied by speed_scale
+    pub speed_scale: f32,
+    // Pattern spreads are multiplied by spread_scale
+    pub spread_scale: f32,
+    pub size: f32,
+    pub damage: f32,
+    // Reused list of volley directions
+    pub directions: &'a mut Vec<f32>,
+    // Picks how far each aimed volley misses
+    pub rng: &'a mut Rng,
+}
+
+impl<'a> Gun<'a> {
+    // Enemy fire at the player, scaled for difficulty and more accurate
+    // at higher levels
+    pub fn at_player(
+        player: &Player,
+        config: &GameConfig,
+        level: u32,
+        bullets: &'a mut BulletPool,
+        ids: &'a mut IdAllocator,
+        directions: &'a mut Vec<f32>,
+        rng: &'a mut Rng,
+    ) -> Gun<'a> {
+        Gun {
+            bullets,
+            ids,
+            target_x: player.x,
+            target_y: player.y,
+            target_vx: player.vx * config.player.speed,
+            target_vy: player.vy * config.player.speed,
+            speed_scale: config.difficulty.bullet_speed,
+            spread_scale: config.enemy_fire.spread_scale(level),
+            size: config.enemy_fire.bullet_size,
+            damage: config.enemy_fire.bullet_damage * config.difficulty.damage,
+            directions,
+            rng,
+        }
+    }
+
+    pub fn fire(&mut self, pattern: &Pattern, rotation: f32, x: f32, y: f32) {
+        let speed = pattern.bullet_speed * self.speed_scale;
+        let (mut dx, mut dy) = (self.target_x - x, self.target_y - y);
+        if pattern.aim == Aim::Lead {
+            let time = intercept_time(dx, dy, self.target_vx, self.target_vy, speed);
+            dx += self.target_vx * time;
+            dy += self.target_vy * time;
+        }
+        let mut aim = dx.atan2(dy);
+        let spread = pattern.spread * self.spread_scale;
+        if pattern.aim != Aim::Down && spread > 0.0 {
+            aim += (self.rng.next_f32() * 2.0 - 1.0) * spread.to_radians();
+        }
+        pattern.directions(rotation, aim, self.directions);
+
+        for &angle in self.directions.iter() {
+            let (vx, vy) = (angle.sin() * speed, angle.cos() * speed);
+            spawn(self.bullets, self.ids, |id| Bullet {
+                id,
+                x,
+                y,
+                prev_x: x,
+                prev_y: y,
+                vx,
+                vy,
+                size: self.size,
+                damage: self.damage,
+            });
+        }
+    }
+}
+
+// Seconds until a bullet at speed fired at a target (dx, dy) away and
+// moving at (vx, vy) meets it, or 0 when it never can
+pub fn intercept_time(dx: f32, dy: f32, vx: f32, vy: f32, speed: f32) -> f32 {
+    // |d + v t| = speed t, a quadratic in t
+    let a = vx * vx + vy * vy - speed * speed;
+    let b = 2.0 * (dx * vx + dy * vy);
+    let c = dx * dx + dy * dy;
+    if a.abs() < 1e-6 {
+        return if b < 0.0 { -c / b } else { 0.0 };
+    }
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return 0.0;
+    }
+    let root = discriminant.sqrt();
+    let (t1, t2) = ((-b - root) / (2.0 * a), (-b + root) / (2.0 * a));
+    let (first, second) = (t1.min(t2), t1.max(t2));
+    if first > 0.0 {
+        first
+    } else {
+        second.max(0.0)
+    }
+}
@@ -0,0 +1,114 @@
+use std::ops::{Deref, DerefMut};
+
+// Fixed-capacity entity storage. All memory is allocated up front, so
+// spawning never allocates, and removal swaps the last entity into the
+// hole instead of shifting everything after it. Order is not kept.
+#[derive(Clone)]
+pub struct Pool<T> {
+    items: Vec<T>,
+    limit: usize,
+}
+
+impl<T> Pool<T> {
+    pub fn new(limit: usize) -> Pool<T> {
+        Pool {
+            items: Vec::with_capacity(limit),
+            limit,
+        }
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.items.len() >= self.limit
+    }
+
+    // Returns false and drops the item when the pool is full
+    pub fn push(&mut self, item: T) -> bool {
+        if self.is_full() {
+            return false;
+        }
+        self.items.push(item);
+        true
+    }
+
+    pub fn swap_remove(&mut self, index: usize) -> T {
+        self.items.swap_remove(index)
+    }
+
+    // Remove several entities at once. Indices must be in descending
+    // order, so every swapped-in entity is one that stays.
+    pub fn remove_descending(&mut self, indices: &[usize], mut removed: impl FnMut(T)) {
+        for &index in indices {
+            if index < self.items.len() {
+                removed(self.items.swap_remove(index));
+            }
+        }
+    }
+
+    // Keep the entities keep returns true for, removed ones go to removed
+    pub fn retain(&mut self, mut keep: impl FnMut(&T) -> bool, mut removed: impl FnMut(T)) {
+        let mut i = 0;
+        while i < self.items.len() {
+            if keep(&self.items[i]) {
+                i += 1;
+            } else {
+                removed(self.items.swap_remove(i));
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+    }
+}
+
+// Fixed-capacity storage an entity can be spawned into
+pub trait Storage<T> {
+    fn is_full(&self) -> bool;
+    fn push(&mut self, item: T) -> bool;
+}
+
+impl<T> Storage<T> for Pool<T> {
+    fn is_full(&self) -> bool {
+        Pool::is_full(self)
+    }
+
+    fn push(&mut self, item: T) -> bool {
+        Pool::push(self, item)
+    }
+}
+
+impl<T> Deref for Pool<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        &self.items
+    }
+}
+
+impl<T> DerefMut for Pool<T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        &mut self.items
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Pool<T> {
+    type Item = &'a T;
+    type IntoIter = std::slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut Pool<T> {
+    type Item = &'a mut T;
+    type IntoIter = std::slice::IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.iter_mut()
+    }
+}
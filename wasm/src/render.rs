@@ -0,0 +1,406 @@
+use crate::ability::Ability;
+use crate::events::{enemy_type_id, power_type_id};
+use crate::sim::{
+    Simulation, MAX_BLACK_HOLES, MAX_BOSS_PARTS, MAX_ENEMIES, MAX_ENEMY_BULLETS, MAX_EXPLOSIONS, MAX_MINES,
+    MAX_PLAYER_BULLETS, MAX_POWER_UPS,
+};
+
+// Bump when a field changes meaning or fields are reordered. Appending a
+// field to a channel doesn't need a bump, renderers look fields up by
+// name from the schema and step by its stride.
+pub const RENDER_SCHEMA_VERSION: u32 = 1;
+
+pub const CHANNEL_PLAYER: usize = 0;
+pub const CHANNEL_ENEMIES: usize = 1;
+pub const CHANNEL_PLAYER_BULLETS: usize = 2;
+pub const CHANNEL_ENEMY_BULLETS: usize = 3;
+pub const CHANNEL_POWER_UPS: usize = 4;
+pub const CHANNEL_EXPLOSIONS: usize = 5;
+pub const CHANNEL_BLACK_HOLES: usize = 6;
+pub const CHANNEL_BOSS: usize = 7;
+pub const CHANNEL_BOSS_PARTS: usize = 8;
+pub const CHANNEL_MINES: usize = 9;
+pub const CHANNEL_COUNT: usize = 10;
+
+pub struct ChannelSchema {
+    pub name: &'static str,
+    pub fields: &'static [&'static str],
+}
+
+impl ChannelSchema {
+    pub fn stride(&self) -> usize {
+        self.fields.len()
+    }
+}
+
+const BULLET_FIELDS: &[&str] = &[
+    "x",
+    "y",
+    "prev_x",
+    "prev_y",
+    "vx",
+    "vy",
+    "size",
+    "damage",
+    "id",
+    "generation",
+];
+
+// Indexed by channel id
+pub const CHANNELS: [ChannelSchema; CHANNEL_COUNT] = [
+    ChannelSchema {
+        name: "player",
+        fields: &[
+            "x",
+            "y",
+            "prev_x",
+            "prev_y",
+            "vx",
+            "vy",
+            "size",
+            "health",
+            "max_health",
+            "power_level",
+            "growth_level",
+            "shield_level",
+            "shield_active",
+            "shield_timer",
+            "black_hole_cooldown",
+        ],
+    },
+    // type is EnemyType::id, 0 basic, 1 fast, 2 tank, 3 splitter,
+    // 4 carrier, 5 sniper, 6 mine layer and 7 healer. ai_state is
+    // AiState::id, 0 enter, 1 attack, 2 evade, 3 retreat and 4 flee. A
+    // sniper that is aiming has laser 1, laser_angle in radians from
+    // straight down towards +x, and laser_charge going from 0 to 1 as it
+    // gets ready to fire.
+    ChannelSchema {
+        name: "enemies",
+        fields: &[
+            "x",
+            "y",
+            "prev_x",
+            "prev_y",
+            "vx",
+            "vy",
+            "size",
+            "health",
+            "max_health",
+            "type",
+            "id",
+            "generation",
+            "ai_state",
+            "laser",
+            "laser_angle",
+            "laser_charge",
+        ],
+    },
+    ChannelSchema {
+        name: "player_bullets",
+        fields: BULLET_FIELDS,
+    },
+    ChannelSchema {
+        name: "enemy_bullets",
+        fields: BULLET_FIELDS,
+    },
+    ChannelSchema {
+        name: "power_ups",
+        fields: &["x", "y", "prev_x", "prev_y", "vy", "size", "type", "id", "generation"],
+    },
+    ChannelSchema {
+        name: "explosions",
+        fields: &["x", "y", "size", "life_ratio"],
+    },
+    ChannelSchema {
+        name: "black_holes",
+        fields: &[
+            "x",
+            "y",
+            "size",
+            "life_ratio",
+            "pull_radius",
+            "consumed",
+            "id",
+            "generation",
+        ],
+    },
+    // Empty unless a boss is fighting. The health bar is health over
+    // max_health, with a mark at next_phase_health (0 in the last phase).
+    ChannelSchema {
+        name: "boss",
+        fields: &[
+            "x",
+            "y",
+            "prev_x",
+            "prev_y",
+            "vx",
+            "vy",
+            "size",
+            "health",
+            "max_health",
+            "phase",
+            "phase_count",
+            "next_phase_health",
+            "entering",
+            "id",
+            "generation",
+        ],
+    },
+    // Parts still standing, part is the index in the boss config
+    ChannelSchema {
+        name: "boss_parts",
+        fields: &["x", "y", "prev_x", "prev_y", "size", "health", "max_health", "part"],
+    },
+    ChannelSchema {
+        name: "mines",
+        fields: &["x", "y", "size", "life_ratio", "id", "generation"],
+    },
+];
+
+// Schema as JSON, for renderers to build their field offsets from:
+// {"version":1,"channels":[{"id":0,"name":"player","stride":15,"fields":["x",...]},...]}
+pub fn schema_json() -> String {
+    let mut json = format!("{{\"version\":{},\"channels\":[", RENDER_SCHEMA_VERSION);
+    for (id, channel) in CHANNELS.iter().enumerate() {
+        if id > 0 {
+            json.push(',');
+        }
+        json.push_str(&format!(
+            "{{\"id\":{},\"name\":\"{}\",\"stride\":{},\"fields\":[",
+            id,
+            channel.name,
+            channel.stride()
+        ));
+        for (i, field) in channel.fields.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            json.push_str(&format!("\"{}\"", field));
+        }
+        json.push_str("]}");
+    }
+    json.push_str("]}");
+    json
+}
+
+// Most entities each channel can hold, the limits of the pools behind them
+pub const CHANNEL_LIMITS: [usize; CHANNEL_COUNT] = [
+    1,
+    MAX_ENEMIES,
+    MAX_PLAYER_BULLETS,
+    MAX_ENEMY_BULLETS,
+    MAX_POWER_UPS,
+    MAX_EXPLOSIONS,
+    MAX_BLACK_HOLES,
+    1,
+    MAX_BOSS_PARTS,
+    MAX_MINES,
+];
+
+// One flat f32 buffer per channel, each entity taking stride floats
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct RenderData {
+    pub channels: [Vec<f32>; CHANNEL_COUNT],
+}
+
+impl RenderData {
+    pub fn with_capacity(floats_per_channel: usize) -> RenderData {
+        RenderData {
+            channels: std::array::from_fn(|_| Vec::with_capacity(floats_per_channel)),
+        }
+    }
+
+    // Room for every channel filled to its limit, so writing a frame never
+    // reallocates and moves a buffer out from under views into it
+    pub fn with_full_capacity() -> RenderData {
+        RenderData {
+            channels: std::array::from_fn(|id| Vec::with_capacity(CHANNEL_LIMITS[id] * CHANNELS[id].stride())),
+        }
+    }
+
+    pub fn channel(&self, id: usize) -> &[f32] {
+        &self.channels[id]
+    }
+
+    // Number of entities in a channel
+    pub fn count(&self, id: usize) -> usize {
+        self.channels[id].len() / CHANNELS[id].stride()
+    }
+}
+
+impl Simulation {
+    pub fn render_data(&self) -> RenderData {
+        let mut data = RenderData::default();
+        self.write_render_data(&mut data);
+        data
+    }
+
+    // Fill caller-owned channel buffers. They are cleared first and only
+    // grow when a frame doesn't fit, so reused buffers stop allocating
+    // once they have warmed up.
+    pub fn write_render_data(&self, data: &mut RenderData) {
+        for channel in &mut data.channels {
+            channel.clear();
+        }
+
+        let player = &self.player;
+        data.channels[CHANNEL_PLAYER].extend_from_slice(&[
+            player.x,
+            player.y,
+            player.prev_x,
+            player.prev_y,
+            player.vx,
+            player.vy,
+            player.size,
+            player.health,
+            player.max_health,
+            player.power_level as f32,
+            player.growth_level as f32,
+            self.shield_level as f32,
+            self.shield_active as u8 as f32,
+            self.shield_timer,
+            player.black_hole_cooldown,
+        ]);
+
+        let enemies = &mut data.channels[CHANNEL_ENEMIES];
+        enemies.reserve(self.enemies.len() * CHANNELS[CHANNEL_ENEMIES].stride());
+        for enemy in &self.enemies {
+            enemies.extend_from_slice(&[
+                enemy.x,
+                enemy.y,
+                enemy.prev_x,
+                enemy.prev_y,
+                enemy.vx,
+                enemy.vy,
+                enemy.size,
+                enemy.health,
+                enemy.max_health,
+                enemy_type_id(&enemy.enemy_type),
+                enemy.id.index as f32,
+                enemy.id.generation as f32,
+                enemy.ai.id() as f32,
+                enemy.laser.is_some() as u8 as f32,
+                enemy.laser.unwrap_or(0.0),
+                match (enemy.laser, self.config.enemy(enemy.enemy_type).ability) {
+                    (Some(_), Some(Ability::Snipe { warning, .. })) if warning > 0.0 => {
+                        (1.0 - enemy.ability_timer / warning).clamp(0.0, 1.0)
+                    }
+                    (Some(_), _) => 1.0,
+                    _ => 0.0,
+                },
+            ]);
+        }
+
+        for (id, bullets) in [
+            (CHANNEL_PLAYER_BULLETS, &self.bullets),
+            (CHANNEL_ENEMY_BULLETS, &self.enemy_bullets),
+        ] {
+            let channel = &mut data.channels[id];
+            channel.reserve(bullets.len() * BULLET_FIELDS.len());
+            for bullet in bullets.iter() {
+                channel.extend_from_slice(&[
+                    bullet.x,
+                    bullet.y,
+                    bullet.prev_x,
+                    bullet.prev_y,
+                    bullet.vx,
+                    bullet.vy,
+                    bullet.size,
+                    bullet.damage,
+                    bullet.id.index as f32,
+                    bullet.id.generation as f32,
+                ]);
+            }
+        }
+
+        let power_ups = &mut data.channels[CHANNEL_POWER_UPS];
+        power_ups.reserve(self.power_ups.len() * CHANNELS[CHANNEL_POWER_UPS].stride());
+        for power_up in &self.power_ups {
+            power_ups.extend_from_slice(&[
+                power_up.x,
+                power_up.y,
+                power_up.prev_x,
+                power_up.prev_y,
+                power_up.vy,
+                power_up.size,
+                power_type_id(&power_up.power_type),
+                power_up.id.index as f32,
+                power_up.id.generation as f32,
+            ]);
+        }
+
+        let explosions = &mut data.channels[CHANNEL_EXPLOSIONS];
+        for explosion in &self.explosions {
+            explosions.extend_from_slice(&[
+                explosion.x,
+                explosion.y,
+                explosion.size,
+                explosion.life / explosion.max_life,
+            ]);
+        }
+
+        let black_holes = &mut data.channels[CHANNEL_BLACK_HOLES];
+        for black_hole in &self.black_holes {
+            black_holes.extend_from_slice(&[
+                black_hole.x,
+                black_hole.y,
+                black_hole.size,
+                black_hole.life / black_hole.max_life,
+                black_hole.pull_radius,
+                black_hole.consumed_enemies.len() as f32,
+                black_hole.id.index as f32,
+                black_hole.id.generation as f32,
+            ]);
+        }
+
+        let mines = &mut data.channels[CHANNEL_MINES];
+        for mine in &self.mines {
+            mines.extend_from_slice(&[
+                mine.x,
+                mine.y,
+                mine.size,
+                mine.life / mine.max_life,
+                mine.id.index as f32,
+                mine.id.generation as f32,
+            ]);
+        }
+
+        if let Some(boss) = &self.boss {
+            let phases = &self.config.boss.phases;
+            data.channels[CHANNEL_BOSS].extend_from_slice(&[
+                boss.x,
+                boss.y,
+                boss.prev_x,
+                boss.prev_y,
+                boss.vx,
+                boss.vy,
+                boss.size,
+                boss.health,
+                boss.max_health,
+                boss.phase as f32,
+                phases.len() as f32,
+                boss.next_phase_health(phases),
+                boss.entering as u8 as f32,
+                boss.id.index as f32,
+                boss.id.generation as f32,
+            ]);
+
+            let parts = &mut data.channels[CHANNEL_BOSS_PARTS];
+            for (i, part) in boss.parts.iter().enumerate() {
+                if part.is_destroyed() {
+                    continue;
+                }
+                parts.extend_from_slice(&[
+                    boss.x + part.offset_x,
+                    boss.y + part.offset_y,
+                    boss.prev_x + part.offset_x,
+                    boss.prev_y + part.offset_y,
+                    part.size,
+                    part.health,
+                    part.max_health,
+                    i as f32,
+                ]);
+            }
+        }
+    }
+}
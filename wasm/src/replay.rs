@@ -0,0 +1,209 @@
+use std::fmt;
+
+use crate::bytes::{Reader, Truncated, Writer};
+use crate::config::{ConfigError, GameConfig};
+use crate::input::InputFrame;
+
+const MAGIC: &[u8; 4] = b"PPMR";
+pub const REPLAY_FORMAT_VERSION: u16 = 3;
+pub const ENGINE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+// Per-tick record flags
+const FLAG_FIRE: u8 = 1;
+const FLAG_BLACK_HOLE: u8 = 1 << 1;
+const FLAG_MOVE: u8 = 1 << 2; // move_x, move_y follow
+const FLAG_DT: u8 = 1 << 3; // tick length follows
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ReplayTick {
+    pub dt: f32,
+    pub input: InputFrame,
+}
+
+// Everything needed to re-simulate a run: the starting configuration
+// plus the input and tick length of every simulated tick
+#[derive(Clone, PartialEq, Debug)]
+pub struct Replay {
+    pub engine_version: String,
+    pub seed: u64,
+    pub width: f32,
+    pub height: f32,
+    pub fixed_timestep: bool,
+    pub config: GameConfig,
+    // Save state the run started from, empty for a fresh game
+    pub initial_state: Vec<u8>,
+    pub ticks: Vec<ReplayTick>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ReplayError {
+    BadMagic,
+    UnsupportedVersion(u16),
+    Truncated,
+    InvalidEngineVersion,
+    InvalidConfig(ConfigError),
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReplayError::BadMagic => write!(f, "not a replay file"),
+            ReplayError::UnsupportedVersion(v) => write!(f, "unsupported replay format version {}", v),
+            ReplayError::Truncated => write!(f, "replay data is truncated"),
+            ReplayError::InvalidEngineVersion => write!(f, "replay engine version is not valid UTF-8"),
+            ReplayError::InvalidConfig(e) => write!(f, "replay has a bad game config, {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl From<Truncated> for ReplayError {
+    fn from(_: Truncated) -> ReplayError {
+        ReplayError::Truncated
+    }
+}
+
+impl Replay {
+    pub fn new(seed: u64, width: f32, height: f32, fixed_timestep: bool, config: GameConfig) -> Replay {
+        Replay {
+            engine_version: ENGINE_VERSION.to_string(),
+            seed,
+            width,
+            height,
+            fixed_timestep,
+            config,
+            initial_state: Vec::new(),
+            ticks: Vec::new(),
+        }
+    }
+
+    // Layout (little-endian):
+    // magic, u16 format version, u8 length + engine version, u64 seed,
+    // f32 width, f32 height, u8 fixed_timestep, u32 length + config
+    // JSON, u32 length + initial state, u32 tick count, then one flags
+    // byte per tick. Movement and tick length are only written when they
+    // change, so a typical tick costs a single byte.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let version = self.engine_version.as_bytes();
+        let version = &version[..version.len().min(u8::MAX as usize)];
+
+        let config = self.config.to_json();
+
+        let mut w = Writer::with_capacity(
+            40 + version.len() + config.len() + self.initial_state.len() + self.ticks.len(),
+        );
+        w.raw(MAGIC);
+        w.u16(REPLAY_FORMAT_VERSION);
+        w.u8(version.len() as u8);
+        w.raw(version);
+        w.u64(self.seed);
+        w.f32(self.width);
+        w.f32(self.height);
+        w.bool(self.fixed_timestep);
+        w.u32(config.len() as u32);
+        w.raw(config.as_bytes());
+        w.u32(self.initial_state.len() as u32);
+        w.raw(&self.initial_state);
+        w.u32(self.ticks.len() as u32);
+
+        let mut prev_move = (0.0, 0.0);
+        let mut prev_dt = 0.0;
+        for tick in &self.ticks {
+            let input = tick.input;
+            let moved = (input.move_x, input.move_y) != prev_move;
+            let dt_changed = tick.dt != prev_dt;
+
+            let mut flags = 0;
+            if input.fire {
+                flags |= FLAG_FIRE;
+            }
+            if input.black_hole {
+                flags |= FLAG_BLACK_HOLE;
+            }
+            if moved {
+                flags |= FLAG_MOVE;
+            }
+            if dt_changed {
+                flags |= FLAG_DT;
+            }
+            w.u8(flags);
+
+            if moved {
+                w.f32(input.move_x);
+                w.f32(input.move_y);
+                prev_move = (input.move_x, input.move_y);
+            }
+            if dt_changed {
+                w.f32(tick.dt);
+                prev_dt = tick.dt;
+            }
+        }
+
+        w.bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Replay, ReplayError> {
+        let mut reader = Reader::new(bytes);
+
+        if reader.take(4)? != MAGIC {
+            return Err(ReplayError::BadMagic);
+        }
+        let format_version = reader.u16()?;
+        if format_version != REPLAY_FORMAT_VERSION {
+            return Err(ReplayError::UnsupportedVersion(format_version));
+        }
+
+        let version_len = reader.u8()? as usize;
+        let engine_version = std::str::from_utf8(reader.take(version_len)?)
+            .map_err(|_| ReplayError::InvalidEngineVersion)?
+            .to_string();
+        let seed = reader.u64()?;
+        let width = reader.f32()?;
+        let height = reader.f32()?;
+        let fixed_timestep = reader.bool()?;
+        let config_len = reader.u32()? as usize;
+        let config = std::str::from_utf8(reader.take(config_len)?)
+            .map_err(|_| ReplayError::InvalidConfig(ConfigError::Parse("not valid UTF-8".to_string())))?;
+        let config = GameConfig::from_json(config).map_err(ReplayError::InvalidConfig)?;
+        let state_len = reader.u32()? as usize;
+        let initial_state = reader.take(state_len)?.to_vec();
+        let tick_count = reader.u32()? as usize;
+
+        // Every tick takes at least one byte, don't trust the count blindly
+        let mut ticks = Vec::with_capacity(tick_count.min(reader.remaining()));
+        let mut move_x = 0.0;
+        let mut move_y = 0.0;
+        let mut dt = 0.0;
+        for _ in 0..tick_count {
+            let flags = reader.u8()?;
+            if flags & FLAG_MOVE != 0 {
+                move_x = reader.f32()?;
+                move_y = reader.f32()?;
+            }
+            if flags & FLAG_DT != 0 {
+                dt = reader.f32()?;
+            }
+            ticks.push(ReplayTick {
+                dt,
+                input: InputFrame {
+                    move_x,
+                    move_y,
+                    fire: flags & FLAG_FIRE != 0,
+                    black_hole: flags & FLAG_BLACK_HOLE != 0,
+                },
+            });
+        }
+
+        Ok(Replay {
+            engine_version,
+            seed,
+            width,
+            height,
+            fixed_timestep,
+            config,
+            initial_state,
+            ticks,
+        })
+    }
+}
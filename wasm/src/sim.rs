@@ -0,0 +1,1396 @@
+use std::collections::VecDeque;
+
+use crate::ability::Ability;
+use crate::boss::Boss;
+use crate::bullets::BulletPool;
+use crate::config::GameConfig;
+use crate::difficulty::DynamicDifficulty;
+use crate::entities::*;
+use crate::events::{DamageSource, GameEvent};
+use crate::formation::Formation;
+use crate::ids::{EntityId, IdAllocator};
+use crate::input::InputFrame;
+use crate::pattern::{Aim, Emitter, Gun, Pattern, Shape};
+use crate::pool::{Pool, Storage};
+use crate::replay::{Replay, ReplayTick};
+use crate::rng::Rng;
+use crate::spatial::SpatialGrid;
+use crate::ai::{threat, AiState, Orders};
+use crate::movement::{steer, Movement, Surroundings};
+use crate::stage::{Stage, StageProgress, Step};
+use crate::state::StateError;
+
+// Length of one simulation tick in fixed-step mode
+pub const FIXED_TIMESTEP: f32 = 1.0 / 60.0;
+// Most ticks advance() will run for one frame, the rest is dropped
+pub const MAX_CATCH_UP_STEPS: u32 = 5;
+// Fastest allowed time scale, low enough that a normal frame never hits
+// the catch-up limit
+pub const MAX_TIME_SCALE: f32 = 4.0;
+// Pool sizes per entity kind. Everything is allocated up front and
+// spawns past a limit are dropped.
+pub const MAX_ENEMIES: usize = 512;
+pub const MAX_PLAYER_BULLETS: usize = 8192;
+pub const MAX_ENEMY_BULLETS: usize = 32768;
+pub const MAX_POWER_UPS: usize = 64;
+pub const MAX_EXPLOSIONS: usize = 256;
+pub const MAX_BLACK_HOLES: usize = 8;
+pub const MAX_MINES: usize = 128;
+pub const MAX_BOSS_PARTS: usize = 32;
+// Broad phase cell size, a few times the biggest enemy
+const GRID_CELL_SIZE: f32 = 64.0;
+
+// Headless simulation core. Pure Rust with no JS dependencies, so it
+// builds and runs natively; GameEngine in lib.rs wraps it for wasm.
+pub struct Simulation {
+    // Gameplay tuning, fixed for the whole run
+    pub config: GameConfig,
+    pub player: Player,
+    pub enemies: Pool<Enemy>,
+    pub bullets: BulletPool,
+    pub enemy_bullets: BulletPool,
+    pub power_ups: Pool<PowerUp>,
+    pub explosions: Pool<Explosion>,
+    pub black_holes: Pool<BlackHole>,
+    pub mines: Pool<Mine>,
+    // At most one boss fights at a time
+    pub boss: Option<Boss>,
+    pub bosses_defeated: u32,
+    // Squadrons flying in or holding formation
+    pub formations: Vec<Formation>,
+    // Ids of every live enemy, bullet, power-up and black hole
+    pub ids: IdAllocator,
+    pub score: u32,
+    pub level: u32,
+    pub game_time: f32,
+    pub enemy_spawn_timer: f32,
+    pub squadron_spawn_timer: f32,
+    pub power_up_spawn_timer: f32,
+    // Spawn pressure from dynamic difficulty, 1 when it's off
+    pub difficulty: DynamicDifficulty,
+    // Progress through config.stage, if there is one
+    pub stage: StageProgress,
+    pub width: f32,
+    pub height: f32,
+    pub game_over: bool,
+    // Shield system fields
+    pub shield_level: u32,
+    pub shield_active: bool,
+    pub shield_timer: f32,
+    // Fixed-step mode: advance() runs whole FIXED_TIMESTEP ticks
+    pub(crate) fixed_timestep: bool,
+    pub(crate) accumulator: f32,
+    // Playback controls, not part of the game state: a paused game runs
+    // no ticks at all, time_scale stretches frame time before it is
+    // simulated, and a frame longer than auto_pause_after (0 is off)
+    // pauses instead of being simulated
+    pub(crate) paused: bool,
+    pub(crate) time_scale: f32,
+    pub(crate) auto_pause_after: f32,
+    // Number of ticks simulated so far
+    pub tick: u32,
+    // Input in effect and frames waiting for their tick, ordered by tick
+    pub input: InputFrame,
+    input_queue: VecDeque<(u32, InputFrame)>,
+    // Recording of the current run, and recorded tick lengths when
+    // playing one back
+    pub(crate) replay: Replay,
+    // Gameplay events since the last drain_events
+    pub(crate) events: Vec<GameEvent>,
+    playback: Option<Vec<f32>>,
+    playback_start: u32,
+    // Deterministic RNG, all random decisions go through it
+    pub(crate) seed: u64,
+    pub(crate) rng: Rng,
+    // Collision broad phase: enemies, and whichever list is being tested
+    // against the player. Candidates is the reused query result.
+    pub(crate) enemy_grid: SpatialGrid,
+    grid: SpatialGrid,
+    // Player bullets, for enemies that dodge them
+    bullet_grid: SpatialGrid,
+    pub(crate) candidates: Vec<u32>,
+    // Reused lists of indices to remove
+    pub(crate) dead: Vec<usize>,
+    dead_enemies: Vec<usize>,
+    // Reused list of bullet directions in a volley
+    pub(crate) directions: Vec<f32>,
+    // Reused list of stage enemies due this tick
+    stage_spawns: Vec<(usize, u32)>,
+    // Reused lists of splitters shot down and drones launched this tick,
+    // by the type of the splitter or carrier and where it was
+    pub(crate) splits: Vec<(EnemyType, f32, f32)>,
+    pub(crate) launches: Vec<(EnemyType, f32, f32)>,
+    // Reused list of healers, with where they are and their radius and rate
+    pub(crate) healers: Vec<(EntityId, f32, f32, f32, f32)>,
+    // Stress mode keeps this many player bullets in flight, 0 is off
+    stress_bullets: u32,
+}
+
+impl Simulation {
+    pub fn new(width: f32, height: f32, seed: u64) -> Simulation {
+        Simulation::with_config(width, height, seed, GameConfig::default())
+    }
+
+    // The config is expected to be validated already, see
+    // GameConfig::validate
+    pub fn with_config(width: f32, height: f32, seed: u64, config: GameConfig) -> Simulation {
+        Simulation {
+            player: Player::new(width, height, &config.player),
+            replay: Replay::new(seed, width, height, false, config.clone()),
+            config,
+            enemies: Pool::new(MAX_ENEMIES),
+            bullets: BulletPool::new(MAX_PLAYER_BULLETS),
+            enemy_bullets: BulletPool::new(MAX_ENEMY_BULLETS),
+            power_ups: Pool::new(MAX_POWER_UPS),
+            explosions: Pool::new(MAX_EXPLOSIONS),
+            black_holes: Pool::new(MAX_BLACK_HOLES),
+            mines: Pool::new(MAX_MINES),
+            boss: None,
+            bosses_defeated: 0,
+            formations: Vec::new(),
+            ids: IdAllocator::new(),
+            score: 0,
+            level: 1,
+            game_time: 0.0,
+            enemy_spawn_timer: 0.0,
+            squadron_spawn_timer: 0.0,
+            power_up_spawn_timer: 0.0,
+            difficulty: DynamicDifficulty::default(),
+            stage: StageProgress::default(),
+            width,
+            height,
+            game_over: false,
+            // Initialize shield system
+            shield_level: 0,
+            shield_active: false,
+            shield_timer: 0.0,
+            fixed_timestep: false,
+            accumulator: 0.0,
+            paused: false,
+            time_scale: 1.0,
+            auto_pause_after: 0.0,
+            tick: 0,
+            input: InputFrame::default(),
+            input_queue: VecDeque::new(),
+            events: Vec::new(),
+            playback: None,
+            playback_start: 0,
+            seed,
+            rng: Rng::new(seed),
+            enemy_grid: SpatialGrid::new(GRID_CELL_SIZE),
+            grid: SpatialGrid::new(GRID_CELL_SIZE),
+            bullet_grid: SpatialGrid::new(GRID_CELL_SIZE),
+            candidates: Vec::new(),
+            dead: Vec::with_capacity(MAX_PLAYER_BULLETS),
+            dead_enemies: Vec::with_capacity(MAX_ENEMIES),
+            directions: Vec::new(),
+            stage_spawns: Vec::new(),
+            splits: Vec::new(),
+            launches: Vec::new(),
+            healers: Vec::new(),
+            stress_bullets: 0,
+        }
+    }
+
+    // Advance by one rendered frame. In fixed-step mode the frame time is
+    // accumulated and consumed in FIXED_TIMESTEP ticks, so gameplay no
+    // longer depends on frame rate
+    pub fn advance(&mut self, frame_time: f32) {
+        if self.auto_pause_after > 0.0 && frame_time > self.auto_pause_after {
+            self.paused = true;
+        }
+        if self.paused {
+            return;
+        }
+
+        let frame_time = frame_time * self.time_scale;
+        if !self.fixed_timestep {
+            // No time passed at a time scale of 0, so no tick either
+            if frame_time > 0.0 {
+                self.update(frame_time);
+            }
+            return;
+        }
+
+        self.accumulator += frame_time.max(0.0);
+
+        let mut steps = 0;
+        while self.accumulator >= FIXED_TIMESTEP && steps < MAX_CATCH_UP_STEPS {
+            self.update(FIXED_TIMESTEP);
+            self.accumulator -= FIXED_TIMESTEP;
+            steps += 1;
+        }
+
+        // Too far behind (e.g. a backgrounded tab), drop the backlog
+        if self.accumulator >= FIXED_TIMESTEP {
+            self.accumulator %= FIXED_TIMESTEP;
+        }
+    }
+
+    // Freezes everything, timers included, until resume
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    // 1 is normal speed, below 1 is slow motion. Clamped to
+    // 0..=MAX_TIME_SCALE, anything not a number is ignored.
+    pub fn set_time_scale(&mut self, scale: f32) {
+        if !scale.is_nan() {
+            self.time_scale = scale.clamp(0.0, MAX_TIME_SCALE);
+        }
+    }
+
+    pub fn time_scale(&self) -> f32 {
+        self.time_scale
+    }
+
+    // Pause instead of simulating frames longer than max_frame_time
+    // seconds, e.g. the first frame after a tab regains focus. 0 turns
+    // it off.
+    pub fn set_auto_pause(&mut self, max_frame_time: f32) {
+        self.auto_pause_after = max_frame_time.max(0.0);
+    }
+
+    pub fn set_fixed_timestep(&mut self, enabled: bool) {
+        self.fixed_timestep = enabled;
+        self.replay.fixed_timestep = enabled;
+    }
+
+    pub fn is_fixed_timestep(&self) -> bool {
+        self.fixed_timestep
+    }
+
+    // How far the renderer is between the previous and current tick (0..1)
+    pub fn interpolation_alpha(&self) -> f32 {
+        if self.fixed_timestep {
+            self.accumulator / FIXED_TIMESTEP
+        } else {
+            1.0
+        }
+    }
+
+    // Re-simulate a recorded run. Recorded inputs are queued at their
+    // ticks and the recorded tick lengths replace the ones passed to
+    // update, so playback goes through the normal update path.
+    pub fn from_replay(replay: &Replay) -> Result<Simulation, StateError> {
+        let mut sim = if replay.initial_state.is_empty() {
+            Simulation::with_config(replay.width, replay.height, replay.seed, replay.config.clone())
+        } else {
+            Simulation::from_state(&replay.initial_state)?
+        };
+        sim.set_fixed_timestep(replay.fixed_timestep);
+        let start_tick = sim.tick;
+
+        // Only changes need queueing, held input carries over. Presses
+        // last one tick, so the held input never has one and every tick
+        // with a press differs from it.
+        let mut prev = sim.input;
+        for (tick, recorded) in replay.ticks.iter().enumerate() {
+            if recorded.input != prev {
+                sim.input_queue.push_back((start_tick + tick as u32, recorded.input));
+                prev = InputFrame {
+                    black_hole: false,
+                    ..recorded.input
+                };
+            }
+        }
+        sim.playback = Some(replay.ticks.iter().map(|t| t.dt).collect());
+        sim.playback_start = start_tick;
+
+        Ok(sim)
+    }
+
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    pub fn is_playing_back(&self) -> bool {
+        self.playback.is_some()
+    }
+
+    pub fn playback_finished(&self) -> bool {
+        match &self.playback {
+            Some(dts) => (self.tick - self.playback_start) as usize >= dts.len(),
+            None => false,
+        }
+    }
+
+    pub fn update(&mut self, delta_time: f32) {
+        self.store_previous_positions();
+
+        if self.game_over {
+            // Nothing will consume queued input until reset
+            self.input_queue.clear();
+            return;
+        }
+
+        let delta_time = match &self.playback {
+            Some(dts) => match dts.get((self.tick - self.playback_start) as usize) {
+                Some(&dt) => dt,
+                None => return,
+            },
+            None => delta_time,
+        };
+
+        // Apply player input for this tick and record it
+        let input = self.apply_input();
+        self.replay.ticks.push(ReplayTick { dt: delta_time, input });
+
+        self.game_time += delta_time;
+        self.enemy_spawn_timer += delta_time;
+        self.squadron_spawn_timer += delta_time;
+        self.power_up_spawn_timer += delta_time;
+
+        // Update player
+        self.update_player(delta_time);
+
+        // Spawn enemies, scripted by the stage if there is one
+        self.update_stage(delta_time);
+        let spawn_interval = self.config.enemy_spawn_interval(self.level) / self.difficulty.factor;
+        if self.random_spawning() && self.enemy_spawn_timer >= spawn_interval {
+            self.spawn_enemy();
+            self.enemy_spawn_timer = 0.0;
+        }
+        // One random squadron at a time
+        let squadron_interval = self.config.spawn.squadron_interval;
+        if squadron_interval > 0.0 && self.squadron_spawn_timer >= squadron_interval {
+            if self.random_spawning() && self.formations.is_empty() {
+                self.spawn_squadron(self.config.squadron);
+            }
+            self.squadron_spawn_timer = 0.0;
+        }
+
+        // Spawn power-ups
+        if self.power_up_spawn_timer >= self.config.power_ups.interval {
+            self.spawn_power_up();
+            self.power_up_spawn_timer = 0.0;
+        }
+
+        if self.stress_bullets > 0 {
+            self.fill_stress();
+        }
+
+        // Update enemies
+        self.update_formations(delta_time);
+        self.update_enemies(delta_time);
+        self.update_healers(delta_time);
+        self.update_boss(delta_time);
+
+        // Update bullets
+        self.update_bullets(delta_time);
+
+        // Update power-ups
+        self.update_power_ups(delta_time);
+
+        // Update explosions
+        self.update_explosions(delta_time);
+
+        // Update black holes
+        self.update_black_holes(delta_time);
+        self.update_mines(delta_time);
+
+        // Update shield system
+        self.update_shield(delta_time);
+
+        // Check collisions
+        self.check_collisions();
+        self.check_mine_collisions();
+        self.check_boss_collisions();
+
+        // Clean up off-screen objects
+        self.cleanup();
+
+        self.difficulty.update(
+            &self.config.dynamic_difficulty,
+            delta_time,
+            self.player.health.max(0.0) / self.player.max_health,
+            self.player.enemies_killed,
+            1.0 / spawn_interval,
+        );
+
+        // Level up
+        if self.score >= self.level * self.config.points_per_level {
+            self.level += 1;
+            self.events.push(GameEvent::LevelUp { level: self.level });
+
+            let every = self.config.boss.every_levels;
+            if every > 0 && self.level.is_multiple_of(every) {
+                self.spawn_boss();
+            }
+        }
+
+        if self.game_over {
+            self.events.push(GameEvent::GameOver { score: self.score });
+        }
+
+        self.tick += 1;
+    }
+
+    // Queue an input frame to be applied at the given tick. Frames for a
+    // tick that already ran are applied on the next one. Frames for the
+    // same tick are merged, the latest winning but keeping any ability
+    // press, so the queue stays one frame per tick however often it is
+    // called. Ignored while playing back a replay.
+    pub fn queue_input(&mut self, tick: u32, mut frame: InputFrame) {
+        if self.playback.is_some() {
+            return;
+        }
+        let idx = self.input_queue.partition_point(|&(t, _)| t <= tick);
+        if let Some((t, queued)) = idx.checked_sub(1).and_then(|last| self.input_queue.get_mut(last)) {
+            if *t == tick {
+                frame.black_hole |= queued.black_hole;
+                *queued = frame;
+                return;
+            }
+        }
+        self.input_queue.insert(idx, (tick, frame));
+    }
+
+    // Returns the input applied this tick, including ability presses
+    fn apply_input(&mut self) -> InputFrame {
+        let mut black_hole = false;
+        while let Some(&(tick, frame)) = self.input_queue.front() {
+            if tick > self.tick {
+                break;
+            }
+            self.input_queue.pop_front();
+            self.input = frame;
+            black_hole |= frame.black_hole;
+        }
+
+        self.move_player(self.input.move_x, self.input.move_y);
+
+        if self.input.fire {
+            self.shoot();
+        }
+
+        // Presses only count on the tick they arrive
+        if black_hole {
+            self.activate_black_hole();
+        }
+
+        let applied = InputFrame {
+            black_hole,
+            ..self.input
+        };
+        self.input.black_hole = false;
+        applied
+    }
+
+    fn store_previous_positions(&mut self) {
+        self.player.prev_x = self.player.x;
+        self.player.prev_y = self.player.y;
+
+        for enemy in &mut self.enemies {
+            enemy.prev_x = enemy.x;
+            enemy.prev_y = enemy.y;
+        }
+
+        if let Some(boss) = &mut self.boss {
+            boss.prev_x = boss.x;
+            boss.prev_y = boss.y;
+        }
+
+        self.bullets.store_previous_positions();
+        self.enemy_bullets.store_previous_positions();
+
+        for power_up in &mut self.power_ups {
+            power_up.prev_x = power_up.x;
+            power_up.prev_y = power_up.y;
+        }
+    }
+
+    pub fn update_player(&mut self, delta_time: f32) {
+        // Update position
+        self.player.x += self.player.vx * delta_time * self.config.player.speed;
+        self.player.y += self.player.vy * delta_time * self.config.player.speed;
+
+        // Keep player in bounds, centred if it's wider than the screen
+        let margin_x = self.player.size.min(self.width / 2.0);
+        let margin_y = self.player.size.min(self.height / 2.0);
+        self.player.x = self.player.x.clamp(margin_x, self.width - margin_x);
+        self.player.y = self.player.y.clamp(margin_y, self.height - margin_y);
+
+        // Update shoot cooldown
+        if self.player.shoot_cooldown > 0.0 {
+            self.player.shoot_cooldown -= delta_time;
+        }
+
+        // Update black hole cooldown
+        if self.player.black_hole_cooldown > 0.0 {
+            self.player.black_hole_cooldown -= delta_time;
+        }
+    }
+
+    pub fn spawn_enemy(&mut self) {
+        let special = EnemyType::SPECIAL;
+        let enemy_type = if self.rng.next_f32() < self.config.spawn.special_chance {
+            special[((self.rng.next_f32() * special.len() as f32) as usize).min(special.len() - 1)]
+        } else if self.rng.next_f32() < self.config.spawn.tank_chance {
+            EnemyType::Tank
+        } else if self.rng.next_f32() < self.config.spawn.fast_chance {
+            EnemyType::Fast
+        } else {
+            EnemyType::Basic
+        };
+
+        let x = self.rng.next_f32() * (self.width - 50.0) + 25.0;
+        let movement = self.config.enemy(enemy_type).movement.unwrap_or(Movement::Drift);
+        self.spawn_enemy_at(enemy_type, x, -50.0, movement);
+    }
+
+    // Spawn an enemy with its configured stats, scaled for difficulty
+    pub fn spawn_enemy_at(&mut self, enemy_type: EnemyType, x: f32, y: f32, movement: Movement) -> Option<EntityId> {
+        let stats = self.config.enemy(enemy_type);
+        let health = stats.health * self.config.difficulty.enemy_health;
+        let (size, speed) = (stats.size, stats.speed);
+        let emitter = stats
+            .pattern
+            .map_or(Emitter::default(), |pattern| Emitter::new(&pattern, self.config.difficulty.fire_rate));
+        let ai = if stats.ai.is_some() { AiState::Enter } else { AiState::Attack };
+        let ability_timer = stats.ability.map_or(0.0, |ability| ability.first_use());
+
+        // Other movements steer from the first tick
+        let vx = match movement {
+            Movement::Drift => (self.rng.next_f32() - 0.5) * speed,
+            _ => 0.0,
+        };
+        self.add_enemy(Enemy {
+            id: EntityId::default(),
+            x,
+            y,
+            prev_x: x,
+            prev_y: y,
+            vx,
+            vy: speed,
+            health,
+            max_health: health,
+            size,
+            enemy_type,
+            shoot_cooldown: 0.0,
+            emitter,
+            movement,
+            movement_time: 0.0,
+            held: 0.0,
+            ai,
+            ai_time: 0.0,
+            squad: None,
+            ability_timer,
+            laser: None,
+        })
+    }
+
+    // Start a new run playing a stage, or random spawning for None. The
+    // stage becomes part of the config, so replays and saves keep it.
+    pub fn start_stage(&mut self, stage: Option<Stage>) {
+        self.config.stage = stage;
+        self.reset();
+    }
+
+    // Random endless spawning, used without a stage and after an endless
+    // stage is complete. It holds off while a boss is fighting.
+    fn random_spawning(&self) -> bool {
+        if self.boss.is_some() {
+            return false;
+        }
+        match &self.config.stage {
+            None => true,
+            Some(stage) => stage.endless && self.stage.complete,
+        }
+    }
+
+    fn update_stage(&mut self, delta_time: f32) {
+        let Some(stage) = &self.config.stage else {
+            return;
+        };
+        let cleared = self.enemies.is_empty() && self.formations.is_empty();
+        if self.stage.update(stage, delta_time, cleared, &mut self.stage_spawns) {
+            self.events.push(GameEvent::StageComplete);
+        }
+
+        for i in 0..self.stage_spawns.len() {
+            let (step, index) = self.stage_spawns[i];
+            let group = match self.config.stage.as_ref().map(|stage| &stage.steps[step]) {
+                Some(Step::Spawn(group)) => *group,
+                Some(Step::Squadron(squadron)) => {
+                    self.spawn_squadron(*squadron);
+                    continue;
+                }
+                _ => continue,
+            };
+            let x = group.x * self.width + index as f32 * group.spacing;
+            let movement = group
+                .movement
+                .or(self.config.enemy(group.enemy).movement)
+                .unwrap_or(Movement::Straight);
+            self.spawn_enemy_at(group.enemy, x, group.y, movement);
+        }
+    }
+
+    pub fn spawn_power_up(&mut self) {
+        let power_type = if self.rng.next_f32() < self.config.power_ups.health_chance {
+            PowerUpType::Health
+        } else if self.rng.next_f32() < self.config.power_ups.weapon_chance {
+            PowerUpType::Weapon
+        } else {
+            PowerUpType::Shield
+        };
+
+        let x = self.rng.next_f32() * (self.width - 30.0) + 15.0;
+        let power_up = PowerUp {
+            id: EntityId::default(),
+            x,
+            y: -30.0,
+            prev_x: x,
+            prev_y: -30.0,
+            vy: self.config.power_ups.speed,
+            size: self.config.power_ups.size,
+            power_type,
+        };
+
+        self.add_power_up(power_up);
+    }
+
+    pub fn update_enemies(&mut self, delta_time: f32) {
+        let fire = &self.config.enemy_fire;
+        let scale = &self.config.difficulty;
+        let chance = (fire.chance * scale.fire_rate).min(1.0);
+        let evading = EnemyType::ALL
+            .iter()
+            .any(|&enemy_type| self.config.enemy(enemy_type).ai.is_some_and(|ai| ai.evade.is_some()));
+        if evading {
+            let bullets = &self.bullets;
+            self.bullet_grid.rebuild(
+                self.width,
+                self.height,
+                (0..bullets.len()).map(|i| (bullets.x[i], bullets.y[i], bullets.size[i])),
+            );
+        }
+        let around = Surroundings {
+            paths: &self.config.paths,
+            player_x: self.player.x,
+            player_y: self.player.y,
+            bullets: &self.bullets,
+            bullet_grid: &self.bullet_grid,
+            width: self.width,
+        };
+        let mut gun = Gun::at_player(
+            &self.player,
+            &self.config,
+            self.level,
+            &mut self.enemy_bullets,
+            &mut self.ids,
+            &mut self.directions,
+            &mut self.rng,
+        );
+        for enemy in &mut self.enemies {
+            let enemy_config = self.config.enemy(enemy.enemy_type);
+            let orders = match &enemy_config.ai {
+                Some(ai) => {
+                    let threat = ai
+                        .evade
+                        .and_then(|evade| threat(enemy, &around, evade.range, &mut self.candidates));
+                    ai.think(enemy, &around, threat, delta_time)
+                }
+                None => Orders::ATTACK,
+            };
+            if orders.steer {
+                steer(enemy, enemy_config.speed, &around, delta_time);
+            }
+            enemy.x += (enemy.vx + orders.push_x) * delta_time;
+            enemy.y += (enemy.vy + orders.push_y) * delta_time;
+
+            // Enemy shooting, a pattern if the type has one
+            if !orders.fire {
+                continue;
+            }
+            match enemy_config.ability {
+                Some(Ability::Snipe { warning, cooldown, bullet_speed }) => {
+                    enemy.ability_timer -= delta_time;
+                    if enemy.ability_timer > 0.0 {
+                        continue;
+                    }
+                    match enemy.laser.take() {
+                        // Fire straight down the sight
+                        Some(angle) => {
+                            let shot = Pattern {
+                                shape: Shape::Single,
+                                aim: Aim::Down,
+                                spin: 0.0,
+                                spread: 0.0,
+                                burst: 1,
+                                burst_interval: 0.0,
+                                cooldown,
+                                bullet_speed,
+                            };
+                            gun.fire(&shot, angle.to_degrees(), enemy.x, enemy.y);
+                            enemy.ability_timer = cooldown / scale.fire_rate;
+                        }
+                        None => {
+                            enemy.laser = Some((gun.target_x - enemy.x).atan2(gun.target_y - enemy.y));
+                            enemy.ability_timer = warning;
+                        }
+                    }
+                    continue;
+                }
+                Some(Ability::Launch { interval, .. }) => {
+                    enemy.ability_timer -= delta_time;
+                    if enemy.ability_timer <= 0.0 {
+                        self.launches.push((enemy.enemy_type, enemy.x, enemy.y + enemy.size));
+                        enemy.ability_timer += interval;
+                    }
+                }
+                Some(Ability::LayMines { interval, size, damage, life }) => {
+                    enemy.ability_timer -= delta_time;
+                    if enemy.ability_timer <= 0.0 {
+                        let (x, y) = (enemy.x, enemy.y);
+                        spawn(&mut self.mines, gun.ids, |id| Mine {
+                            id,
+                            x,
+                            y,
+                            size,
+                            damage: damage * scale.damage,
+                            life,
+                            max_life: life,
+                        });
+                        enemy.ability_timer += interval;
+                    }
+                }
+                _ => {}
+            }
+            if let Some(pattern) = &enemy_config.pattern {
+                if enemy.emitter.update(pattern, delta_time, scale.fire_rate) {
+                    gun.fire(pattern, enemy.emitter.rotation, enemy.x, enemy.y + enemy.size);
+                }
+            } else if enemy.shoot_cooldown > 0.0 {
+                enemy.shoot_cooldown -= delta_time;
+            } else if gun.rng.next_f32() < chance {
+                let shot = fire.single_shot(enemy_config.aim);
+                gun.fire(&shot, 0.0, enemy.x, enemy.y + enemy.size);
+                enemy.shoot_cooldown = fire.cooldown / scale.fire_rate;
+            }
+        }
+        self.launch_drones();
+    }
+
+    pub fn update_bullets(&mut self, delta_time: f32) {
+        self.bullets.integrate(delta_time);
+        self.enemy_bullets.integrate(delta_time);
+    }
+
+    pub fn update_power_ups(&mut self, delta_time: f32) {
+        for power_up in &mut self.power_ups {
+            power_up.y += power_up.vy * delta_time;
+        }
+    }
+
+    pub fn update_explosions(&mut self, delta_time: f32) {
+        for explosion in &mut self.explosions {
+            explosion.life -= delta_time;
+        }
+    }
+
+    pub fn update_black_holes(&mut self, delta_time: f32) {
+        for black_hole in &mut self.black_holes {
+            black_hole.life -= delta_time;
+
+            // Track enemies to remove (consumed by black hole)
+            self.dead.clear();
+
+            // Only enemies near the black hole can be pulled. Rebuilt for
+            // each one, the previous black hole moved and removed enemies.
+            self.enemy_grid.rebuild(
+                self.width,
+                self.height,
+                self.enemies.iter().map(|enemy| (enemy.x, enemy.y, enemy.size)),
+            );
+            self.enemy_grid
+                .query(black_hole.x, black_hole.y, black_hole.pull_radius, &mut self.candidates);
+
+            // Pull enemies towards the black hole
+            for &enemy_idx in &self.candidates {
+                let enemy_idx = enemy_idx as usize;
+                let enemy = &mut self.enemies[enemy_idx];
+
+                // Safety check: ensure enemy is valid
+                if enemy.health <= 0.0 || enemy.size <= 0.0 {
+                    self.dead.push(enemy_idx);
+                    continue;
+                }
+
+                let dx = black_hole.x - enemy.x;
+                let dy = black_hole.y - enemy.y;
+                let distance_sq = dx * dx + dy * dy;
+
+                if distance_sq < black_hole.pull_radius * black_hole.pull_radius {
+                    // Calculate pull force (stronger when closer)
+                    let distance = distance_sq.sqrt();
+                    let pull_force = self.config.black_hole.pull_force * (1.0 - distance / black_hole.pull_radius);
+                    let normalized_dx = dx / distance;
+                    let normalized_dy = dy / distance;
+
+                    enemy.x += normalized_dx * pull_force * delta_time;
+                    enemy.y += normalized_dy * pull_force * delta_time;
+
+                    // If enemy is very close, consume it
+                    if distance < black_hole.size {
+                        black_hole.consumed_enemies.push((enemy.x, enemy.y));
+                        self.dead.push(enemy_idx);
+                    }
+                }
+            }
+
+            // Candidates come in ascending order, remove from the back
+            self.dead.reverse();
+            self.enemies.remove_descending(&self.dead, |enemy| self.ids.free(enemy.id));
+        }
+    }
+
+    pub fn update_shield(&mut self, delta_time: f32) {
+        // Update shield timer
+        if self.shield_timer > 0.0 {
+            self.shield_timer -= delta_time;
+            if self.shield_timer <= 0.0 {
+                self.shield_active = false;
+                self.shield_timer = 0.0;
+            }
+        }
+    }
+
+    pub fn check_collisions(&mut self) {
+        // Removals are collected in the reused index lists and applied
+        // with swap-remove, highest index first
+        self.dead.clear();
+        self.dead_enemies.clear();
+
+        // Every pass below only tests the candidates the grids return
+        self.enemy_grid.rebuild(
+            self.width,
+            self.height,
+            self.enemies.iter().map(|enemy| (enemy.x, enemy.y, enemy.size)),
+        );
+
+        // Player bullets vs enemies
+        for (bullet_idx, bullet) in self.bullets.iter().enumerate() {
+            self.enemy_grid.query(bullet.x, bullet.y, bullet.size, &mut self.candidates);
+            for &enemy_idx in &self.candidates {
+                let enemy_idx = enemy_idx as usize;
+                let enemy = &mut self.enemies[enemy_idx];
+
+                // Safety check: ensure enemy is valid
+                if enemy.health <= 0.0 || enemy.size <= 0.0 {
+                    self.dead_enemies.push(enemy_idx);
+                    continue;
+                }
+
+                let dx = bullet.x - enemy.x;
+                let dy = bullet.y - enemy.y;
+                let reach = bullet.size + enemy.size;
+
+                if dx * dx + dy * dy < reach * reach {
+                    self.dead.push(bullet_idx);
+                    enemy.health -= bullet.damage;
+
+                    if enemy.health <= 0.0 {
+                        self.dead_enemies.push(enemy_idx);
+                        let score = self.config.enemy(enemy.enemy_type).score;
+                        self.score += score;
+                        self.events.push(GameEvent::EnemyKilled {
+                            id: enemy.id,
+                            enemy_type: enemy.enemy_type,
+                            x: enemy.x,
+                            y: enemy.y,
+                            score,
+                        });
+
+                        // Track enemies killed and update growth level
+                        self.player.enemies_killed += 1;
+                        let new_growth_level = (self.player.enemies_killed / 10).min(5);
+                        if new_growth_level > self.player.growth_level {
+                            self.player.growth_level = new_growth_level;
+                        }
+
+                        if let Some(Ability::Split { .. }) = self.config.enemy(enemy.enemy_type).ability {
+                            self.splits.push((enemy.enemy_type, enemy.x, enemy.y));
+                        }
+
+                        // Create explosion for tank enemies
+                        if matches!(enemy.enemy_type, EnemyType::Tank | EnemyType::Carrier) {
+                            self.explosions.push(Explosion {
+                                x: enemy.x,
+                                y: enemy.y,
+                                size: enemy.size * 2.0,
+                                life: 1.0,
+                                max_life: 1.0,
+                            });
+                        }
+                    }
+                    break; // Only hit one enemy per bullet
+                }
+            }
+        }
+
+        // Bullets that hit aren't looked at again, remove them now
+        self.dead.reverse();
+        self.bullets.remove_descending(&self.dead, |bullet| self.ids.free(bullet.id));
+
+        // Enemy bullets vs player, a vectorised circle test over all of them
+        self.enemy_bullets
+            .overlapping(self.player.x, self.player.y, self.player.size, &mut self.dead);
+        for i in 0..self.dead.len() {
+            let bullet = self.enemy_bullets.get(self.dead[i]);
+            self.hit_player(bullet.id, DamageSource::EnemyBullet, bullet.x, bullet.y, bullet.damage, 1);
+        }
+
+        // Remove bullets that hit the player
+        self.dead.reverse();
+        self.enemy_bullets.remove_descending(&self.dead, |bullet| self.ids.free(bullet.id));
+
+        // Enemies vs player. Enemies haven't moved or been removed since
+        // the grid was built.
+        self.enemy_grid
+            .query(self.player.x, self.player.y, self.player.size, &mut self.candidates);
+        for i in 0..self.candidates.len() {
+            let enemy_idx = self.candidates[i] as usize;
+            let enemy = &self.enemies[enemy_idx];
+
+            // Safety check: ensure enemy is valid
+            if enemy.health <= 0.0 || enemy.size <= 0.0 {
+                self.dead_enemies.push(enemy_idx);
+                continue;
+            }
+
+            let dx = enemy.x - self.player.x;
+            let dy = enemy.y - self.player.y;
+            let reach = enemy.size + self.player.size;
+
+            if dx * dx + dy * dy < reach * reach {
+                let (id, x, y) = (enemy.id, enemy.x, enemy.y);
+                let contact_damage = self.config.enemy(enemy.enemy_type).contact_damage * self.config.difficulty.damage;
+                // Tanks take more than one shield level to stop
+                let shield_cost = match enemy.enemy_type {
+                    EnemyType::Tank => self.config.shield.tank_cost,
+                    _ => 1,
+                };
+
+                if self.hit_player(id, DamageSource::EnemyCollision, x, y, contact_damage, shield_cost) {
+                    // The shield destroyed it
+                    self.dead_enemies.push(enemy_idx);
+                }
+            }
+        }
+
+        // Enemies destroyed by shield or invalid are removed together with
+        // the ones killed by bullets, so indices are never reused after a removal
+        self.dead_enemies.sort_unstable_by(|a, b| b.cmp(a));
+        self.dead_enemies.dedup();
+        self.enemies.remove_descending(&self.dead_enemies, |enemy| self.ids.free(enemy.id));
+        self.split_enemies();
+
+        // Power-ups vs player
+        self.dead.clear();
+        self.grid.rebuild(
+            self.width,
+            self.height,
+            self.power_ups.iter().map(|power_up| (power_up.x, power_up.y, power_up.size)),
+        );
+        self.grid.query(self.player.x, self.player.y, self.player.size, &mut self.candidates);
+        for &power_up_idx in &self.candidates {
+            let power_up_idx = power_up_idx as usize;
+            let power_up = &self.power_ups[power_up_idx];
+            let dx = power_up.x - self.player.x;
+            let dy = power_up.y - self.player.y;
+            let reach = power_up.size + self.player.size;
+
+            if dx * dx + dy * dy < reach * reach {
+                match power_up.power_type {
+                    PowerUpType::Health => {
+                        self.player.max_health += self.config.power_ups.max_health_bonus;
+                        self.player.health = (self.player.health + self.config.power_ups.heal).min(self.player.max_health);
+                    }
+                    PowerUpType::Weapon => {
+                        self.player.power_level = (self.player.power_level + 1).min(self.config.weapon.max_level);
+                    }
+                    PowerUpType::Shield => {
+                        // Increase shield level (separate from health)
+                        self.shield_level = (self.shield_level + 1).min(self.config.shield.max_level);
+                        self.shield_active = true;
+                        self.shield_timer = self.config.shield.duration;
+                        // No health bonus - shield is separate system
+                    }
+                }
+                self.dead.push(power_up_idx);
+                self.events.push(GameEvent::PowerUpCollected {
+                    id: power_up.id,
+                    power_type: power_up.power_type,
+                    x: power_up.x,
+                    y: power_up.y,
+                });
+            }
+        }
+
+        self.dead.reverse();
+        self.power_ups.remove_descending(&self.dead, |power_up| self.ids.free(power_up.id));
+    }
+
+    pub fn cleanup(&mut self) {
+        let (width, height) = (self.width, self.height);
+
+        // Remove off-screen bullets
+        self.bullets
+            .retain_between(-50.0, height + 50.0, |bullet| self.ids.free(bullet.id));
+        self.enemy_bullets
+            .retain_between(-50.0, height + 50.0, |bullet| self.ids.free(bullet.id));
+
+        // Remove off-screen enemies and invalid enemies. Off the top and
+        // sides only counts while heading away, as enemies enter from there.
+        let on_screen = |enemy: &Enemy| {
+            enemy.y < height + 100.0
+                && (enemy.y > -150.0 || enemy.vy >= 0.0)
+                && (enemy.x > -100.0 || enemy.vx >= 0.0)
+                && (enemy.x < width + 100.0 || enemy.vx <= 0.0)
+        };
+        self.enemies.retain(
+            |enemy| on_screen(enemy) && enemy.health > 0.0 && enemy.size > 0.0,
+            |enemy| self.ids.free(enemy.id),
+        );
+
+        // Remove off-screen power-ups
+        self.power_ups.retain(
+            |power_up| power_up.y < height + 50.0,
+            |power_up| self.ids.free(power_up.id),
+        );
+
+        // Remove spent mines
+        self.mines.retain(|mine| mine.life > 0.0, |mine| self.ids.free(mine.id));
+
+        // Remove dead explosions
+        self.explosions.retain(|explosion| explosion.life > 0.0, |_| {});
+
+        // Remove dead black holes and create explosions
+        self.dead.clear();
+        for (i, black_hole) in self.black_holes.iter().enumerate() {
+            if black_hole.life <= 0.0 {
+                self.dead.push(i);
+
+                // Create massive explosion when black hole expires
+                let explosion_size = black_hole.size * 3.0 + black_hole.consumed_enemies.len() as f32 * 10.0;
+                self.explosions.push(Explosion {
+                    x: black_hole.x,
+                    y: black_hole.y,
+                    size: explosion_size,
+                    life: 2.0, // Longer explosion
+                    max_life: 2.0,
+                });
+
+                // Add score for consumed enemies
+                let consumed = black_hole.consumed_enemies.len() as u32;
+                let score = consumed * self.config.black_hole.score_per_enemy;
+                self.score += score;
+                self.events.push(GameEvent::BlackHoleCollapsed {
+                    id: black_hole.id,
+                    x: black_hole.x,
+                    y: black_hole.y,
+                    consumed,
+                    score,
+                });
+            }
+        }
+
+        // Remove dead black holes
+        self.dead.reverse();
+        self.black_holes.remove_descending(&self.dead, |black_hole| self.ids.free(black_hole.id));
+    }
+
+    // Flood the screen with player bullets and enemies to profile the
+    // collision passes. The player can't die meanwhile. Not recorded in
+    // replays, so stressed runs don't play back.
+    pub fn set_stress_mode(&mut self, bullets: u32) {
+        self.stress_bullets = bullets;
+    }
+
+    pub fn stress_mode(&self) -> u32 {
+        self.stress_bullets
+    }
+
+    fn fill_stress(&mut self) {
+        self.player.health = self.player.max_health;
+
+        while self.bullets.len() < (self.stress_bullets as usize).min(self.bullets.limit()) {
+            let x = self.rng.next_f32() * self.width;
+            let y = self.height + self.rng.next_f32() * 40.0;
+            let vx = (self.rng.next_f32() - 0.5) * 100.0;
+            self.add_bullet(Bullet {
+                id: EntityId::default(),
+                x,
+                y,
+                prev_x: x,
+                prev_y: y,
+                vx,
+                vy: -300.0,
+                size: 8.0,
+                // Low damage so enemies soak up many hits each
+                damage: 1.0,
+            });
+        }
+
+        // One stationary enemy per 20 bullets in the top half
+        while self.enemies.len() < (self.stress_bullets as usize / 20).min(self.enemies.limit()) {
+            let x = self.rng.next_f32() * self.width;
+            let y = self.rng.next_f32() * self.height * 0.5;
+            self.add_enemy(Enemy {
+                id: EntityId::default(),
+                x,
+                y,
+                prev_x: x,
+                prev_y: y,
+                vx: 0.0,
+                vy: 0.0,
+                health: 50.0,
+                max_health: 50.0,
+                size: 25.0,
+                enemy_type: EnemyType::Tank,
+                shoot_cooldown: 0.0,
+                emitter: Emitter::default(),
+                movement: Movement::Straight,
+                movement_time: 0.0,
+                held: 0.0,
+                ai: AiState::Attack,
+                ai_time: 0.0,
+                squad: None,
+                ability_timer: 0.0,
+                laser: None,
+            });
+        }
+    }
+
+    // Damage the player, or use up shield levels instead if there are
+    // enough left. Returns whether the shield took the hit.
+    pub(crate) fn hit_player(
+        &mut self,
+        id: EntityId,
+        source: DamageSource,
+        x: f32,
+        y: f32,
+        damage: f32,
+        shield_cost: u32,
+    ) -> bool {
+        if self.shield_active && self.shield_level >= shield_cost {
+            self.shield_level -= shield_cost;
+            if self.shield_level == 0 {
+                self.shield_active = false;
+                self.shield_timer = 0.0;
+            }
+            self.events.push(GameEvent::ShieldAbsorbed {
+                id,
+                source,
+                x,
+                y,
+                shield_level: self.shield_level,
+            });
+            return true;
+        }
+
+        self.player.health -= damage;
+        self.events.push(GameEvent::PlayerHit { id, source, x, y, damage });
+        self.player.growth_level = self.player.growth_level.saturating_sub(1);
+        if self.player.health <= 0.0 {
+            self.game_over = true;
+        }
+        false
+    }
+
+    pub fn move_player(&mut self, dx: f32, dy: f32) {
+        self.player.vx = dx;
+        self.player.vy = dy;
+    }
+
+    pub fn shoot(&mut self) {
+        if self.player.shoot_cooldown <= 0.0 {
+            let weapon = &self.config.weapon;
+            let bullet_speed = weapon.bullet_speed;
+            let bullet_size = weapon.bullet_size;
+            let bullet_damage = weapon.damage_per_level * self.player.power_level as f32;
+
+            let y = self.player.y - self.player.size;
+
+            match self.player.power_level {
+                1 => {
+                    let x = self.player.x;
+                    spawn(&mut self.bullets, &mut self.ids, |id| Bullet {
+                        id,
+                        x,
+                        y,
+                        prev_x: x,
+                        prev_y: y,
+                        vx: 0.0,
+                        vy: -bullet_speed,
+                        size: bullet_size,
+                        damage: bullet_damage,
+                    });
+                }
+                2 => {
+                    for offset in [-10.0, 10.0] {
+                        let x = self.player.x + offset;
+                        spawn(&mut self.bullets, &mut self.ids, |id| Bullet {
+                            id,
+                            x,
+                            y,
+                            prev_x: x,
+                            prev_y: y,
+                            vx: 0.0,
+                            vy: -bullet_speed,
+                            size: bullet_size,
+                            damage: bullet_damage,
+                        });
+                    }
+                }
+                // Level 3 spread, also used by any higher levels a config allows
+                _ => {
+                    for i in -1..=1 {
+                        let x = self.player.x + i as f32 * 15.0;
+                        spawn(&mut self.bullets, &mut self.ids, |id| Bullet {
+                            id,
+                            x,
+                            y,
+                            prev_x: x,
+                            prev_y: y,
+                            vx: i as f32 * 50.0,
+                            vy: -bullet_speed,
+                            size: bullet_size,
+                            damage: bullet_damage,
+                        });
+                    }
+                }
+            }
+
+            self.player.shoot_cooldown = weapon.cooldown;
+        }
+    }
+
+    pub fn activate_black_hole(&mut self) {
+        if self.player.black_hole_cooldown <= 0.0 {
+            // Calculate target position in front of player
+            let black_hole = &self.config.black_hole;
+            let target_x = self.player.x;
+            let target_y = self.player.y - black_hole.distance; // Shoot upward
+
+            // Create black hole at target position
+            let spawned = spawn(&mut self.black_holes, &mut self.ids, |id| BlackHole {
+                id,
+                x: target_x,
+                y: target_y,
+                size: black_hole.size,
+                life: black_hole.duration,
+                max_life: black_hole.duration,
+                pull_radius: black_hole.pull_radius,
+                consumed_enemies: Vec::new(),
+            });
+
+            if let Some(id) = spawned {
+                self.events.push(GameEvent::BlackHoleOpened {
+                    id,
+                    x: target_x,
+                    y: target_y,
+                });
+
+                self.player.black_hole_cooldown = black_hole.cooldown;
+            }
+        }
+    }
+
+    // Add entities with a fresh id, replacing whatever id they carried.
+    // None when that kind's pool is full.
+    pub fn add_enemy(&mut self, enemy: Enemy) -> Option<EntityId> {
+        spawn(&mut self.enemies, &mut self.ids, |id| Enemy { id, ..enemy })
+    }
+
+    pub fn add_bullet(&mut self, bullet: Bullet) -> Option<EntityId> {
+        spawn(&mut self.bullets, &mut self.ids, |id| Bullet { id, ..bullet })
+    }
+
+    pub fn add_enemy_bullet(&mut self, bullet: Bullet) -> Option<EntityId> {
+        spawn(&mut self.enemy_bullets, &mut self.ids, |id| Bullet { id, ..bullet })
+    }
+
+    pub fn add_power_up(&mut self, power_up: PowerUp) -> Option<EntityId> {
+        spawn(&mut self.power_ups, &mut self.ids, |id| PowerUp { id, ..power_up })
+    }
+
+    pub fn events(&self) -> &[GameEvent] {
+        &self.events
+    }
+
+    pub fn drain_events(&mut self) -> Vec<GameEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn rng_state(&self) -> u64 {
+        self.rng.state()
+    }
+
+    pub fn reset(&mut self) {
+        self.player = Player::new(self.width, self.height, &self.config.player);
+        self.enemies.clear();
+        self.bullets.clear();
+        self.enemy_bullets.clear();
+        self.power_ups.clear();
+        self.explosions.clear();
+        self.black_holes.clear();
+        self.mines.clear();
+        self.splits.clear();
+        self.launches.clear();
+        self.stage_spawns.clear();
+        self.boss = None;
+        self.bosses_defeated = 0;
+        self.formations.clear();
+        self.ids = IdAllocator::new();
+        self.score = 0;
+        self.level = 1;
+        self.game_time = 0.0;
+        self.enemy_spawn_timer = 0.0;
+        self.squadron_spawn_timer = 0.0;
+        self.power_up_spawn_timer = 0.0;
+        self.difficulty = DynamicDifficulty::default();
+        self.stage = StageProgress::default();
+        self.game_over = false;
+        // Reset shield system
+        self.shield_level = 0;
+        self.shield_active = false;
+        self.shield_timer = 0.0;
+        self.accumulator = 0.0;
+        self.tick = 0;
+        self.input = InputFrame::default();
+        self.input_queue.clear();
+        self.playback = None;
+        self.events.clear();
+        // Each run gets its own seed, derived from the previous one,
+        // so new_with_seed(get_seed()) reproduces the current run
+        self.seed = self.rng.next_u64();
+        self.rng = Rng::new(self.seed);
+        self.replay = Replay::new(self.seed, self.width, self.height, self.fixed_timestep, self.config.clone());
+    }
+}
+
+// Push a new entity with a fresh id. Full pools drop it without using
+// up an id.
+pub(crate) fn spawn<T>(pool: &mut impl Storage<T>, ids: &mut IdAllocator, make: impl FnOnce(EntityId) -> T) -> Option<EntityId> {
+    if pool.is_full() {
+        return None;
+    }
+    let id = ids.alloc();
+    pool.push(make(id));
+    Some(id)
+}
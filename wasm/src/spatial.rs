@@ -0,0 +1,84 @@
+// Uniform grid broad phase, rebuilt every tick. Each entity is bucketed
+// into every cell its bounding box covers, and queries hand back the
+// candidates that still need an exact circle test.
+pub struct SpatialGrid {
+    cell_size: f32,
+    // Entities past the covered area are clamped into the edge cells, so
+    // queries anywhere stay correct, just less selective out there
+    min_x: f32,
+    min_y: f32,
+    cols: i32,
+    rows: i32,
+    // Entity indices per cell, row-major. Cleared, not freed, on rebuild
+    cells: Vec<Vec<u32>>,
+}
+
+// Margin covered around the screen, where entities spawn and despawn
+const MARGIN: f32 = 100.0;
+
+impl SpatialGrid {
+    pub fn new(cell_size: f32) -> SpatialGrid {
+        SpatialGrid {
+            cell_size,
+            min_x: 0.0,
+            min_y: 0.0,
+            cols: 0,
+            rows: 0,
+            cells: Vec::new(),
+        }
+    }
+
+    // Rebuild over a width x height screen from (x, y, radius) per
+    // entity, in index order
+    pub fn rebuild(&mut self, width: f32, height: f32, items: impl Iterator<Item = (f32, f32, f32)>) {
+        self.min_x = -MARGIN;
+        self.min_y = -MARGIN;
+        self.cols = (((width + 2.0 * MARGIN) / self.cell_size).ceil() as i32).max(1);
+        self.rows = (((height + 2.0 * MARGIN) / self.cell_size).ceil() as i32).max(1);
+
+        let len = (self.cols * self.rows) as usize;
+        if self.cells.len() != len {
+            self.cells.resize_with(len, Vec::new);
+        }
+        for cell in &mut self.cells {
+            cell.clear();
+        }
+
+        for (i, (x, y, radius)) in items.enumerate() {
+            let (x0, y0, x1, y1) = self.cell_range(x, y, radius);
+            for row in y0..=y1 {
+                for col in x0..=x1 {
+                    self.cells[(row * self.cols + col) as usize].push(i as u32);
+                }
+            }
+        }
+    }
+
+    // Indices of entities sharing a cell with the circle's bounding box,
+    // ascending and without duplicates, so callers see them in the same
+    // order as a full scan would
+    pub fn query(&self, x: f32, y: f32, radius: f32, out: &mut Vec<u32>) {
+        out.clear();
+        if self.cells.is_empty() {
+            return;
+        }
+
+        let (x0, y0, x1, y1) = self.cell_range(x, y, radius);
+        for row in y0..=y1 {
+            for col in x0..=x1 {
+                out.extend_from_slice(&self.cells[(row * self.cols + col) as usize]);
+            }
+        }
+
+        if x0 != x1 || y0 != y1 {
+            out.sort_unstable();
+            out.dedup();
+        }
+    }
+
+    fn cell_range(&self, x: f32, y: f32, radius: f32) -> (i32, i32, i32, i32) {
+        let col = |x: f32| (((x - self.min_x) / self.cell_size).floor() as i32).clamp(0, self.cols - 1);
+        let row = |y: f32| (((y - self.min_y) / self.cell_size).floor() as i32).clamp(0, self.rows - 1);
+        (col(x - radius), row(y - radius), col(x + radius), row(y + radius))
+    }
+}
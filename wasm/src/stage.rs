@@ -0,0 +1,205 @@
+use serde::{Deserialize, Serialize};
+
+use crate::config::ConfigError;
+use crate::entities::EnemyType;
+use crate::formation::Squadron;
+use crate::movement::Movement;
+
+// A hand-designed stage: steps run in order, spawning groups of enemies,
+// waiting for a while or until the screen is clear. Written as JSON, e.g.
+//
+// { "steps": [
+//     { "spawn": { "enemy": "basic", "count": 5, "x": 0.2, "spacing": 60 } },
+//     { "wait": 2.0 },
+//     { "squadron": { "enemy": "fast", "rows": 3, "columns": 6, "path": 1 } },
+//     { "spawn": { "enemy": "tank", "x": 0.5, "movement": "drift" } },
+//     "wait_until_cleared"
+//   ],
+//   "endless": true }
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Stage {
+    pub steps: Vec<Step>,
+    // Go back to random endless spawning once the last step is done
+    #[serde(default)]
+    pub endless: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum Step {
+    // Start spawning a group. The next step runs right away, the group
+    // keeps spawning in the background.
+    Spawn(SpawnGroup),
+    // Send in a squadron that flies into formation. The next step runs
+    // right away.
+    Squadron(Squadron),
+    // Seconds before the next step
+    Wait(f32),
+    // Hold until every group has finished spawning and no enemy or
+    // squadron is left
+    WaitUntilCleared,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SpawnGroup {
+    pub enemy: EnemyType,
+    #[serde(default = "one")]
+    pub count: u32,
+    // Entry point of the first enemy, x as a share of the screen width.
+    // The default y is just above the top edge.
+    pub x: f32,
+    #[serde(default = "entry_y")]
+    pub y: f32,
+    // Horizontal distance between consecutive enemies
+    #[serde(default)]
+    pub spacing: f32,
+    // Seconds between consecutive enemies
+    #[serde(default)]
+    pub interval: f32,
+    // Overrides the enemy type's movement, straight down if neither is set
+    #[serde(default)]
+    pub movement: Option<Movement>,
+}
+
+fn one() -> u32 {
+    1
+}
+
+fn entry_y() -> f32 {
+    -50.0
+}
+
+impl Stage {
+    pub fn from_json(json: &str) -> Result<Stage, ConfigError> {
+        let stage: Stage = serde_json::from_str(json).map_err(|e| ConfigError::Parse(e.to_string()))?;
+        stage.validate("stage")?;
+        Ok(stage)
+    }
+
+    // field is the path of the stage itself, for error messages
+    pub fn validate(&self, field: &str) -> Result<(), ConfigError> {
+        for (i, step) in self.steps.iter().enumerate() {
+            let invalid = |name: &str, reason| Err(ConfigError::Invalid(format!("{}.steps[{}].{}", field, i, name), reason));
+            match step {
+                Step::Spawn(group) => {
+                    if group.count == 0 {
+                        return invalid("spawn.count", "must be at least 1");
+                    }
+                    if !(0.0..=1.0).contains(&group.x) {
+                        return invalid("spawn.x", "must be between 0 and 1");
+                    }
+                    if !group.y.is_finite() {
+                        return invalid("spawn.y", "must be a number");
+                    }
+                    if !group.spacing.is_finite() {
+                        return invalid("spawn.spacing", "must be a number");
+                    }
+                    if !(group.interval.is_finite() && group.interval >= 0.0) {
+                        return invalid("spawn.interval", "must be zero or more");
+                    }
+                    if let Some(movement) = &group.movement {
+                        movement.validate(&format!("{}.steps[{}].spawn.movement", field, i))?;
+                    }
+                }
+                Step::Squadron(squadron) => {
+                    squadron.validate(&format!("{}.steps[{}].squadron", field, i))?;
+                }
+                Step::Wait(seconds) => {
+                    if !(seconds.is_finite() && *seconds >= 0.0) {
+                        return invalid("wait", "must be zero or more");
+                    }
+                }
+                Step::WaitUntilCleared => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+// How far the current run is through its stage
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StageProgress {
+    // Next step to run
+    pub step: usize,
+    // Seconds since the stage started
+    pub time: f32,
+    // When the running wait step ends
+    pub resume_at: Option<f32>,
+    // Spawn groups still spawning
+    pub groups: Vec<GroupProgress>,
+    pub complete: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GroupProgress {
+    // The spawn step this group came from
+    pub step: usize,
+    pub spawned: u32,
+    // Seconds until the next enemy
+    pub timer: f32,
+}
+
+impl StageProgress {
+    // Run one tick of the stage and push (step, index in group) for every
+    // enemy due now into spawns, and (step, 0) for squadrons due now.
+    // cleared is whether no enemy or squadron is alive.
+    // Returns true on the tick the stage completes.
+    pub fn update(&mut self, stage: &Stage, delta_time: f32, mut cleared: bool, spawns: &mut Vec<(usize, u32)>) -> bool {
+        spawns.clear();
+        if self.complete {
+            return false;
+        }
+
+        self.time += delta_time;
+        for group in &mut self.groups {
+            group.timer -= delta_time;
+        }
+
+        while let Some(step) = stage.steps.get(self.step) {
+            match step {
+                Step::Spawn(_) => self.groups.push(GroupProgress {
+                    step: self.step,
+                    spawned: 0,
+                    timer: 0.0,
+                }),
+                Step::Squadron(_) => {
+                    spawns.push((self.step, 0));
+                    cleared = false;
+                }
+                Step::Wait(seconds) => {
+                    let resume_at = *self.resume_at.get_or_insert(self.time + seconds);
+                    if self.time < resume_at {
+                        break;
+                    }
+                    self.resume_at = None;
+                }
+                Step::WaitUntilCleared => {
+                    if !(self.groups.is_empty() && cleared) {
+                        break;
+                    }
+                }
+            }
+            self.step += 1;
+        }
+
+        for group in &mut self.groups {
+            let Step::Spawn(spawn) = &stage.steps[group.step] else {
+                continue;
+            };
+            while group.spawned < spawn.count && group.timer <= 0.0 {
+                spawns.push((group.step, group.spawned));
+                group.spawned += 1;
+                group.timer += spawn.interval;
+            }
+        }
+        self.groups.retain(|group| match &stage.steps[group.step] {
+            Step::Spawn(spawn) => group.spawned < spawn.count,
+            _ => false,
+        });
+
+        self.complete = self.step >= stage.steps.len() && self.groups.is_empty();
+        self.complete
+    }
+}
@@ -0,0 +1,893 @@
+use std::fmt;
+
+use crate::ai::AiState;
+use crate::boss::{Boss, BossPart};
+use crate::bytes::{Reader, Truncated, Writer};
+use crate::config::{ConfigError, GameConfig};
+use crate::difficulty::{DifficultySample, DynamicDifficulty};
+use crate::stage::{GroupProgress, Stage, StageProgress, Step};
+use crate::entities::*;
+use crate::formation::{Formation, Squad, Squadron};
+use crate::ids::{EntityId, IdAllocator};
+use crate::input::InputFrame;
+use crate::movement::Movement;
+use crate::pattern::Emitter;
+use crate::pool::Storage;
+use crate::replay::Replay;
+use crate::rng::Rng;
+use crate::sim::{Simulation, MAX_BOSS_PARTS};
+
+const MAGIC: &[u8; 4] = b"PPMS";
+// Bump whenever the layout below changes, older saves are then rejected
+pub const STATE_FORMAT_VERSION: u16 = 12;
+
+#[derive(Debug, PartialEq)]
+pub enum StateError {
+    BadMagic,
+    UnsupportedVersion(u16),
+    Truncated,
+    InvalidEnemyType(u8),
+    InvalidPowerUpType(u8),
+    InvalidIdSlot(u32),
+    TooManyEntities(u32),
+    InvalidConfig(ConfigError),
+    InvalidStageStep(u32),
+    InvalidBossPhase(u32),
+    InvalidMovement(u8),
+    InvalidAiState(u8),
+    InvalidSquadSlot(u32),
+    InvalidPath(u32),
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StateError::BadMagic => write!(f, "not a save state"),
+            StateError::UnsupportedVersion(v) => write!(f, "unsupported save state version {}", v),
+            StateError::Truncated => write!(f, "save state is truncated"),
+            StateError::InvalidEnemyType(t) => write!(f, "invalid enemy type {} in save state", t),
+            StateError::InvalidPowerUpType(t) => write!(f, "invalid power-up type {} in save state", t),
+            StateError::InvalidIdSlot(i) => write!(f, "invalid free id slot {} in save state", i),
+            StateError::TooManyEntities(n) => write!(f, "{} entities of one kind in save state, over the limit", n),
+            StateError::InvalidConfig(e) => write!(f, "save state has a bad game config, {}", e),
+            StateError::InvalidStageStep(s) => write!(f, "invalid stage step {} in save state", s),
+            StateError::InvalidBossPhase(p) => write!(f, "invalid boss phase {} in save state", p),
+            StateError::InvalidMovement(m) => write!(f, "invalid enemy movement {} in save state", m),
+            StateError::InvalidAiState(s) => write!(f, "invalid enemy AI state {} in save state", s),
+            StateError::InvalidSquadSlot(s) => write!(f, "invalid formation slot {} in save state", s),
+            StateError::InvalidPath(p) => write!(f, "invalid movement path {} in save state", p),
+        }
+    }
+}
+
+impl std::error::Error for StateError {}
+
+impl From<Truncated> for StateError {
+    fn from(_: Truncated) -> StateError {
+        StateError::Truncated
+    }
+}
+
+impl Simulation {
+    // Snapshot of the whole game. The replay recording and pending input
+    // queue are not part of it.
+    pub fn save_state(&self) -> Vec<u8> {
+        let config = self.config.to_json();
+        let mut w = Writer::with_capacity(
+            128 + config.len()
+                + self.ids.generations.len() * 8
+                + self.enemies.len() * 100
+                + (self.bullets.len() + self.enemy_bullets.len()) * 40
+                + self.power_ups.len() * 32
+                + self.explosions.len() * 20
+                + self.black_holes.len() * 40
+                + self.mines.len() * 32
+                + self.formations.iter().map(|f| 80 + f.slots.len() * 9).sum::<usize>(),
+        );
+        w.raw(MAGIC);
+        w.u16(STATE_FORMAT_VERSION);
+
+        w.f32(self.width);
+        w.f32(self.height);
+        w.u32(config.len() as u32);
+        w.raw(config.as_bytes());
+        w.u64(self.seed);
+        w.u64(self.rng.state());
+        w.u32(self.tick);
+        w.bool(self.fixed_timestep);
+        w.f32(self.accumulator);
+        write_input(&mut w, &self.input);
+
+        w.u32(self.score);
+        w.u32(self.level);
+        w.f32(self.game_time);
+        w.f32(self.enemy_spawn_timer);
+        w.f32(self.squadron_spawn_timer);
+        w.f32(self.power_up_spawn_timer);
+        write_difficulty(&mut w, &self.difficulty);
+        write_stage(&mut w, &self.stage);
+        w.bool(self.game_over);
+
+        // Shield system
+        w.u32(self.shield_level);
+        w.bool(self.shield_active);
+        w.f32(self.shield_timer);
+
+        write_ids(&mut w, &self.ids);
+        write_player(&mut w, &self.player);
+
+        w.u32(self.enemies.len() as u32);
+        for enemy in &self.enemies {
+            write_enemy(&mut w, enemy);
+        }
+
+        w.u32(self.bullets.len() as u32);
+        for bullet in self.bullets.iter() {
+            write_bullet(&mut w, &bullet);
+        }
+
+        w.u32(self.enemy_bullets.len() as u32);
+        for bullet in self.enemy_bullets.iter() {
+            write_bullet(&mut w, &bullet);
+        }
+
+        w.u32(self.power_ups.len() as u32);
+        for power_up in &self.power_ups {
+            write_power_up(&mut w, power_up);
+        }
+
+        w.u32(self.explosions.len() as u32);
+        for explosion in &self.explosions {
+            write_explosion(&mut w, explosion);
+        }
+
+        w.u32(self.black_holes.len() as u32);
+        for black_hole in &self.black_holes {
+            write_black_hole(&mut w, black_hole);
+        }
+
+        w.u32(self.mines.len() as u32);
+        for mine in &self.mines {
+            write_mine(&mut w, mine);
+        }
+
+        w.u32(self.bosses_defeated);
+        w.bool(self.boss.is_some());
+        if let Some(boss) = &self.boss {
+            write_boss(&mut w, boss);
+        }
+
+        w.u32(self.formations.len() as u32);
+        for formation in &self.formations {
+            write_formation(&mut w, formation);
+        }
+
+        w.bytes
+    }
+
+    // Replace the current game with a saved one. Nothing changes if the
+    // data is rejected.
+    pub fn load_state(&mut self, bytes: &[u8]) -> Result<(), StateError> {
+        let mut sim = Simulation::from_state(bytes)?;
+        // Pause and speed belong to the session, not the save
+        sim.paused = self.paused;
+        sim.time_scale = self.time_scale;
+        sim.auto_pause_after = self.auto_pause_after;
+        *self = sim;
+        Ok(())
+    }
+
+    pub fn from_state(bytes: &[u8]) -> Result<Simulation, StateError> {
+        let mut r = Reader::new(bytes);
+
+        if r.take(4)? != MAGIC {
+            return Err(StateError::BadMagic);
+        }
+        let version = r.u16()?;
+        if version != STATE_FORMAT_VERSION {
+            return Err(StateError::UnsupportedVersion(version));
+        }
+
+        let width = r.f32()?;
+        let height = r.f32()?;
+        let config = read_config(&mut r)?;
+        let seed = r.u64()?;
+        let mut sim = Simulation::with_config(width, height, seed, config);
+        sim.rng = Rng::new(r.u64()?);
+        sim.tick = r.u32()?;
+        sim.fixed_timestep = r.bool()?;
+        sim.accumulator = r.f32()?;
+        sim.input = read_input(&mut r)?;
+
+        sim.score = r.u32()?;
+        sim.level = r.u32()?;
+        sim.game_time = r.f32()?;
+        sim.enemy_spawn_timer = r.f32()?;
+        sim.squadron_spawn_timer = r.f32()?;
+        sim.power_up_spawn_timer = r.f32()?;
+        sim.difficulty = read_difficulty(&mut r)?;
+        sim.stage = read_stage(&mut r, sim.config.stage.as_ref())?;
+        sim.game_over = r.bool()?;
+
+        sim.shield_level = r.u32()?;
+        sim.shield_active = r.bool()?;
+        sim.shield_timer = r.f32()?;
+
+        sim.ids = read_ids(&mut r)?;
+        sim.player = read_player(&mut r)?;
+        read_pool(&mut r, &mut sim.enemies, read_enemy)?;
+        read_pool(&mut r, &mut sim.bullets, read_bullet)?;
+        read_pool(&mut r, &mut sim.enemy_bullets, read_bullet)?;
+        read_pool(&mut r, &mut sim.power_ups, read_power_up)?;
+        read_pool(&mut r, &mut sim.explosions, read_explosion)?;
+        read_pool(&mut r, &mut sim.black_holes, read_black_hole)?;
+        read_pool(&mut r, &mut sim.mines, read_mine)?;
+
+        sim.bosses_defeated = r.u32()?;
+        if r.bool()? {
+            let boss = read_boss(&mut r)?;
+            if boss.phase >= sim.config.boss.phases.len() {
+                return Err(StateError::InvalidBossPhase(boss.phase as u32));
+            }
+            sim.boss = Some(boss);
+        }
+
+        sim.formations = read_list(&mut r, read_formation)?;
+        for formation in &mut sim.formations {
+            formation.layout(sim.width);
+        }
+        // Members must have a slot in one of the formations
+        for enemy in &sim.enemies {
+            let Some(squad) = enemy.squad else {
+                continue;
+            };
+            let formation = sim.formations.iter().find(|f| f.id == squad.formation);
+            if formation.is_none_or(|f| squad.slot as usize >= f.slots.len()) {
+                return Err(StateError::InvalidSquadSlot(squad.slot));
+            }
+        }
+        // Path movements must name one of the config's paths
+        let paths = sim
+            .enemies
+            .iter()
+            .filter_map(|enemy| match enemy.movement {
+                Movement::Path(path) => Some(path),
+                _ => None,
+            })
+            .chain(sim.formations.iter().filter_map(|formation| formation.spec.path));
+        for path in paths {
+            if path as usize >= sim.config.paths.len() {
+                return Err(StateError::InvalidPath(path));
+            }
+        }
+
+        // Recording continues from the loaded state
+        sim.replay = Replay::new(seed, width, height, sim.fixed_timestep, sim.config.clone());
+        sim.replay.initial_state = bytes.to_vec();
+
+        Ok(sim)
+    }
+}
+
+fn read_list<T>(
+    r: &mut Reader,
+    read: fn(&mut Reader) -> Result<T, StateError>,
+) -> Result<Vec<T>, StateError> {
+    let len = r.u32()? as usize;
+    // Every entry takes at least a byte, don't trust the count blindly
+    let mut list = Vec::with_capacity(len.min(r.remaining()));
+    for _ in 0..len {
+        list.push(read(r)?);
+    }
+    Ok(list)
+}
+
+// Fill an empty pool. Saves never hold more than the pool limits.
+fn read_pool<T>(
+    r: &mut Reader,
+    pool: &mut impl Storage<T>,
+    read: fn(&mut Reader) -> Result<T, StateError>,
+) -> Result<(), StateError> {
+    let len = r.u32()?;
+    for _ in 0..len {
+        if !pool.push(read(r)?) {
+            return Err(StateError::TooManyEntities(len));
+        }
+    }
+    Ok(())
+}
+
+// Stored as JSON, the same text GameConfig::from_json reads
+fn read_config(r: &mut Reader) -> Result<GameConfig, StateError> {
+    let len = r.u32()? as usize;
+    let json = std::str::from_utf8(r.take(len)?)
+        .map_err(|_| StateError::InvalidConfig(ConfigError::Parse("not valid UTF-8".to_string())))?;
+    GameConfig::from_json(json).map_err(StateError::InvalidConfig)
+}
+
+fn write_difficulty(w: &mut Writer, difficulty: &DynamicDifficulty) {
+    w.f32(difficulty.factor);
+    w.f32(difficulty.sample_timer);
+    w.u32(difficulty.sample_kills);
+    w.u32(difficulty.samples.len() as u32);
+    for sample in &difficulty.samples {
+        w.f32(sample.health);
+        w.u32(sample.kills);
+    }
+}
+
+fn read_difficulty(r: &mut Reader) -> Result<DynamicDifficulty, StateError> {
+    Ok(DynamicDifficulty {
+        factor: r.f32()?,
+        sample_timer: r.f32()?,
+        sample_kills: r.u32()?,
+        samples: read_list(r, |r| {
+            Ok(DifficultySample {
+                health: r.f32()?,
+                kills: r.u32()?,
+            })
+        })?
+        .into(),
+    })
+}
+
+fn write_stage(w: &mut Writer, progress: &StageProgress) {
+    w.u32(progress.step as u32);
+    w.f32(progress.time);
+    w.bool(progress.resume_at.is_some());
+    w.f32(progress.resume_at.unwrap_or(0.0));
+    w.bool(progress.complete);
+    w.u32(progress.groups.len() as u32);
+    for group in &progress.groups {
+        w.u32(group.step as u32);
+        w.u32(group.spawned);
+        w.f32(group.timer);
+    }
+}
+
+// Groups must point at spawn steps of the saved config's stage
+fn read_stage(r: &mut Reader, stage: Option<&Stage>) -> Result<StageProgress, StateError> {
+    let step = r.u32()? as usize;
+    let time = r.f32()?;
+    let waiting = r.bool()?;
+    let resume_at = r.f32()?;
+    let complete = r.bool()?;
+    let groups = read_list(r, |r| {
+        Ok(GroupProgress {
+            step: r.u32()? as usize,
+            spawned: r.u32()?,
+            timer: r.f32()?,
+        })
+    })?;
+
+    for group in &groups {
+        let steps = stage.map_or(&[][..], |stage| &stage.steps);
+        if !matches!(steps.get(group.step), Some(Step::Spawn(_))) {
+            return Err(StateError::InvalidStageStep(group.step as u32));
+        }
+    }
+
+    Ok(StageProgress {
+        step,
+        time,
+        resume_at: waiting.then_some(resume_at),
+        groups,
+        complete,
+    })
+}
+
+fn write_input(w: &mut Writer, input: &InputFrame) {
+    w.f32(input.move_x);
+    w.f32(input.move_y);
+    w.bool(input.fire);
+    w.bool(input.black_hole);
+}
+
+fn read_input(r: &mut Reader) -> Result<InputFrame, StateError> {
+    Ok(InputFrame {
+        move_x: r.f32()?,
+        move_y: r.f32()?,
+        fire: r.bool()?,
+        black_hole: r.bool()?,
+    })
+}
+
+fn write_id(w: &mut Writer, id: EntityId) {
+    w.u32(id.index);
+    w.u32(id.generation);
+}
+
+fn read_id(r: &mut Reader) -> Result<EntityId, StateError> {
+    Ok(EntityId {
+        index: r.u32()?,
+        generation: r.u32()?,
+    })
+}
+
+// Slot generations and the free list, so ids keep counting up from
+// where the saved game left off
+fn write_ids(w: &mut Writer, ids: &IdAllocator) {
+    w.u32(ids.generations.len() as u32);
+    for &generation in &ids.generations {
+        w.u32(generation);
+    }
+    w.u32(ids.free.len() as u32);
+    for &index in &ids.free {
+        w.u32(index);
+    }
+}
+
+fn read_ids(r: &mut Reader) -> Result<IdAllocator, StateError> {
+    let generations = read_list(r, |r| Ok(r.u32()?))?;
+    let free = read_list(r, |r| Ok(r.u32()?))?;
+    if let Some(&index) = free.iter().find(|&&index| index as usize >= generations.len()) {
+        return Err(StateError::InvalidIdSlot(index));
+    }
+    Ok(IdAllocator { generations, free })
+}
+
+fn write_player(w: &mut Writer, player: &Player) {
+    w.f32(player.x);
+    w.f32(player.y);
+    w.f32(player.prev_x);
+    w.f32(player.prev_y);
+    w.f32(player.vx);
+    w.f32(player.vy);
+    w.f32(player.health);
+    w.f32(player.max_health);
+    w.f32(player.size);
+    w.f32(player.shoot_cooldown);
+    w.u32(player.power_level);
+    w.u32(player.growth_level);
+    w.u32(player.enemies_killed);
+    w.f32(player.black_hole_cooldown);
+}
+
+fn read_player(r: &mut Reader) -> Result<Player, StateError> {
+    Ok(Player {
+        x: r.f32()?,
+        y: r.f32()?,
+        prev_x: r.f32()?,
+        prev_y: r.f32()?,
+        vx: r.f32()?,
+        vy: r.f32()?,
+        health: r.f32()?,
+        max_health: r.f32()?,
+        size: r.f32()?,
+        shoot_cooldown: r.f32()?,
+        power_level: r.u32()?,
+        growth_level: r.u32()?,
+        enemies_killed: r.u32()?,
+        black_hole_cooldown: r.f32()?,
+    })
+}
+
+fn write_enemy(w: &mut Writer, enemy: &Enemy) {
+    write_id(w, enemy.id);
+    w.f32(enemy.x);
+    w.f32(enemy.y);
+    w.f32(enemy.prev_x);
+    w.f32(enemy.prev_y);
+    w.f32(enemy.vx);
+    w.f32(enemy.vy);
+    w.f32(enemy.health);
+    w.f32(enemy.max_health);
+    w.f32(enemy.size);
+    write_enemy_type(w, enemy.enemy_type);
+    w.f32(enemy.shoot_cooldown);
+    write_emitter(w, &enemy.emitter);
+    write_movement(w, &enemy.movement);
+    w.f32(enemy.movement_time);
+    w.f32(enemy.held);
+    w.u8(enemy.ai.id());
+    w.f32(enemy.ai_time);
+    w.bool(enemy.squad.is_some());
+    if let Some(squad) = enemy.squad {
+        write_id(w, squad.formation);
+        w.u32(squad.slot);
+        w.bool(squad.holding);
+    }
+    w.f32(enemy.ability_timer);
+    w.bool(enemy.laser.is_some());
+    w.f32(enemy.laser.unwrap_or(0.0));
+}
+
+fn read_enemy(r: &mut Reader) -> Result<Enemy, StateError> {
+    Ok(Enemy {
+        id: read_id(r)?,
+        x: r.f32()?,
+        y: r.f32()?,
+        prev_x: r.f32()?,
+        prev_y: r.f32()?,
+        vx: r.f32()?,
+        vy: r.f32()?,
+        health: r.f32()?,
+        max_health: r.f32()?,
+        size: r.f32()?,
+        enemy_type: read_enemy_type(r)?,
+        shoot_cooldown: r.f32()?,
+        emitter: read_emitter(r)?,
+        movement: read_movement(r)?,
+        movement_time: r.f32()?,
+        held: r.f32()?,
+        ai: {
+            let id = r.u8()?;
+            AiState::from_id(id).ok_or(StateError::InvalidAiState(id))?
+        },
+        ai_time: r.f32()?,
+        squad: match r.bool()? {
+            true => Some(Squad {
+                formation: read_id(r)?,
+                slot: r.u32()?,
+                holding: r.bool()?,
+            }),
+            false => None,
+        },
+        ability_timer: r.f32()?,
+        laser: {
+            let aiming = r.bool()?;
+            let angle = r.f32()?;
+            aiming.then_some(angle)
+        },
+    })
+}
+
+fn write_enemy_type(w: &mut Writer, enemy_type: EnemyType) {
+    w.u8(enemy_type.id());
+}
+
+fn read_enemy_type(r: &mut Reader) -> Result<EnemyType, StateError> {
+    let id = r.u8()?;
+    EnemyType::from_id(id).ok_or(StateError::InvalidEnemyType(id))
+}
+
+// u8 kind, then the kind's fields
+fn write_movement(w: &mut Writer, movement: &Movement) {
+    match *movement {
+        Movement::Straight => w.u8(0),
+        Movement::Drift => w.u8(1),
+        Movement::Sine { amplitude, frequency } => {
+            w.u8(2);
+            w.f32(amplitude);
+            w.f32(frequency);
+        }
+        Movement::Dive { speed, delay } => {
+            w.u8(3);
+            w.f32(speed);
+            w.f32(delay);
+        }
+        Movement::Strafe { y, speed, hold } => {
+            w.u8(4);
+            w.f32(y);
+            w.f32(speed);
+            w.f32(hold);
+        }
+        Movement::Path(path) => {
+            w.u8(5);
+            w.u32(path);
+        }
+    }
+}
+
+fn read_movement(r: &mut Reader) -> Result<Movement, StateError> {
+    Ok(match r.u8()? {
+        0 => Movement::Straight,
+        1 => Movement::Drift,
+        2 => Movement::Sine {
+            amplitude: r.f32()?,
+            frequency: r.f32()?,
+        },
+        3 => Movement::Dive {
+            speed: r.f32()?,
+            delay: r.f32()?,
+        },
+        4 => Movement::Strafe {
+            y: r.f32()?,
+            speed: r.f32()?,
+            hold: r.f32()?,
+        },
+        5 => Movement::Path(r.u32()?),
+        m => return Err(StateError::InvalidMovement(m)),
+    })
+}
+
+fn write_emitter(w: &mut Writer, emitter: &Emitter) {
+    w.f32(emitter.timer);
+    w.u32(emitter.burst_left);
+    w.f32(emitter.rotation);
+}
+
+fn read_emitter(r: &mut Reader) -> Result<Emitter, StateError> {
+    Ok(Emitter {
+        timer: r.f32()?,
+        burst_left: r.u32()?,
+        rotation: r.f32()?,
+    })
+}
+
+fn write_bullet(w: &mut Writer, bullet: &Bullet) {
+    write_id(w, bullet.id);
+    w.f32(bullet.x);
+    w.f32(bullet.y);
+    w.f32(bullet.prev_x);
+    w.f32(bullet.prev_y);
+    w.f32(bullet.vx);
+    w.f32(bullet.vy);
+    w.f32(bullet.size);
+    w.f32(bullet.damage);
+}
+
+fn read_bullet(r: &mut Reader) -> Result<Bullet, StateError> {
+    Ok(Bullet {
+        id: read_id(r)?,
+        x: r.f32()?,
+        y: r.f32()?,
+        prev_x: r.f32()?,
+        prev_y: r.f32()?,
+        vx: r.f32()?,
+        vy: r.f32()?,
+        size: r.f32()?,
+        damage: r.f32()?,
+    })
+}
+
+fn write_power_up(w: &mut Writer, power_up: &PowerUp) {
+    write_id(w, power_up.id);
+    w.f32(power_up.x);
+    w.f32(power_up.y);
+    w.f32(power_up.prev_x);
+    w.f32(power_up.prev_y);
+    w.f32(power_up.vy);
+    w.f32(power_up.size);
+    w.u8(match power_up.power_type {
+        PowerUpType::Health => 0,
+        PowerUpType::Weapon => 1,
+        PowerUpType::Shield => 2,
+    });
+}
+
+fn read_power_up(r: &mut Reader) -> Result<PowerUp, StateError> {
+    Ok(PowerUp {
+        id: read_id(r)?,
+        x: r.f32()?,
+        y: r.f32()?,
+        prev_x: r.f32()?,
+        prev_y: r.f32()?,
+        vy: r.f32()?,
+        size: r.f32()?,
+        power_type: match r.u8()? {
+            0 => PowerUpType::Health,
+            1 => PowerUpType::Weapon,
+            2 => PowerUpType::Shield,
+            t => return Err(StateError::InvalidPowerUpType(t)),
+        },
+    })
+}
+
+fn write_explosion(w: &mut Writer, explosion: &Explosion) {
+    w.f32(explosion.x);
+    w.f32(explosion.y);
+    w.f32(explosion.size);
+    w.f32(explosion.life);
+    w.f32(explosion.max_life);
+}
+
+fn read_explosion(r: &mut Reader) -> Result<Explosion, StateError> {
+    Ok(Explosion {
+        x: r.f32()?,
+        y: r.f32()?,
+        size: r.f32()?,
+        life: r.f32()?,
+        max_life: r.f32()?,
+    })
+}
+
+fn write_black_hole(w: &mut Writer, black_hole: &BlackHole) {
+    write_id(w, black_hole.id);
+    w.f32(black_hole.x);
+    w.f32(black_hole.y);
+    w.f32(black_hole.size);
+    w.f32(black_hole.life);
+    w.f32(black_hole.max_life);
+    w.f32(black_hole.pull_radius);
+    w.u32(black_hole.consumed_enemies.len() as u32);
+    for &(x, y) in &black_hole.consumed_enemies {
+        w.f32(x);
+        w.f32(y);
+    }
+}
+
+fn read_black_hole(r: &mut Reader) -> Result<BlackHole, StateError> {
+    let id = read_id(r)?;
+    let x = r.f32()?;
+    let y = r.f32()?;
+    let size = r.f32()?;
+    let life = r.f32()?;
+    let max_life = r.f32()?;
+    let pull_radius = r.f32()?;
+    let consumed = r.u32()? as usize;
+    let mut consumed_enemies = Vec::with_capacity(consumed.min(r.remaining()));
+    for _ in 0..consumed {
+        consumed_enemies.push((r.f32()?, r.f32()?));
+    }
+
+    Ok(BlackHole {
+        id,
+        x,
+        y,
+        size,
+        life,
+        max_life,
+        pull_radius,
+        consumed_enemies,
+    })
+}
+
+fn write_mine(w: &mut Writer, mine: &Mine) {
+    write_id(w, mine.id);
+    w.f32(mine.x);
+    w.f32(mine.y);
+    w.f32(mine.size);
+    w.f32(mine.damage);
+    w.f32(mine.life);
+    w.f32(mine.max_life);
+}
+
+fn read_mine(r: &mut Reader) -> Result<Mine, StateError> {
+    Ok(Mine {
+        id: read_id(r)?,
+        x: r.f32()?,
+        y: r.f32()?,
+        size: r.f32()?,
+        damage: r.f32()?,
+        life: r.f32()?,
+        max_life: r.f32()?,
+    })
+}
+
+fn write_boss(w: &mut Writer, boss: &Boss) {
+    write_id(w, boss.id);
+    w.f32(boss.x);
+    w.f32(boss.y);
+    w.f32(boss.prev_x);
+    w.f32(boss.prev_y);
+    w.f32(boss.vx);
+    w.f32(boss.vy);
+    w.f32(boss.size);
+    w.f32(boss.health);
+    w.f32(boss.max_health);
+    w.u32(boss.phase as u32);
+    w.f32(boss.phase_time);
+    write_emitter(w, &boss.emitter);
+    w.bool(boss.entering);
+    w.u32(boss.parts.len() as u32);
+    for part in &boss.parts {
+        w.f32(part.offset_x);
+        w.f32(part.offset_y);
+        w.f32(part.size);
+        w.f32(part.health);
+        w.f32(part.max_health);
+        write_emitter(w, &part.emitter);
+    }
+}
+
+fn read_boss(r: &mut Reader) -> Result<Boss, StateError> {
+    Ok(Boss {
+        id: read_id(r)?,
+        x: r.f32()?,
+        y: r.f32()?,
+        prev_x: r.f32()?,
+        prev_y: r.f32()?,
+        vx: r.f32()?,
+        vy: r.f32()?,
+        size: r.f32()?,
+        health: r.f32()?,
+        max_health: r.f32()?,
+        phase: r.u32()? as usize,
+        phase_time: r.f32()?,
+        emitter: read_emitter(r)?,
+        entering: r.bool()?,
+        parts: read_boss_parts(r)?,
+    })
+}
+
+// Never more than a config allows, so they always fit the render buffer
+fn read_boss_parts(r: &mut Reader) -> Result<Vec<BossPart>, StateError> {
+    let parts = read_list(r, |r| {
+            Ok(BossPart {
+                offset_x: r.f32()?,
+                offset_y: r.f32()?,
+                size: r.f32()?,
+                health: r.f32()?,
+                max_health: r.f32()?,
+                emitter: read_emitter(r)?,
+            })
+        })?;
+    if parts.len() > MAX_BOSS_PARTS {
+        return Err(StateError::TooManyEntities(parts.len() as u32));
+    }
+    Ok(parts)
+}
+
+// Slot positions aren't saved, they are laid out again on load
+fn write_formation(w: &mut Writer, formation: &Formation) {
+    write_id(w, formation.id);
+    let spec = &formation.spec;
+    write_enemy_type(w, spec.enemy);
+    w.u32(spec.rows);
+    w.u32(spec.columns);
+    w.f32(spec.spacing);
+    w.f32(spec.x);
+    w.f32(spec.y);
+    w.f32(spec.entry_x);
+    w.bool(spec.path.is_some());
+    w.u32(spec.path.unwrap_or(0));
+    w.f32(spec.interval);
+    w.f32(spec.join_speed);
+    w.f32(spec.dive_interval);
+    w.f32(spec.dive_speed);
+    w.f32(spec.sway);
+    w.f32(formation.time);
+    w.u32(formation.spawned);
+    w.f32(formation.spawn_timer);
+    w.f32(formation.dive_timer);
+    w.bool(formation.release.is_some());
+    w.u32(formation.release.unwrap_or(0));
+    w.u32(formation.slots.len() as u32);
+    for slot in &formation.slots {
+        w.bool(slot.is_some());
+        write_id(w, slot.unwrap_or_default());
+    }
+}
+
+fn read_formation(r: &mut Reader) -> Result<Formation, StateError> {
+    let id = read_id(r)?;
+    let spec = Squadron {
+        enemy: read_enemy_type(r)?,
+        rows: r.u32()?,
+        columns: r.u32()?,
+        spacing: r.f32()?,
+        x: r.f32()?,
+        y: r.f32()?,
+        entry_x: r.f32()?,
+        path: {
+            let some = r.bool()?;
+            let path = r.u32()?;
+            some.then_some(path)
+        },
+        interval: r.f32()?,
+        join_speed: r.f32()?,
+        dive_interval: r.f32()?,
+        dive_speed: r.f32()?,
+        sway: r.f32()?,
+    };
+    spec.validate("squadron").map_err(StateError::InvalidConfig)?;
+    let time = r.f32()?;
+    let spawned = r.u32()?;
+    let spawn_timer = r.f32()?;
+    let dive_timer = r.f32()?;
+    let releasing = r.bool()?;
+    let release = r.u32()?;
+    let slots = read_list(r, |r| {
+        let some = r.bool()?;
+        let id = read_id(r)?;
+        Ok(some.then_some(id))
+    })?;
+
+    // One slot per grid cell, checked before the grid is allocated
+    if spec.rows.checked_mul(spec.columns) != Some(slots.len() as u32) {
+        return Err(StateError::InvalidSquadSlot(slots.len() as u32));
+    }
+    if releasing && release as usize >= slots.len() {
+        return Err(StateError::InvalidSquadSlot(release));
+    }
+    Ok(Formation {
+        time,
+        spawned,
+        spawn_timer,
+        dive_timer,
+        slots,
+        release: releasing.then_some(release),
+        ..Formation::new(id, spec)
+    })
+}
@@ -0,0 +1,35 @@
+// Logical resolution new games are simulated in, whatever the screen
+// size. Positions, speeds and culling edges are all in these units, so
+// the game plays the same on every display and only the drawing scales.
+pub const LOGICAL_WIDTH: f32 = 1280.0;
+pub const LOGICAL_HEIGHT: f32 = 720.0;
+
+// Maps world (logical) coordinates to screen pixels: a uniform scale
+// plus an offset that centres the world, leaving bars on the sides or
+// top and bottom when the aspect ratios differ
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ViewTransform {
+    pub scale: f32,
+    pub offset_x: f32,
+    pub offset_y: f32,
+}
+
+impl ViewTransform {
+    // Largest scale that fits the whole world on screen
+    pub fn fit(world_width: f32, world_height: f32, screen_width: f32, screen_height: f32) -> ViewTransform {
+        let scale = (screen_width / world_width).min(screen_height / world_height);
+        ViewTransform {
+            scale,
+            offset_x: (screen_width - world_width * scale) * 0.5,
+            offset_y: (screen_height - world_height * scale) * 0.5,
+        }
+    }
+
+    pub fn to_screen(&self, x: f32, y: f32) -> (f32, f32) {
+        (x * self.scale + self.offset_x, y * self.scale + self.offset_y)
+    }
+
+    pub fn to_world(&self, x: f32, y: f32) -> (f32, f32) {
+        ((x - self.offset_x) / self.scale, (y - self.offset_y) / self.scale)
+    }
+}
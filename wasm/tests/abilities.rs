@@ -0,0 +1,256 @@
+mod common;
+
+use common::*;
+use particle_system::ability::Ability;
+use particle_system::entities::*;
+use particle_system::events::DamageSource;
+use particle_system::movement::Movement;
+use particle_system::render::{CHANNELS, CHANNEL_ENEMIES, CHANNEL_MINES};
+use particle_system::sim::FIXED_TIMESTEP;
+use particle_system::{GameConfig, GameEvent, Simulation};
+
+// A sim where only abilities fire
+fn quiet() -> Simulation {
+    let mut sim = sim();
+    sim.config.enemy_fire.chance = 0.0;
+    sim
+}
+
+fn run(sim: &mut Simulation, seconds: f32) {
+    for _ in 0..(seconds / FIXED_TIMESTEP).round() as u32 {
+        sim.update_enemies(FIXED_TIMESTEP);
+    }
+}
+
+fn field(channel: usize, name: &str) -> usize {
+    CHANNELS[channel].fields.iter().position(|f| *f == name).unwrap()
+}
+
+#[test]
+fn splitters_break_into_smaller_enemies() {
+    let mut sim = quiet();
+    sim.add_enemy(enemy(400.0, 200.0, EnemyType::Splitter));
+    sim.add_bullet(player_bullet(400.0, 200.0, 100.0));
+    sim.check_collisions();
+
+    assert_eq!(sim.enemies.len(), 3);
+    assert!(sim.enemies.iter().all(|enemy| enemy.enemy_type == EnemyType::Fast));
+    let vx: Vec<f32> = sim.enemies.iter().map(|enemy| enemy.vx).collect();
+    assert_eq!(vx, vec![-60.0, 0.0, 60.0]);
+
+    // The pieces don't split again
+    for enemy in &mut sim.enemies {
+        enemy.health = 0.0;
+    }
+    sim.cleanup();
+    sim.check_collisions();
+    assert!(sim.enemies.is_empty());
+}
+
+#[test]
+fn carriers_launch_drones() {
+    let mut sim = quiet();
+    sim.spawn_enemy_at(EnemyType::Carrier, 400.0, 100.0, Movement::Straight);
+
+    run(&mut sim, 2.9);
+    assert_eq!(sim.enemies.len(), 1);
+    run(&mut sim, 0.2);
+    assert_eq!(sim.enemies.len(), 2);
+    let drone = &sim.enemies[1];
+    assert_eq!(drone.enemy_type, EnemyType::Fast);
+    assert_eq!(drone.movement, Movement::Dive { speed: 220.0, delay: 0.5 });
+    assert!(drone.y > sim.enemies[0].y);
+
+    run(&mut sim, 3.0);
+    assert_eq!(sim.enemies.len(), 3);
+}
+
+#[test]
+fn snipers_show_their_sight_before_firing() {
+    let mut sim = quiet();
+    let (px, py) = (sim.player.x, sim.player.y);
+    sim.spawn_enemy_at(EnemyType::Sniper, px - 300.0, py - 300.0, Movement::Strafe {
+        y: py - 300.0,
+        speed: 0.0,
+        hold: 0.0,
+    });
+
+    // Locked on once the cooldown is up, and not firing yet
+    run(&mut sim, 2.6);
+    let angle = sim.enemies[0].laser.unwrap();
+    assert!((angle - std::f32::consts::FRAC_PI_4).abs() < 1e-3);
+    assert!(sim.enemy_bullets.is_empty());
+
+    let data = sim.render_data();
+    let enemies = data.channel(CHANNEL_ENEMIES);
+    assert_eq!(enemies[field(CHANNEL_ENEMIES, "laser")], 1.0);
+    assert!(enemies[field(CHANNEL_ENEMIES, "laser_charge")] < 0.2);
+
+    // The player moving away doesn't move the sight
+    sim.player.x += 100.0;
+    run(&mut sim, 1.0);
+    assert_eq!(sim.enemy_bullets.len(), 1);
+    let bullet = sim.enemy_bullets.iter().next().unwrap();
+    assert!((bullet.vx - bullet.vy).abs() < 1e-2);
+    assert!((bullet.vx.hypot(bullet.vy) - 450.0).abs() < 1e-2);
+    assert_eq!(sim.enemies[0].laser, None);
+}
+
+#[test]
+fn mines_stay_put_and_hurt_the_player() {
+    let mut sim = quiet();
+    sim.spawn_enemy_at(EnemyType::MineLayer, 400.0, 100.0, Movement::Straight);
+    run(&mut sim, 1.6);
+    assert_eq!(sim.mines.len(), 1);
+    let mine = sim.mines.iter().next().unwrap().clone();
+    assert_eq!((mine.size, mine.damage, mine.life), (10.0, 15.0, 12.0));
+    assert!(mine.y < sim.enemies[0].y);
+
+    // Drawn fading out
+    sim.update_mines(6.0);
+    let data = sim.render_data();
+    assert_eq!(data.channel(CHANNEL_MINES)[field(CHANNEL_MINES, "life_ratio")], 0.5);
+
+    sim.player.x = mine.x;
+    sim.player.y = mine.y;
+    let health = sim.player.health;
+    sim.check_mine_collisions();
+    assert_eq!(sim.player.health, health - 15.0);
+    assert!(sim.mines.is_empty());
+    assert!(matches!(
+        sim.drain_events()[..],
+        [GameEvent::PlayerHit { source: DamageSource::Mine, .. }]
+    ));
+
+    // Soaked up by the shield like a bullet
+    run(&mut sim, 1.5);
+    let mine = sim.mines.iter().next().unwrap().clone();
+    sim.player.x = mine.x;
+    sim.player.y = mine.y;
+    sim.shield_active = true;
+    sim.shield_level = 1;
+    sim.check_mine_collisions();
+    assert_eq!(sim.player.health, health - 15.0);
+    assert!(!sim.shield_active);
+    assert!(matches!(
+        sim.drain_events()[..],
+        [GameEvent::ShieldAbsorbed { source: DamageSource::Mine, shield_level: 0, .. }]
+    ));
+
+    // Gone when they run out
+    run(&mut sim, 1.5);
+    assert_eq!(sim.mines.len(), 1);
+    sim.update_mines(12.0);
+    sim.cleanup();
+    assert!(sim.mines.is_empty());
+}
+
+#[test]
+fn healers_restore_others_nearby() {
+    let mut sim = quiet();
+    let near = sim.add_enemy(enemy(400.0, 200.0, EnemyType::Tank)).unwrap();
+    let far = sim.add_enemy(enemy(600.0, 200.0, EnemyType::Tank)).unwrap();
+    let healer = sim.add_enemy(enemy(450.0, 200.0, EnemyType::Healer)).unwrap();
+    for enemy in &mut sim.enemies {
+        enemy.health = 10.0;
+    }
+
+    sim.update_healers(1.0);
+    let health = |sim: &Simulation, id| sim.enemies.iter().find(|enemy| enemy.id == id).unwrap().health;
+    assert_eq!(health(&sim, near), 18.0);
+    assert_eq!(health(&sim, far), 10.0);
+    assert_eq!(health(&sim, healer), 10.0);
+
+    // Never past full
+    sim.update_healers(1000.0);
+    assert_eq!(health(&sim, near), sim.config.enemies.tank.health);
+}
+
+#[test]
+fn each_type_scores_and_draws_as_itself() {
+    let mut sim = quiet();
+    let mut scores = Vec::new();
+    for (i, &enemy_type) in EnemyType::SPECIAL.iter().enumerate() {
+        let x = 100.0 + i as f32 * 120.0;
+        sim.add_enemy(enemy(x, 200.0, enemy_type));
+        sim.add_bullet(player_bullet(x, 200.0, 1000.0));
+        scores.push(sim.config.enemy(enemy_type).score);
+    }
+
+    let data = sim.render_data();
+    let (enemies, stride) = (data.channel(CHANNEL_ENEMIES), CHANNELS[CHANNEL_ENEMIES].stride());
+    let types: Vec<f32> = (0..5).map(|i| enemies[i * stride + field(CHANNEL_ENEMIES, "type")]).collect();
+    assert_eq!(types, vec![3.0, 4.0, 5.0, 6.0, 7.0]);
+
+    sim.check_collisions();
+    let killed: Vec<(EnemyType, u32)> = sim
+        .drain_events()
+        .into_iter()
+        .filter_map(|event| match event {
+            GameEvent::EnemyKilled { enemy_type, score, .. } => Some((enemy_type, score)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        killed,
+        vec![
+            (EnemyType::Splitter, 250),
+            (EnemyType::Carrier, 500),
+            (EnemyType::Sniper, 350),
+            (EnemyType::MineLayer, 300),
+            (EnemyType::Healer, 400),
+        ]
+    );
+    assert_eq!(scores, vec![250, 500, 350, 300, 400]);
+    for enemy_type in EnemyType::ALL {
+        assert_eq!(EnemyType::from_id(enemy_type.id()), Some(enemy_type));
+    }
+}
+
+#[test]
+fn abilities_are_configured_per_type() {
+    let config = GameConfig::from_json(
+        r#"{"enemies": {"basic": {"ability": {"heal": {"radius": 80, "rate": 5}}}}, "spawn": {"special_chance": 1}}"#,
+    )
+    .unwrap();
+    assert_eq!(config.enemies.basic.ability, Some(Ability::Heal { radius: 80.0, rate: 5.0 }));
+    let mut sim = Simulation::with_config(WIDTH, HEIGHT, 42, config);
+    sim.spawn_enemy();
+    assert!(EnemyType::SPECIAL.contains(&sim.enemies[0].enemy_type));
+
+    let err = GameConfig::from_json(r#"{"enemies": {"carrier": {"ability": {"launch": {"enemy": "fast", "interval": 0}}}}}"#)
+        .unwrap_err();
+    assert_eq!(err.to_string(), "invalid game config: enemies.carrier.ability.launch.interval must be a positive number");
+
+    let err = GameConfig::from_json(r#"{"enemies": {"splitter": {"ability": {"split": {"into": "fast", "count": 0, "spread": 10}}}}}"#)
+        .unwrap_err();
+    assert_eq!(err.to_string(), "invalid game config: enemies.splitter.ability.split.count must be at least 1");
+
+    let err = GameConfig::from_json(r#"{"spawn": {"special_chance": 2}}"#).unwrap_err();
+    assert_eq!(err.to_string(), "invalid game config: spawn.special_chance must be between 0 and 1");
+}
+
+#[test]
+fn saves_keep_mines_and_sights() {
+    let mut sim = sim();
+    sim.spawn_enemy_at(EnemyType::MineLayer, 200.0, 50.0, Movement::Straight);
+    sim.spawn_enemy_at(EnemyType::Sniper, 500.0, 50.0, Movement::Strafe {
+        y: 80.0,
+        speed: 60.0,
+        hold: 0.0,
+    });
+    sim.spawn_enemy_at(EnemyType::Carrier, 350.0, 50.0, Movement::Straight);
+    sim.player.health = 1e9;
+    for _ in 0..170 {
+        sim.update(FIXED_TIMESTEP);
+    }
+    assert!(!sim.mines.is_empty());
+    assert!(sim.enemies.iter().any(|enemy| enemy.laser.is_some()));
+
+    let mut loaded = Simulation::from_state(&sim.save_state()).unwrap();
+    for _ in 0..300 {
+        sim.update(FIXED_TIMESTEP);
+        loaded.update(FIXED_TIMESTEP);
+    }
+    assert_eq!(loaded.save_state(), sim.save_state());
+}
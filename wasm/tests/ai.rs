@@ -0,0 +1,241 @@
+mod common;
+
+use common::*;
+use particle_system::ai::{AiConfig, AiState, FleeConfig, Reaction};
+use particle_system::entities::*;
+use particle_system::movement::Movement;
+use particle_system::render::{CHANNELS, CHANNEL_ENEMIES};
+use particle_system::sim::FIXED_TIMESTEP;
+use particle_system::{GameConfig, Simulation};
+
+fn ai() -> AiConfig {
+    AiConfig {
+        enter_y: 100.0,
+        evade: None,
+        retreat: None,
+        flee: None,
+    }
+}
+
+// A sim where basic enemies always fire when they may, with the AI
+fn with_ai(ai: AiConfig) -> Simulation {
+    let mut sim = sim();
+    sim.config.enemies.basic.ai = Some(ai);
+    sim.config.enemy_fire.chance = 1.0;
+    sim.config.enemy_fire.cooldown = 100.0;
+    sim
+}
+
+fn run(sim: &mut Simulation, seconds: f32) {
+    for _ in 0..(seconds / FIXED_TIMESTEP).round() as u32 {
+        sim.update_enemies(FIXED_TIMESTEP);
+    }
+}
+
+#[test]
+fn enemies_hold_fire_until_they_have_entered() {
+    let mut sim = with_ai(ai());
+    sim.spawn_enemy_at(EnemyType::Basic, 400.0, -50.0, Movement::Straight);
+    assert_eq!(sim.enemies[0].ai, AiState::Enter);
+
+    // Fifty pixels a second to come down 150
+    run(&mut sim, 2.9);
+    assert_eq!(sim.enemies[0].ai, AiState::Enter);
+    assert!(sim.enemy_bullets.is_empty());
+
+    run(&mut sim, 0.2);
+    assert_eq!(sim.enemies[0].ai, AiState::Attack);
+    assert_eq!(sim.enemy_bullets.len(), 1);
+}
+
+#[test]
+fn enemies_sidestep_incoming_bullets() {
+    let mut sim = with_ai(AiConfig {
+        evade: Some(Reaction {
+            range: 150.0,
+            speed: 120.0,
+        }),
+        ..ai()
+    });
+    sim.spawn_enemy_at(EnemyType::Basic, 400.0, 200.0, Movement::Straight);
+    let mut bullet = player_bullet(395.0, 300.0, 10.0);
+    bullet.vy = -300.0;
+    sim.add_bullet(bullet);
+
+    run(&mut sim, 0.1);
+    let enemy = &sim.enemies[0];
+    assert_eq!(enemy.ai, AiState::Evade);
+    assert!(enemy.x > 400.0);
+    assert!(sim.enemy_bullets.is_empty());
+
+    // Back to attacking once clear, carrying on straight down
+    sim.bullets.clear();
+    run(&mut sim, FIXED_TIMESTEP);
+    let enemy = &sim.enemies[0];
+    assert_eq!(enemy.ai, AiState::Attack);
+    assert_eq!((enemy.vx, enemy.vy), (0.0, sim.config.enemies.basic.speed));
+    assert_eq!(sim.enemy_bullets.len(), 1);
+}
+
+#[test]
+fn only_bullets_in_the_strip_below_are_dodged() {
+    let evade = AiConfig {
+        evade: Some(Reaction {
+            range: 300.0,
+            speed: 120.0,
+        }),
+        ..ai()
+    };
+    let size = sim().config.enemies.basic.size;
+    // Bullets just inside the corners of the strip, and just outside it
+    for (dx, dy, dodged) in [
+        (size + 7.0, 299.0, true),
+        (-(size + 7.0), 1.0, true),
+        (size + 9.0, 150.0, false),
+        (0.0, 301.0, false),
+        (0.0, -5.0, false),
+    ] {
+        let mut sim = with_ai(evade);
+        sim.spawn_enemy_at(EnemyType::Basic, 400.0, 100.0, Movement::Straight);
+        // Scattered elsewhere on the screen
+        for i in 0..200 {
+            sim.add_bullet(player_bullet((i * 37 % 800) as f32, 500.0 + (i % 50) as f32, 10.0));
+        }
+        let mut bullet = player_bullet(400.0 + dx, 100.0 + dy, 10.0);
+        bullet.vy = -1.0;
+        sim.add_bullet(bullet);
+
+        sim.update_enemies(FIXED_TIMESTEP);
+        assert_eq!(sim.enemies[0].ai == AiState::Evade, dodged, "bullet at {}, {}", dx, dy);
+    }
+}
+
+#[test]
+fn enemies_back_off_from_a_close_player() {
+    let mut sim = with_ai(AiConfig {
+        retreat: Some(Reaction {
+            range: 150.0,
+            speed: 100.0,
+        }),
+        ..ai()
+    });
+    sim.spawn_enemy_at(EnemyType::Basic, sim.player.x, sim.player.y - 120.0, Movement::Straight);
+
+    run(&mut sim, FIXED_TIMESTEP);
+    assert_eq!(sim.enemies[0].ai, AiState::Retreat);
+    // Still shooting while backing off
+    assert_eq!(sim.enemy_bullets.len(), 1);
+
+    // Up at 100 less its own 50 down, until a quarter past the range
+    let y = sim.enemies[0].y;
+    run(&mut sim, 1.0);
+    assert!((sim.enemies[0].y - (y - 50.0)).abs() < 0.1);
+    assert_eq!(sim.enemies[0].ai, AiState::Retreat);
+    run(&mut sim, 1.0);
+    assert_eq!(sim.enemies[0].ai, AiState::Attack);
+}
+
+#[test]
+fn badly_hurt_enemies_flee_for_good() {
+    let mut sim = with_ai(AiConfig {
+        flee: Some(FleeConfig {
+            health: 0.3,
+            speed: 150.0,
+        }),
+        ..ai()
+    });
+    sim.spawn_enemy_at(EnemyType::Basic, 400.0, 200.0, Movement::Sine {
+        amplitude: 30.0,
+        frequency: 1.0,
+    });
+    sim.enemies[0].health = 5.0;
+
+    run(&mut sim, FIXED_TIMESTEP);
+    let enemy = &sim.enemies[0];
+    assert_eq!(enemy.ai, AiState::Flee);
+    assert!(enemy.vy < 0.0);
+    assert!((enemy.vx.hypot(enemy.vy) - 150.0).abs() < 1e-3);
+
+    sim.enemies[0].health = 20.0;
+    run(&mut sim, 3.0);
+    assert_eq!(sim.enemies[0].ai, AiState::Flee);
+    assert!(sim.enemy_bullets.is_empty());
+
+    // Gone once off the top
+    sim.cleanup();
+    assert!(sim.enemies.is_empty());
+}
+
+#[test]
+fn states_are_exported_for_debug_rendering() {
+    let mut sim = with_ai(ai());
+    sim.spawn_enemy_at(EnemyType::Basic, 100.0, 0.0, Movement::Straight);
+    sim.spawn_enemy_at(EnemyType::Tank, 300.0, 0.0, Movement::Straight);
+
+    let field = CHANNELS[CHANNEL_ENEMIES].fields.iter().position(|f| *f == "ai_state").unwrap();
+    let data = sim.render_data();
+    let enemies = data.channel(CHANNEL_ENEMIES);
+    let stride = CHANNELS[CHANNEL_ENEMIES].stride();
+    // Tanks have no AI and are always attacking
+    assert_eq!(enemies[field], AiState::Enter.id() as f32);
+    assert_eq!(enemies[stride + field], AiState::Attack.id() as f32);
+}
+
+#[test]
+fn ai_is_configured_per_type() {
+    let config = GameConfig::from_json(
+        r#"{"enemies": {"fast": {"ai": {"enter_y": 80, "evade": {"range": 100, "speed": 150}}}}}"#,
+    )
+    .unwrap();
+    assert_eq!(
+        config.enemies.fast.ai,
+        Some(AiConfig {
+            enter_y: 80.0,
+            evade: Some(Reaction {
+                range: 100.0,
+                speed: 150.0
+            }),
+            retreat: None,
+            flee: None,
+        })
+    );
+    assert_eq!(GameConfig::default().enemies.basic.ai, None);
+
+    let err = GameConfig::from_json(r#"{"enemies": {"tank": {"ai": {"enter_y": 80, "flee": {"health": 2, "speed": 10}}}}}"#)
+        .unwrap_err();
+    assert_eq!(err.to_string(), "invalid game config: enemies.tank.ai.flee.health must be between 0 and 1");
+
+    let err = GameConfig::from_json(r#"{"enemies": {"basic": {"ai": {"enter_y": 80, "retreat": {"range": 0, "speed": 10}}}}}"#)
+        .unwrap_err();
+    assert_eq!(err.to_string(), "invalid game config: enemies.basic.ai.retreat.range must be a positive number");
+}
+
+#[test]
+fn saves_keep_ai_states() {
+    let mut sim = with_ai(AiConfig {
+        evade: Some(Reaction {
+            range: 150.0,
+            speed: 120.0,
+        }),
+        flee: Some(FleeConfig {
+            health: 0.5,
+            speed: 100.0,
+        }),
+        ..ai()
+    });
+    sim.config.enemy_fire.cooldown = 1.0;
+    sim.spawn_enemy_at(EnemyType::Basic, 200.0, -40.0, Movement::Straight);
+    sim.spawn_enemy_at(EnemyType::Basic, 500.0, 150.0, Movement::Straight);
+    sim.enemies[1].health = 5.0;
+    for _ in 0..30 {
+        sim.update(FIXED_TIMESTEP);
+    }
+
+    let mut loaded = Simulation::from_state(&sim.save_state()).unwrap();
+    assert_eq!(loaded.enemies[1].ai, AiState::Flee);
+    for _ in 0..300 {
+        sim.update(FIXED_TIMESTEP);
+        loaded.update(FIXED_TIMESTEP);
+    }
+    assert_eq!(loaded.save_state(), sim.save_state());
+}
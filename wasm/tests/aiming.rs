@@ -0,0 +1,102 @@
+mod common;
+
+use common::*;
+use particle_system::entities::*;
+use particle_system::pattern::{intercept_time, Aim};
+use particle_system::sim::FIXED_TIMESTEP;
+use particle_system::{GameConfig, Simulation};
+
+// One shot from an enemy of the type at (x, y)
+fn shoot(sim: &mut Simulation, enemy_type: EnemyType, x: f32, y: f32) -> Bullet {
+    sim.config.enemy_fire.chance = 1.0;
+    sim.enemies.clear();
+    sim.enemy_bullets.clear();
+    sim.add_enemy(enemy(x, y, enemy_type));
+    sim.update_enemies(FIXED_TIMESTEP);
+    assert_eq!(sim.enemy_bullets.len(), 1);
+    sim.enemy_bullets.get(0).clone()
+}
+
+// Degrees between a bullet's heading and the player
+fn miss(sim: &Simulation, bullet: &Bullet) -> f32 {
+    let aim = (sim.player.x - bullet.x).atan2(sim.player.y - bullet.y);
+    (bullet.vx.atan2(bullet.vy) - aim).to_degrees().abs()
+}
+
+#[test]
+fn each_type_has_its_own_aim() {
+    let config = GameConfig::default();
+    assert_eq!(config.enemies.basic.aim, Aim::Down);
+    assert_eq!(config.enemies.fast.aim, Aim::Player);
+    assert_eq!(config.enemies.tank.aim, Aim::Lead);
+
+    let mut sim = sim();
+    sim.config.enemy_fire.spread = 0.0;
+    let shot = shoot(&mut sim, EnemyType::Basic, 100.0, 100.0);
+    assert_eq!((shot.vx, shot.vy), (0.0, 150.0));
+
+    let shot = shoot(&mut sim, EnemyType::Fast, 100.0, 100.0);
+    assert!(miss(&sim, &shot) < 1e-3);
+    assert!((shot.vx.hypot(shot.vy) - 150.0).abs() < 1e-3);
+}
+
+#[test]
+fn lead_shots_meet_a_moving_player() {
+    let mut sim = sim();
+    sim.config.enemy_fire.spread = 0.0;
+    // Running left at half speed
+    sim.player.vx = -0.5;
+    let bullet = shoot(&mut sim, EnemyType::Tank, 100.0, 100.0);
+    assert!(miss(&sim, &bullet) > 1.0);
+
+    let speed = -0.5 * sim.config.player.speed;
+    let time = intercept_time(sim.player.x - bullet.x, sim.player.y - bullet.y, speed, 0.0, 150.0);
+    assert!(time > 0.0);
+    let (bullet_x, bullet_y) = (bullet.x + bullet.vx * time, bullet.y + bullet.vy * time);
+    let (player_x, player_y) = (sim.player.x + speed * time, sim.player.y);
+    assert!((bullet_x - player_x).hypot(bullet_y - player_y) < 0.5);
+
+    // Standing still, leading is the same as aiming
+    sim.player.vx = 0.0;
+    let shot = shoot(&mut sim, EnemyType::Tank, 100.0, 100.0);
+    assert!(miss(&sim, &shot) < 1e-3);
+}
+
+#[test]
+fn intercepts_that_cannot_happen_aim_at_the_target() {
+    // Target running straight away faster than the bullet
+    assert_eq!(intercept_time(0.0, 100.0, 0.0, 200.0, 150.0), 0.0);
+    // Target coming straight at the shooter
+    assert!((intercept_time(0.0, 300.0, 0.0, -150.0, 150.0) - 1.0).abs() < 1e-5);
+    // Same speed as the bullet, running across
+    assert!(intercept_time(100.0, 0.0, 0.0, 150.0, 150.0) == 0.0);
+}
+
+#[test]
+fn aim_tightens_as_levels_go_up() {
+    let worst_miss = |level: u32| {
+        let mut sim = sim();
+        sim.level = level;
+        (0..200)
+            .map(|_| {
+                let shot = shoot(&mut sim, EnemyType::Fast, 200.0, 100.0);
+                miss(&sim, &shot)
+            })
+            .fold(0.0, f32::max)
+    };
+
+    let spread = GameConfig::default().enemy_fire.spread;
+    let early = worst_miss(0);
+    assert!(early > spread * 0.8 && early <= spread);
+    let late = worst_miss(40);
+    assert!(late <= spread / 11.0 + 1e-3);
+}
+
+#[test]
+fn spreads_are_validated() {
+    let err = GameConfig::from_json(r#"{"enemy_fire": {"spread": -5}}"#).unwrap_err();
+    assert_eq!(err.to_string(), "invalid game config: enemy_fire.spread must be zero or more");
+
+    let err = GameConfig::from_json(r#"{"enemies": {"fast": {"aim": "sideways"}}}"#).unwrap_err();
+    assert!(err.to_string().starts_with("could not parse game config"));
+}
@@ -0,0 +1,70 @@
+mod common;
+
+use common::*;
+use particle_system::entities::*;
+
+#[test]
+fn black_hole_opens_in_front_of_player() {
+    let mut sim = sim();
+
+    sim.activate_black_hole();
+
+    assert_eq!(sim.black_holes.len(), 1);
+    assert_eq!(sim.black_holes[0].x, sim.player.x);
+    assert_eq!(sim.black_holes[0].y, sim.player.y - 200.0);
+    assert_eq!(sim.player.black_hole_cooldown, 10.0);
+}
+
+#[test]
+fn black_hole_respects_cooldown() {
+    let mut sim = sim();
+
+    sim.activate_black_hole();
+    sim.activate_black_hole();
+
+    assert_eq!(sim.black_holes.len(), 1);
+}
+
+#[test]
+fn black_hole_pulls_enemies_in_range() {
+    let mut sim = sim();
+    sim.activate_black_hole();
+    let (hx, hy) = (sim.black_holes[0].x, sim.black_holes[0].y);
+    sim.add_enemy(enemy(hx + 100.0, hy, EnemyType::Basic));
+    sim.add_enemy(enemy(hx + 300.0, hy, EnemyType::Basic));
+
+    sim.update_black_holes(0.1);
+
+    assert!(sim.enemies[0].x < hx + 100.0);
+    assert_eq!(sim.enemies[1].x, hx + 300.0);
+}
+
+#[test]
+fn black_hole_consumes_close_enemies() {
+    let mut sim = sim();
+    sim.activate_black_hole();
+    let (hx, hy) = (sim.black_holes[0].x, sim.black_holes[0].y);
+    sim.add_enemy(enemy(hx + 10.0, hy, EnemyType::Tank));
+
+    sim.update_black_holes(0.016);
+
+    assert!(sim.enemies.is_empty());
+    assert_eq!(sim.black_holes[0].consumed_enemies.len(), 1);
+}
+
+#[test]
+fn black_hole_collapse_scores_consumed_enemies() {
+    let mut sim = sim();
+    sim.activate_black_hole();
+    let (hx, hy) = (sim.black_holes[0].x, sim.black_holes[0].y);
+    sim.add_enemy(enemy(hx + 5.0, hy, EnemyType::Basic));
+    sim.add_enemy(enemy(hx - 5.0, hy, EnemyType::Basic));
+
+    sim.update_black_holes(3.0);
+    sim.cleanup();
+
+    assert!(sim.black_holes.is_empty());
+    assert_eq!(sim.score, 400);
+    assert_eq!(sim.explosions.len(), 1);
+    assert_eq!(sim.explosions[0].size, 30.0 * 3.0 + 20.0);
+}
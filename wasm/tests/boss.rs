@@ -0,0 +1,270 @@
+mod common;
+
+use common::*;
+use particle_system::boss::BossMovement;
+use particle_system::pattern::Shape;
+use particle_system::events::GameEvent;
+use particle_system::render::{CHANNELS, CHANNEL_BOSS, CHANNEL_BOSS_PARTS};
+use particle_system::sim::FIXED_TIMESTEP;
+use particle_system::{GameConfig, Simulation};
+
+// A boss that has finished flying in
+fn boss_fight() -> Simulation {
+    let mut sim = sim();
+    sim.spawn_boss().unwrap();
+    while sim.boss.as_ref().unwrap().entering {
+        sim.update_boss(FIXED_TIMESTEP);
+    }
+    sim
+}
+
+// A player bullet at the core, or at a part
+fn hit_core(sim: &mut Simulation, damage: f32) {
+    let boss = sim.boss.as_ref().unwrap();
+    let (x, y) = (boss.x, boss.y);
+    sim.add_bullet(player_bullet(x, y, damage));
+    sim.check_boss_collisions();
+}
+
+fn hit_part(sim: &mut Simulation, part: usize, damage: f32) {
+    let boss = sim.boss.as_ref().unwrap();
+    let (x, y) = boss.part_position(&boss.parts[part]);
+    sim.add_bullet(player_bullet(x, y, damage));
+    sim.check_boss_collisions();
+}
+
+fn field(channel: usize, name: &str) -> usize {
+    CHANNELS[channel].fields.iter().position(|f| *f == name).unwrap()
+}
+
+#[test]
+fn bosses_arrive_every_few_levels() {
+    let mut sim = sim();
+    sim.level = 4;
+    sim.score = 4 * sim.config.points_per_level;
+    sim.update(FIXED_TIMESTEP);
+
+    assert_eq!(sim.level, 5);
+    let boss = sim.boss.as_ref().unwrap();
+    assert!(sim.events().contains(&GameEvent::BossSpawned {
+        id: boss.id,
+        x: WIDTH / 2.0,
+        y: -120.0,
+    }));
+
+    // Random enemies hold off during the fight
+    sim.enemies.clear();
+    for _ in 0..300 {
+        sim.update(FIXED_TIMESTEP);
+    }
+    assert!(sim.enemies.is_empty());
+    assert!(sim.boss.is_some());
+}
+
+#[test]
+fn bosses_can_be_turned_off() {
+    let config = GameConfig::from_json(r#"{"boss": {"every_levels": 0}}"#).unwrap();
+    let mut sim = Simulation::with_config(WIDTH, HEIGHT, 42, config);
+    sim.level = 4;
+    sim.score = 4 * sim.config.points_per_level;
+    sim.update(FIXED_TIMESTEP);
+    assert_eq!(sim.level, 5);
+    assert!(sim.boss.is_none());
+}
+
+#[test]
+fn bosses_fly_in_before_attacking() {
+    let mut sim = boss_fight();
+    let boss = sim.boss.as_ref().unwrap();
+    assert_eq!(boss.y, sim.config.boss.entry_y);
+    assert!(sim.enemy_bullets.is_empty());
+
+    // First phase: a five bullet fan from the core, then the turrets
+    // start their bursts
+    let run = |sim: &mut Simulation, seconds: f32| {
+        for _ in 0..(seconds / FIXED_TIMESTEP).ceil() as u32 + 1 {
+            sim.update_boss(FIXED_TIMESTEP);
+        }
+    };
+    let (core, turret) = (sim.config.boss.phases[0].pattern, sim.config.boss.parts[0].pattern);
+    run(&mut sim, core.cooldown);
+    assert_eq!(sim.enemy_bullets.len(), 5);
+    let fan: Vec<f32> = sim.enemy_bullets.iter().map(|b| b.vx).collect();
+    assert!(fan[0] < 0.0 && fan[2].abs() < 1e-3 && fan[4] > 0.0);
+
+    run(&mut sim, turret.cooldown - core.cooldown);
+    assert_eq!(sim.enemy_bullets.len(), 5 + 2);
+}
+
+#[test]
+fn parts_shield_the_core_until_destroyed() {
+    let mut sim = boss_fight();
+    let part_health = sim.boss.as_ref().unwrap().parts[1].health;
+
+    hit_part(&mut sim, 1, part_health / 2.0);
+    let boss = sim.boss.as_ref().unwrap();
+    assert_eq!(boss.parts[1].health, part_health / 2.0);
+    assert_eq!(boss.health, boss.max_health);
+    assert!(sim.bullets.is_empty());
+
+    hit_part(&mut sim, 1, part_health / 2.0);
+    let boss = sim.boss.as_ref().unwrap();
+    let (x, y) = boss.part_position(&boss.parts[1]);
+    assert!(boss.parts[1].is_destroyed());
+    assert_eq!(sim.score, sim.config.boss.parts[1].score);
+    assert!(sim.events().contains(&GameEvent::BossPartDestroyed {
+        id: boss.id,
+        part: 1,
+        x,
+        y,
+        score: sim.config.boss.parts[1].score,
+    }));
+
+    // Bullets now pass through where it was
+    hit_part(&mut sim, 1, 10.0);
+    assert_eq!(sim.bullets.len(), 1);
+}
+
+#[test]
+fn phases_follow_health() {
+    let mut sim = boss_fight();
+    let max_health = sim.boss.as_ref().unwrap().max_health;
+
+    hit_core(&mut sim, max_health * 0.3);
+    assert_eq!(sim.boss.as_ref().unwrap().phase, 0);
+
+    hit_core(&mut sim, max_health * 0.15);
+    let boss = sim.boss.as_ref().unwrap();
+    assert_eq!(boss.phase, 1);
+    assert!(sim.events().contains(&GameEvent::BossPhaseChanged {
+        id: boss.id,
+        phase: 1,
+        x: boss.x,
+        y: boss.y,
+    }));
+
+    // Big enough hits skip phases
+    hit_core(&mut sim, max_health * 0.4);
+    assert_eq!(sim.boss.as_ref().unwrap().phase, 2);
+}
+
+#[test]
+fn phases_change_movement_and_attacks() {
+    let mut config = GameConfig::default();
+    config.boss.parts.clear();
+    config.boss.phases[1].movement = BossMovement::Hover;
+    config.boss.phases[1].pattern.shape = Shape::Ring { count: 8 };
+    let mut sim = Simulation::with_config(WIDTH, HEIGHT, 42, config);
+    sim.spawn_boss();
+    while sim.boss.as_ref().unwrap().entering {
+        sim.update_boss(FIXED_TIMESTEP);
+    }
+
+    let max_health = sim.boss.as_ref().unwrap().max_health;
+    hit_core(&mut sim, max_health * 0.5);
+    sim.boss.as_mut().unwrap().emitter.timer = 0.0;
+    let x = sim.boss.as_ref().unwrap().x;
+    sim.update_boss(FIXED_TIMESTEP);
+
+    assert_eq!(sim.boss.as_ref().unwrap().x, x);
+    assert_eq!(sim.enemy_bullets.len(), 8);
+    assert!(sim.enemy_bullets.iter().any(|b| b.vy < 0.0));
+}
+
+#[test]
+fn oversized_bosses_stay_on_screen() {
+    let mut config = GameConfig::default();
+    config.boss.size = HEIGHT;
+    let mut sim = Simulation::with_config(WIDTH, HEIGHT, 42, config);
+    sim.spawn_boss();
+    while sim.boss.as_ref().unwrap().entering {
+        sim.update_boss(FIXED_TIMESTEP);
+    }
+    sim.update_boss(FIXED_TIMESTEP);
+    assert_eq!(sim.boss.as_ref().unwrap().y, HEIGHT / 2.0);
+}
+
+#[test]
+fn beating_a_boss_scores_and_the_next_is_tougher() {
+    let mut sim = boss_fight();
+    let boss = sim.boss.clone().unwrap();
+    hit_core(&mut sim, boss.max_health);
+
+    assert!(sim.boss.is_none());
+    assert!(!sim.ids.is_alive(boss.id));
+    assert_eq!(sim.bosses_defeated, 1);
+    assert_eq!(sim.score, sim.config.boss.score);
+    assert!(sim.events().contains(&GameEvent::BossDefeated {
+        id: boss.id,
+        x: boss.x,
+        y: boss.y,
+        score: sim.config.boss.score,
+    }));
+
+    sim.spawn_boss();
+    assert_eq!(sim.boss.as_ref().unwrap().max_health, boss.max_health * 1.5);
+}
+
+#[test]
+fn bosses_have_their_own_render_channels() {
+    let mut sim = boss_fight();
+    hit_part(&mut sim, 0, 1000.0);
+    let data = sim.render_data();
+
+    assert_eq!(data.count(CHANNEL_BOSS), 1);
+    let boss = data.channel(CHANNEL_BOSS);
+    let max_health = sim.boss.as_ref().unwrap().max_health;
+    assert_eq!(boss[field(CHANNEL_BOSS, "health")], max_health);
+    assert_eq!(boss[field(CHANNEL_BOSS, "phase_count")], 3.0);
+    assert_eq!(boss[field(CHANNEL_BOSS, "next_phase_health")], max_health * 0.6);
+
+    // Only the part still standing
+    assert_eq!(data.count(CHANNEL_BOSS_PARTS), 1);
+    assert_eq!(data.channel(CHANNEL_BOSS_PARTS)[field(CHANNEL_BOSS_PARTS, "part")], 1.0);
+
+    sim.boss = None;
+    assert_eq!(sim.render_data().count(CHANNEL_BOSS), 0);
+}
+
+#[test]
+fn saves_keep_the_boss_fight() {
+    let mut sim = boss_fight();
+    hit_part(&mut sim, 0, 1000.0);
+    hit_core(&mut sim, 700.0);
+
+    let mut loaded = Simulation::from_state(&sim.save_state()).unwrap();
+    assert_eq!(loaded.boss, sim.boss);
+    for _ in 0..300 {
+        sim.update(FIXED_TIMESTEP);
+        loaded.update(FIXED_TIMESTEP);
+    }
+    assert_eq!(loaded.save_state(), sim.save_state());
+}
+
+#[test]
+fn boss_phases_are_validated() {
+    let err = GameConfig::from_json(r#"{"boss": {"phases": []}}"#).unwrap_err();
+    assert_eq!(err.to_string(), "invalid game config: boss.phases must have at least one phase");
+
+    let phase = |health: f32| {
+        format!(
+            r#"{{"health": {}, "movement": "hover", "pattern": {{"shape": {{"ring": {{"count": 6}}}}, "cooldown": 1, "bullet_speed": 100}}}}"#,
+            health
+        )
+    };
+    let json = format!(r#"{{"boss": {{"phases": [{}, {}, {}]}}}}"#, phase(1.0), phase(0.5), phase(0.7));
+    let err = GameConfig::from_json(&json).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid game config: boss.phases[2].health must be above 0 and below the phase before"
+    );
+
+    let json = format!(r#"{{"boss": {{"phases": [{}, {}]}}}}"#, phase(1.0), phase(0.5));
+    assert_eq!(GameConfig::from_json(&json).unwrap().boss.phases.len(), 2);
+
+    let mut config = GameConfig::default();
+    let part = config.boss.parts[0].clone();
+    config.boss.parts = vec![part; 33];
+    let err = GameConfig::from_json(&config.to_json()).unwrap_err();
+    assert_eq!(err.to_string(), "invalid game config: boss.parts must have at most 32 parts");
+}
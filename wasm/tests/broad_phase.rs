@@ -0,0 +1,108 @@
+mod common;
+
+use std::time::Instant;
+
+use common::*;
+use particle_system::entities::*;
+use particle_system::rng::Rng;
+use particle_system::sim::FIXED_TIMESTEP;
+use particle_system::spatial::SpatialGrid;
+
+#[test]
+fn query_finds_every_overlapping_circle() {
+    let mut rng = Rng::new(7);
+    // Includes circles well outside the screen, which land in edge cells
+    let circles: Vec<(f32, f32, f32)> = (0..500)
+        .map(|_| {
+            (
+                rng.next_f32() * 1400.0 - 300.0,
+                rng.next_f32() * 1200.0 - 300.0,
+                rng.next_f32() * 40.0 + 2.0,
+            )
+        })
+        .collect();
+
+    let mut grid = SpatialGrid::new(64.0);
+    grid.rebuild(WIDTH, HEIGHT, circles.iter().copied());
+
+    let mut candidates = Vec::new();
+    for _ in 0..200 {
+        let (x, y) = (rng.next_f32() * 1400.0 - 300.0, rng.next_f32() * 1200.0 - 300.0);
+        let radius = rng.next_f32() * 150.0;
+        grid.query(x, y, radius, &mut candidates);
+
+        assert!(candidates.windows(2).all(|w| w[0] < w[1]));
+        for (i, &(cx, cy, cr)) in circles.iter().enumerate() {
+            let (dx, dy) = (cx - x, cy - y);
+            if dx * dx + dy * dy < (cr + radius) * (cr + radius) {
+                assert!(candidates.contains(&(i as u32)), "missed circle {}", i);
+            }
+        }
+    }
+}
+
+#[test]
+fn bullet_hits_the_first_overlapping_enemy_only() {
+    let mut sim = sim();
+    sim.add_enemy(enemy(400.0, 300.0, EnemyType::Tank));
+    sim.add_enemy(enemy(410.0, 300.0, EnemyType::Tank));
+    sim.add_bullet(player_bullet(405.0, 300.0, 25.0));
+
+    sim.check_collisions();
+
+    assert_eq!(sim.enemies[0].health, 25.0);
+    assert_eq!(sim.enemies[1].health, 50.0);
+    assert!(sim.bullets.is_empty());
+}
+
+#[test]
+fn stress_mode_keeps_the_screen_full() {
+    let mut sim = sim();
+    sim.set_stress_mode(2000);
+
+    for _ in 0..120 {
+        sim.update(FIXED_TIMESTEP);
+    }
+
+    assert!(!sim.game_over);
+    // Topped up every tick, less whatever hit this tick
+    assert!(sim.bullets.len() > 1900);
+    assert!(sim.enemies.len() >= 90);
+
+    // Without the health top-ups the leftover tanks and their aimed shots
+    // would bring the player down
+    sim.set_stress_mode(0);
+    sim.enemies.clear();
+    sim.enemy_bullets.clear();
+    for _ in 0..240 {
+        sim.update(FIXED_TIMESTEP);
+    }
+    assert!(sim.bullets.len() < 100);
+}
+
+// Frame budget check, run with
+// cargo test --release --test broad_phase -- --ignored
+#[test]
+#[ignore]
+fn stress_mode_holds_60_fps() {
+    let mut sim = sim();
+    sim.set_stress_mode(5000);
+    for _ in 0..60 {
+        sim.update(FIXED_TIMESTEP);
+    }
+
+    let ticks = 600;
+    let start = Instant::now();
+    for _ in 0..ticks {
+        sim.update(FIXED_TIMESTEP);
+    }
+    let per_tick = start.elapsed().as_secs_f64() * 1000.0 / ticks as f64;
+
+    assert!(
+        per_tick < 1000.0 / 60.0,
+        "{} bullets, {} enemies: {:.3} ms per tick",
+        sim.bullets.len(),
+        sim.enemies.len(),
+        per_tick
+    );
+}
@@ -0,0 +1,142 @@
+use particle_system::bullets::BulletPool;
+use particle_system::entities::Bullet;
+use particle_system::rng::Rng;
+use particle_system::EntityId;
+
+fn bullet(index: u32, x: f32, y: f32, vx: f32, vy: f32, size: f32) -> Bullet {
+    Bullet {
+        id: EntityId { index, generation: 0 },
+        x,
+        y,
+        prev_x: x,
+        prev_y: y,
+        vx,
+        vy,
+        size,
+        damage: 10.0,
+    }
+}
+
+// Odd count so both the four-wide lanes and the tail get exercised
+fn random_pool(rng: &mut Rng, count: u32) -> (BulletPool, Vec<Bullet>) {
+    let mut pool = BulletPool::new(1024);
+    let mut reference = Vec::new();
+    for i in 0..count {
+        let b = bullet(
+            i,
+            rng.next_f32() * 800.0,
+            rng.next_f32() * 600.0,
+            (rng.next_f32() - 0.5) * 300.0,
+            (rng.next_f32() - 0.5) * 300.0,
+            rng.next_f32() * 10.0 + 2.0,
+        );
+        assert!(pool.push(b.clone()));
+        reference.push(b);
+    }
+    (pool, reference)
+}
+
+#[test]
+fn integrate_matches_per_bullet_update() {
+    let mut rng = Rng::new(3);
+    let (mut pool, mut reference) = random_pool(&mut rng, 203);
+
+    for _ in 0..60 {
+        pool.store_previous_positions();
+        pool.integrate(1.0 / 60.0);
+        for b in &mut reference {
+            b.prev_x = b.x;
+            b.prev_y = b.y;
+            b.x += b.vx * (1.0 / 60.0);
+            b.y += b.vy * (1.0 / 60.0);
+        }
+    }
+
+    for (i, b) in reference.iter().enumerate() {
+        let got = pool.get(i);
+        assert_eq!((got.x, got.y), (b.x, b.y));
+        assert_eq!((got.prev_x, got.prev_y), (b.prev_x, b.prev_y));
+    }
+}
+
+#[test]
+fn overlapping_matches_brute_force() {
+    let mut rng = Rng::new(11);
+    let (pool, reference) = random_pool(&mut rng, 519);
+    let mut hits = Vec::new();
+
+    for _ in 0..100 {
+        let (x, y, radius) = (rng.next_f32() * 800.0, rng.next_f32() * 600.0, 20.0);
+        pool.overlapping(x, y, radius, &mut hits);
+
+        let expected: Vec<usize> = reference
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| {
+                let (dx, dy) = (b.x - x, b.y - y);
+                dx * dx + dy * dy < (b.size + radius) * (b.size + radius)
+            })
+            .map(|(i, _)| i)
+            .collect();
+        assert_eq!(hits, expected);
+    }
+}
+
+#[test]
+fn removal_keeps_columns_together() {
+    let mut pool = BulletPool::new(8);
+    for i in 0..4 {
+        pool.push(bullet(i, i as f32, i as f32 * 100.0, 0.0, 0.0, 5.0));
+    }
+
+    let removed = pool.swap_remove(0);
+    assert_eq!(removed.id.index, 0);
+    let moved = pool.get(0);
+    assert_eq!((moved.id.index, moved.x, moved.y), (3, 3.0, 300.0));
+
+    let mut culled = Vec::new();
+    pool.retain_between(50.0, 250.0, |b| culled.push(b.id.index));
+    assert_eq!(culled, vec![3]);
+    let left: Vec<u32> = pool.iter().map(|b| b.id.index).collect();
+    assert_eq!(left, vec![2, 1]);
+}
+
+#[test]
+fn full_pool_rejects_bullets() {
+    let mut pool = BulletPool::new(1);
+    assert!(pool.push(bullet(0, 0.0, 0.0, 0.0, 0.0, 5.0)));
+    assert!(!pool.push(bullet(1, 0.0, 0.0, 0.0, 0.0, 5.0)));
+    assert_eq!(pool.len(), 1);
+}
+
+// The SIMD kernels against the scalar ones, bit for bit, over lengths
+// leaving every size of tail. Only built for wasm32 with the simd
+// feature, run with a WASI runtime such as wasmtime:
+// RUSTFLAGS="-C target-feature=+simd128" cargo test --target wasm32-wasip1 --features simd --test bullets
+#[cfg(all(feature = "simd", target_arch = "wasm32", target_feature = "simd128"))]
+#[test]
+fn simd_kernels_match_scalar() {
+    use particle_system::bullets::{scalar, simd};
+
+    let mut rng = Rng::new(17);
+    for len in (0..12).chain([255, 1021]) {
+        let mut column = || (0..len).map(|_| (rng.next_f32() - 0.5) * 1000.0).collect::<Vec<f32>>();
+        let (xs, ys, velocities) = (column(), column(), column());
+        let sizes: Vec<f32> = xs.iter().map(|x| x.abs() % 10.0 + 2.0).collect();
+
+        let (mut simd_xs, mut scalar_xs) = (xs.clone(), xs.clone());
+        simd::integrate(&mut simd_xs, &velocities, 1.0 / 60.0);
+        scalar::integrate(&mut scalar_xs, &velocities, 1.0 / 60.0);
+        assert_eq!(simd_xs, scalar_xs);
+
+        let (mut simd_hits, mut scalar_hits) = (Vec::new(), Vec::new());
+        for _ in 0..20 {
+            let (x, y) = ((rng.next_f32() - 0.5) * 1000.0, (rng.next_f32() - 0.5) * 1000.0);
+            simd_hits.clear();
+            scalar_hits.clear();
+            simd::overlapping(&xs, &ys, &sizes, x, y, 150.0, &mut simd_hits);
+            scalar::overlapping(&xs, &ys, &sizes, x, y, 150.0, &mut scalar_hits);
+            assert_eq!(simd_hits, scalar_hits);
+        }
+    }
+}
@@ -0,0 +1,135 @@
+mod common;
+
+use common::*;
+use particle_system::entities::*;
+
+#[test]
+fn player_bullet_kills_basic_enemy_and_scores() {
+    let mut sim = sim();
+    sim.add_enemy(enemy(100.0, 100.0, EnemyType::Basic));
+    sim.add_bullet(player_bullet(100.0, 105.0, 25.0));
+
+    sim.check_collisions();
+
+    assert!(sim.enemies.is_empty());
+    assert!(sim.bullets.is_empty());
+    assert_eq!(sim.score, 100);
+    assert_eq!(sim.player.enemies_killed, 1);
+}
+
+#[test]
+fn score_depends_on_enemy_type() {
+    for (enemy_type, score) in [(EnemyType::Fast, 150), (EnemyType::Tank, 300)] {
+        let mut sim = sim();
+        sim.add_enemy(enemy(100.0, 100.0, enemy_type));
+        sim.add_bullet(player_bullet(100.0, 100.0, 100.0));
+
+        sim.check_collisions();
+
+        assert_eq!(sim.score, score);
+    }
+}
+
+#[test]
+fn tank_survives_single_hit_and_explodes_on_kill() {
+    let mut sim = sim();
+    sim.add_enemy(enemy(100.0, 100.0, EnemyType::Tank));
+    sim.add_bullet(player_bullet(100.0, 100.0, 25.0));
+
+    sim.check_collisions();
+
+    assert_eq!(sim.enemies.len(), 1);
+    assert_eq!(sim.enemies[0].health, 25.0);
+    assert!(sim.explosions.is_empty());
+
+    sim.add_bullet(player_bullet(100.0, 100.0, 25.0));
+    sim.check_collisions();
+
+    assert!(sim.enemies.is_empty());
+    assert_eq!(sim.explosions.len(), 1);
+    assert_eq!(sim.explosions[0].size, 50.0);
+}
+
+#[test]
+fn bullet_only_hits_one_enemy() {
+    let mut sim = sim();
+    sim.add_enemy(enemy(100.0, 100.0, EnemyType::Basic));
+    sim.add_enemy(enemy(105.0, 100.0, EnemyType::Basic));
+    sim.add_bullet(player_bullet(102.0, 100.0, 25.0));
+
+    sim.check_collisions();
+
+    assert_eq!(sim.enemies.len(), 1);
+    assert_eq!(sim.score, 100);
+}
+
+#[test]
+fn bullet_misses_distant_enemy() {
+    let mut sim = sim();
+    sim.add_enemy(enemy(100.0, 100.0, EnemyType::Basic));
+    sim.add_bullet(player_bullet(200.0, 100.0, 25.0));
+
+    sim.check_collisions();
+
+    assert_eq!(sim.enemies.len(), 1);
+    assert_eq!(sim.bullets.len(), 1);
+    assert_eq!(sim.score, 0);
+}
+
+#[test]
+fn enemy_bullet_damages_player_and_drops_growth() {
+    let mut sim = sim();
+    sim.player.growth_level = 2;
+    let (px, py) = (sim.player.x, sim.player.y);
+    sim.add_enemy_bullet(enemy_bullet(px, py));
+
+    sim.check_collisions();
+
+    assert!(sim.enemy_bullets.is_empty());
+    assert_eq!(sim.player.health, 90.0);
+    assert_eq!(sim.player.growth_level, 1);
+}
+
+#[test]
+fn enemy_contact_damages_player() {
+    let mut sim = sim();
+    let (px, py) = (sim.player.x, sim.player.y);
+    sim.add_enemy(enemy(px, py, EnemyType::Basic));
+
+    sim.check_collisions();
+
+    assert_eq!(sim.player.health, 80.0);
+    assert!(!sim.game_over);
+}
+
+#[test]
+fn lethal_hit_ends_game() {
+    let mut sim = sim();
+    sim.player.health = 10.0;
+    let (px, py) = (sim.player.x, sim.player.y);
+    sim.add_enemy_bullet(enemy_bullet(px, py));
+
+    sim.check_collisions();
+
+    assert!(sim.game_over);
+}
+
+#[test]
+fn power_ups_apply_their_effect() {
+    let mut sim = sim();
+    sim.player.health = 50.0;
+    let (px, py) = (sim.player.x, sim.player.y);
+    sim.add_power_up(power_up(px, py, PowerUpType::Health));
+    sim.add_power_up(power_up(px, py, PowerUpType::Weapon));
+    sim.add_power_up(power_up(px, py, PowerUpType::Shield));
+
+    sim.check_collisions();
+
+    assert!(sim.power_ups.is_empty());
+    assert_eq!(sim.player.max_health, 120.0);
+    assert_eq!(sim.player.health, 80.0);
+    assert_eq!(sim.player.power_level, 2);
+    assert_eq!(sim.shield_level, 1);
+    assert!(sim.shield_active);
+    assert_eq!(sim.shield_timer, 10.0);
+}
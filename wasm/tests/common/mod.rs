@@ -0,0 +1,87 @@
+#![allow(dead_code)]
+
+use particle_system::ai::AiState;
+use particle_system::entities::*;
+use particle_system::movement::Movement;
+use particle_system::pattern::Emitter;
+use particle_system::{EntityId, GameConfig};
+use particle_system::Simulation;
+
+pub const WIDTH: f32 = 800.0;
+pub const HEIGHT: f32 = 600.0;
+
+pub fn sim() -> Simulation {
+    Simulation::new(WIDTH, HEIGHT, 42)
+}
+
+// Entities for Simulation::add_*, which assigns the real id.
+// Same stats spawn_enemy uses for each type
+pub fn enemy(x: f32, y: f32, enemy_type: EnemyType) -> Enemy {
+    let config = GameConfig::default();
+    let stats = config.enemy(enemy_type);
+    let (size, health) = (stats.size, stats.health);
+    Enemy {
+        id: EntityId::default(),
+        x,
+        y,
+        prev_x: x,
+        prev_y: y,
+        vx: 0.0,
+        vy: 0.0,
+        health,
+        max_health: health,
+        size,
+        enemy_type,
+        shoot_cooldown: 0.0,
+        emitter: Emitter::default(),
+        movement: Movement::Straight,
+        movement_time: 0.0,
+        held: 0.0,
+        ai: AiState::Attack,
+        ai_time: 0.0,
+        squad: None,
+        ability_timer: 0.0,
+        laser: None,
+    }
+}
+
+pub fn player_bullet(x: f32, y: f32, damage: f32) -> Bullet {
+    Bullet {
+        id: EntityId::default(),
+        x,
+        y,
+        prev_x: x,
+        prev_y: y,
+        vx: 0.0,
+        vy: -300.0,
+        size: 8.0,
+        damage,
+    }
+}
+
+pub fn enemy_bullet(x: f32, y: f32) -> Bullet {
+    Bullet {
+        id: EntityId::default(),
+        x,
+        y,
+        prev_x: x,
+        prev_y: y,
+        vx: 0.0,
+        vy: 150.0,
+        size: 5.0,
+        damage: 10.0,
+    }
+}
+
+pub fn power_up(x: f32, y: f32, power_type: PowerUpType) -> PowerUp {
+    PowerUp {
+        id: EntityId::default(),
+        x,
+        y,
+        prev_x: x,
+        prev_y: y,
+        vy: 80.0,
+        size: 15.0,
+        power_type,
+    }
+}
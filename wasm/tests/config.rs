@@ -0,0 +1,142 @@
+mod common;
+
+use common::*;
+use particle_system::ability::Ability;
+use particle_system::config::ConfigError;
+use particle_system::movement::Movement;
+use particle_system::sim::FIXED_TIMESTEP;
+use particle_system::state::StateError;
+use particle_system::{GameConfig, InputFrame, Replay, Simulation};
+
+#[test]
+fn default_config_round_trips_through_json() {
+    let config = GameConfig::default();
+    assert_eq!(GameConfig::from_json(&config.to_json()).unwrap(), config);
+    assert_eq!(GameConfig::from_json("{}").unwrap(), config);
+}
+
+#[test]
+fn partial_entries_keep_their_own_defaults() {
+    let config = GameConfig::from_json(r#"{ "enemies": { "tank": { "health": 80 } } }"#).unwrap();
+
+    let defaults = GameConfig::default();
+    assert_eq!(config.enemies.tank.health, 80.0);
+    assert_eq!(config.enemies.tank.size, defaults.enemies.tank.size);
+    assert_eq!(config.enemies.tank.score, defaults.enemies.tank.score);
+    assert_eq!(config.enemies.basic, defaults.enemies.basic);
+}
+
+#[test]
+fn overrides_can_switch_enum_variants() {
+    let config = GameConfig::from_json(
+        r#"{ "enemies": {
+            "carrier": { "movement": { "sine": { "amplitude": 40, "frequency": 0.5 } } },
+            "splitter": { "ability": { "heal": { "radius": 100, "rate": 5 } } },
+            "mine_layer": { "ability": { "lay_mines": { "interval": 3 } } }
+        } }"#,
+    )
+    .unwrap();
+    assert_eq!(config.enemies.carrier.movement, Some(Movement::Sine { amplitude: 40.0, frequency: 0.5 }));
+    assert_eq!(config.enemies.splitter.ability, Some(Ability::Heal { radius: 100.0, rate: 5.0 }));
+
+    // The same variant still merges field by field
+    assert_eq!(
+        config.enemies.mine_layer.ability,
+        Some(Ability::LayMines {
+            interval: 3.0,
+            size: 10.0,
+            damage: 15.0,
+            life: 12.0
+        })
+    );
+}
+
+#[test]
+fn ron_configs_load_natively() {
+    let config = GameConfig::from_ron("(shield: (duration: 4.0), points_per_level: 500)").unwrap();
+    assert_eq!(config.shield.duration, 4.0);
+    assert_eq!(config.points_per_level, 500);
+    assert_eq!(config.black_hole, GameConfig::default().black_hole);
+}
+
+#[test]
+fn bad_configs_say_what_is_wrong() {
+    assert!(matches!(GameConfig::from_json("{ not json"), Err(ConfigError::Parse(_))));
+    // Typos are caught instead of silently ignored
+    assert!(matches!(
+        GameConfig::from_json(r#"{ "black_hole": { "raduis": 200 } }"#),
+        Err(ConfigError::Parse(_))
+    ));
+
+    let err = GameConfig::from_json(r#"{ "enemies": { "fast": { "health": 0 } } }"#).unwrap_err();
+    assert_eq!(err, ConfigError::Invalid("enemies.fast.health".to_string(), "must be a positive number"));
+    assert_eq!(err.to_string(), "invalid game config: enemies.fast.health must be a positive number");
+
+    let err = GameConfig::from_json(r#"{ "spawn": { "tank_chance": 1.5 } }"#).unwrap_err();
+    assert_eq!(err, ConfigError::Invalid("spawn.tank_chance".to_string(), "must be between 0 and 1"));
+}
+
+#[test]
+fn config_drives_gameplay() {
+    let mut config = GameConfig::default();
+    config.black_hole.cooldown = 2.5;
+    config.black_hole.pull_radius = 300.0;
+    config.player.max_health = 250.0;
+    let mut sim = Simulation::with_config(WIDTH, HEIGHT, 1, config);
+
+    assert_eq!(sim.player.health, 250.0);
+
+    sim.queue_input(0, InputFrame { black_hole: true, ..InputFrame::default() });
+    sim.update(FIXED_TIMESTEP);
+    assert!((sim.player.black_hole_cooldown - 2.5).abs() < FIXED_TIMESTEP * 2.0);
+    assert_eq!(sim.black_holes[0].pull_radius, 300.0);
+
+    // A new run keeps the config
+    sim.reset();
+    assert_eq!(sim.player.max_health, 250.0);
+}
+
+#[test]
+fn oversized_player_stays_centred() {
+    let mut config = GameConfig::default();
+    config.player.size = HEIGHT;
+    let mut sim = Simulation::with_config(WIDTH, HEIGHT, 1, config);
+    sim.update_player(FIXED_TIMESTEP);
+    assert_eq!(sim.player.y, HEIGHT / 2.0);
+}
+
+#[test]
+fn replays_and_saves_carry_the_config() {
+    let mut config = GameConfig::default();
+    config.spawn.enemy_interval = 0.25;
+    config.enemies.basic.score = 1;
+    let mut sim = Simulation::with_config(WIDTH, HEIGHT, 7, config.clone());
+    for _ in 0..600 {
+        sim.queue_input(sim.tick, InputFrame { fire: true, ..InputFrame::default() });
+        sim.update(FIXED_TIMESTEP);
+    }
+
+    let replay = Replay::from_bytes(&sim.replay().to_bytes()).unwrap();
+    assert_eq!(replay.config, config);
+    let mut playback = Simulation::from_replay(&replay).unwrap();
+    while !playback.playback_finished() {
+        playback.advance(FIXED_TIMESTEP);
+    }
+    assert_eq!(playback.score, sim.score);
+    assert_eq!(playback.render_data(), sim.render_data());
+
+    let mut loaded = Simulation::new(WIDTH, HEIGHT, 0);
+    loaded.load_state(&sim.save_state()).unwrap();
+    assert_eq!(loaded.config, config);
+}
+
+#[test]
+fn saves_with_a_broken_config_are_rejected() {
+    let sim = sim();
+    let mut bytes = sim.save_state();
+    // Config JSON starts after magic, version, width, height and its length
+    let start = 4 + 2 + 4 + 4 + 4;
+    bytes[start] = b'[';
+
+    assert!(matches!(Simulation::from_state(&bytes), Err(StateError::InvalidConfig(_))));
+}
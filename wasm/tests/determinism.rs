@@ -0,0 +1,47 @@
+use particle_system::{InputFrame, Simulation};
+
+fn run(seed: u64) -> Simulation {
+    let mut sim = Simulation::new(800.0, 600.0, seed);
+    for frame in 0..1200 {
+        let input = InputFrame {
+            move_x: if frame % 120 < 60 { 1.0 } else { -1.0 },
+            fire: true,
+            ..InputFrame::default()
+        };
+        sim.queue_input(sim.tick, input);
+        sim.update(1.0 / 60.0);
+    }
+    sim
+}
+
+#[test]
+fn same_seed_same_game() {
+    let a = run(7);
+    let b = run(7);
+
+    assert_eq!(a.rng_state(), b.rng_state());
+    assert_eq!(a.score, b.score);
+    assert_eq!(a.render_data(), b.render_data());
+}
+
+#[test]
+fn different_seeds_diverge() {
+    let a = run(7);
+    let b = run(8);
+
+    assert_ne!(a.render_data(), b.render_data());
+}
+
+#[test]
+fn reset_seed_reproduces_the_run() {
+    let mut sim = run(7);
+    sim.reset();
+    let mut replay = Simulation::new(800.0, 600.0, sim.seed());
+
+    for _ in 0..600 {
+        sim.update(1.0 / 60.0);
+        replay.update(1.0 / 60.0);
+    }
+
+    assert_eq!(sim.render_data(), replay.render_data());
+}
@@ -0,0 +1,138 @@
+mod common;
+
+use common::*;
+use particle_system::config::DynamicDifficultyConfig;
+use particle_system::difficulty::{Difficulty, DynamicDifficulty};
+use particle_system::entities::*;
+use particle_system::sim::FIXED_TIMESTEP;
+use particle_system::{GameConfig, InputFrame, Simulation};
+
+fn dynamic_config() -> DynamicDifficultyConfig {
+    DynamicDifficultyConfig {
+        enabled: true,
+        ..DynamicDifficultyConfig::default()
+    }
+}
+
+fn play(sim: &mut Simulation, ticks: u32) {
+    for tick in 0..ticks {
+        let input = InputFrame {
+            move_x: if tick % 120 < 60 { 1.0 } else { -1.0 },
+            fire: true,
+            ..InputFrame::default()
+        };
+        sim.queue_input(sim.tick, input);
+        sim.update(FIXED_TIMESTEP);
+    }
+}
+
+#[test]
+fn presets_scale_enemies() {
+    let normal = GameConfig::default();
+    let hard = GameConfig::default().with_difficulty(Difficulty::Hard);
+    let mut sim = Simulation::with_config(WIDTH, HEIGHT, 3, hard);
+
+    sim.spawn_enemy();
+    let enemy = &sim.enemies[0];
+    assert_eq!(enemy.health, normal.enemy(enemy.enemy_type).health * 1.5);
+
+    // Enemies always fire once the chance roll allows
+    sim.config.enemy_fire.chance = 1.0;
+    sim.update_enemies(FIXED_TIMESTEP);
+    let bullet = sim.enemy_bullets.get(0);
+    assert_eq!(bullet.vy, normal.enemy_fire.bullet_speed * 1.25);
+    assert_eq!(bullet.damage, normal.enemy_fire.bullet_damage * 1.5);
+    assert_eq!(sim.enemies[0].shoot_cooldown, normal.enemy_fire.cooldown / 1.5);
+}
+
+#[test]
+fn presets_are_picked_by_name() {
+    assert_eq!(Difficulty::from_name("Nightmare"), Ok(Difficulty::Nightmare));
+    assert_eq!(Difficulty::from_name("easy"), Ok(Difficulty::Easy));
+    let err = Difficulty::from_name("brutal").unwrap_err();
+    assert_eq!(err.to_string(), "unknown difficulty \"brutal\", expected easy, normal, hard or nightmare");
+}
+
+#[test]
+fn easy_enemies_ram_softer() {
+    let easy = GameConfig::default().with_difficulty(Difficulty::Easy);
+    let mut sim = Simulation::with_config(WIDTH, HEIGHT, 3, easy);
+    sim.add_enemy(enemy(sim.player.x, sim.player.y, EnemyType::Basic));
+
+    sim.check_collisions();
+
+    assert_eq!(sim.player.health, 90.0);
+}
+
+#[test]
+fn struggling_player_gets_fewer_enemies() {
+    let config = dynamic_config();
+    let mut difficulty = DynamicDifficulty::default();
+
+    for _ in 0..60 {
+        difficulty.update(&config, 1.0, 0.2, 0, 1.0);
+    }
+
+    assert_eq!(difficulty.factor, config.min_factor);
+}
+
+#[test]
+fn dominant_player_gets_more_enemies() {
+    let config = dynamic_config();
+    let mut difficulty = DynamicDifficulty::default();
+
+    // Full health and killing everything that spawns
+    let mut kills = 0;
+    for _ in 0..60 {
+        kills += 2;
+        difficulty.update(&config, 1.0, 1.0, kills, 2.0);
+    }
+
+    assert_eq!(difficulty.factor, config.max_factor);
+}
+
+#[test]
+fn window_only_keeps_recent_samples() {
+    let config = dynamic_config();
+    let mut difficulty = DynamicDifficulty::default();
+
+    // Ten samples with kills, then a quiet window's worth without
+    let mut kills = 0;
+    for _ in 0..10 {
+        kills += 5;
+        difficulty.update(&config, 1.0, 0.6, kills, 1.0);
+    }
+    for _ in 0..10 {
+        difficulty.update(&config, 1.0, 0.6, kills, 1.0);
+    }
+
+    assert_eq!(difficulty.samples().count(), 10);
+    assert!(difficulty.samples().all(|sample| sample.kills == 0));
+}
+
+#[test]
+fn factor_stays_put_when_disabled() {
+    let mut sim = sim();
+    play(&mut sim, 1200);
+    assert_eq!(sim.difficulty.factor, 1.0);
+    assert_eq!(sim.difficulty.samples().count(), 0);
+}
+
+#[test]
+fn dynamic_difficulty_survives_save_and_load() {
+    let config = GameConfig {
+        dynamic_difficulty: dynamic_config(),
+        ..GameConfig::default()
+    };
+    let mut sim = Simulation::with_config(WIDTH, HEIGHT, 8, config);
+    play(&mut sim, 900);
+    assert_ne!(sim.difficulty.factor, 1.0);
+
+    let mut loaded = Simulation::from_state(&sim.save_state()).unwrap();
+    assert_eq!(loaded.difficulty, sim.difficulty);
+
+    play(&mut sim, 600);
+    play(&mut loaded, 600);
+    assert_eq!(loaded.difficulty, sim.difficulty);
+    assert_eq!(loaded.render_data(), sim.render_data());
+}
@@ -0,0 +1,130 @@
+mod common;
+
+use common::*;
+use particle_system::entities::*;
+use particle_system::events::{DamageSource, EVENT_STRIDE};
+use particle_system::{EntityId, GameEvent};
+
+#[test]
+fn kill_emits_enemy_killed() {
+    let mut sim = sim();
+    let id = sim.add_enemy(enemy(100.0, 120.0, EnemyType::Fast)).unwrap();
+    sim.add_bullet(player_bullet(100.0, 120.0, 25.0));
+
+    sim.check_collisions();
+
+    assert_eq!(
+        sim.drain_events(),
+        vec![GameEvent::EnemyKilled {
+            id,
+            enemy_type: EnemyType::Fast,
+            x: 100.0,
+            y: 120.0,
+            score: 150,
+        }]
+    );
+    assert!(sim.drain_events().is_empty());
+}
+
+#[test]
+fn hits_report_damage_source() {
+    let mut sim = sim();
+    let (px, py) = (sim.player.x, sim.player.y);
+    sim.add_enemy_bullet(enemy_bullet(px, py));
+    sim.add_enemy(enemy(px, py, EnemyType::Basic));
+
+    sim.check_collisions();
+
+    let sources: Vec<_> = sim
+        .drain_events()
+        .into_iter()
+        .filter_map(|e| match e {
+            GameEvent::PlayerHit { source, .. } => Some(source),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(sources, vec![DamageSource::EnemyBullet, DamageSource::EnemyCollision]);
+}
+
+#[test]
+fn shield_emits_absorbed_instead_of_hit() {
+    let mut sim = sim();
+    sim.shield_level = 2;
+    sim.shield_active = true;
+    let (px, py) = (sim.player.x, sim.player.y);
+    sim.add_enemy_bullet(enemy_bullet(px, py));
+
+    sim.check_collisions();
+
+    assert!(matches!(
+        sim.drain_events()[..],
+        [GameEvent::ShieldAbsorbed {
+            source: DamageSource::EnemyBullet,
+            shield_level: 1,
+            ..
+        }]
+    ));
+}
+
+#[test]
+fn power_up_and_black_hole_lifecycle() {
+    let mut sim = sim();
+    let (px, py) = (sim.player.x, sim.player.y);
+    sim.add_power_up(power_up(px, py, PowerUpType::Weapon));
+    sim.check_collisions();
+    sim.activate_black_hole();
+    sim.update_black_holes(3.0);
+    sim.cleanup();
+
+    let events = sim.drain_events();
+    assert!(matches!(
+        events[0],
+        GameEvent::PowerUpCollected {
+            power_type: PowerUpType::Weapon,
+            ..
+        }
+    ));
+    assert!(matches!(events[1], GameEvent::BlackHoleOpened { .. }));
+    assert!(matches!(
+        events[2],
+        GameEvent::BlackHoleCollapsed { consumed: 0, score: 0, .. }
+    ));
+}
+
+#[test]
+fn level_up_and_game_over_are_reported_once() {
+    let mut sim = sim();
+    sim.score = 1000;
+    sim.player.health = 5.0;
+    let (px, py) = (sim.player.x, sim.player.y);
+    sim.add_enemy_bullet(enemy_bullet(px, py));
+    sim.add_enemy_bullet(enemy_bullet(px, py));
+
+    sim.update(0.0);
+    sim.update(0.0);
+
+    let events = sim.drain_events();
+    assert!(events.contains(&GameEvent::LevelUp { level: 2 }));
+    let game_overs = events
+        .iter()
+        .filter(|e| matches!(e, GameEvent::GameOver { .. }))
+        .count();
+    assert_eq!(game_overs, 1);
+}
+
+#[test]
+fn events_encode_with_fixed_stride() {
+    let mut data = Vec::new();
+    GameEvent::EnemyKilled {
+        id: EntityId { index: 4, generation: 1 },
+        enemy_type: EnemyType::Tank,
+        x: 1.0,
+        y: 2.0,
+        score: 300,
+    }
+    .encode(&mut data);
+    GameEvent::LevelUp { level: 3 }.encode(&mut data);
+
+    assert_eq!(data.len(), 2 * EVENT_STRIDE);
+    assert_eq!(data, vec![0.0, 1.0, 2.0, 2.0, 300.0, 4.0, 1.0, 6.0, 0.0, 0.0, 3.0, 0.0, -1.0, 0.0]);
+}
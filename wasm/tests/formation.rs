@@ -0,0 +1,279 @@
+mod common;
+
+use common::*;
+use particle_system::entities::*;
+use particle_system::formation::Squadron;
+use particle_system::movement::Movement;
+use particle_system::sim::FIXED_TIMESTEP;
+use particle_system::stage::{Stage, Step};
+use particle_system::state::StateError;
+use particle_system::{GameConfig, Simulation};
+
+// A row of three straight into the middle, not diving for a long while
+fn squadron() -> Squadron {
+    Squadron {
+        enemy: EnemyType::Basic,
+        rows: 1,
+        columns: 3,
+        spacing: 50.0,
+        x: 0.5,
+        y: 100.0,
+        entry_x: 0.5,
+        path: None,
+        interval: 0.2,
+        join_speed: 200.0,
+        dive_interval: 100.0,
+        dive_speed: 250.0,
+        sway: 0.0,
+    }
+}
+
+// A sim where enemies never fire
+fn quiet() -> Simulation {
+    let mut sim = sim();
+    sim.config.enemy_fire.chance = 0.0;
+    sim
+}
+
+fn run(sim: &mut Simulation, seconds: f32) {
+    for _ in 0..(seconds / FIXED_TIMESTEP).round() as u32 {
+        sim.update_formations(FIXED_TIMESTEP);
+        sim.update_enemies(FIXED_TIMESTEP);
+        sim.cleanup();
+    }
+}
+
+// Rounded, top row first, left to right
+fn positions(sim: &Simulation) -> Vec<(f32, f32)> {
+    let mut positions: Vec<(f32, f32)> = sim.enemies.iter().map(|enemy| (enemy.x.round(), enemy.y.round())).collect();
+    positions.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.total_cmp(&b.0)));
+    positions
+}
+
+fn kill(sim: &mut Simulation, slot: u32) {
+    for enemy in &mut sim.enemies {
+        if enemy.squad.is_some_and(|squad| squad.slot == slot) {
+            enemy.health = 0.0;
+        }
+    }
+}
+
+#[test]
+fn members_fly_in_and_lock_into_the_grid() {
+    let mut sim = quiet();
+    sim.spawn_squadron(squadron());
+
+    // One at a time
+    run(&mut sim, 0.1);
+    assert_eq!(sim.enemies.len(), 1);
+    run(&mut sim, 0.4);
+    assert_eq!(sim.enemies.len(), 3);
+
+    run(&mut sim, 2.0);
+    assert!(sim.enemies.iter().all(|enemy| enemy.squad.is_some_and(|squad| squad.holding)));
+    assert_eq!(positions(&sim), vec![(350.0, 100.0), (400.0, 100.0), (450.0, 100.0)]);
+
+    // Holding still, unlike loners
+    run(&mut sim, 1.0);
+    assert_eq!(positions(&sim), vec![(350.0, 100.0), (400.0, 100.0), (450.0, 100.0)]);
+}
+
+#[test]
+fn members_hold_still_on_ticks_with_no_time() {
+    let mut sim = quiet();
+    sim.spawn_squadron(squadron());
+    run(&mut sim, 2.5);
+    let before = positions(&sim);
+
+    sim.update_formations(0.0);
+    sim.update_enemies(0.0);
+    sim.cleanup();
+    assert_eq!(positions(&sim), before);
+    assert!(sim.enemies.iter().all(|enemy| enemy.vx.is_finite() && enemy.vy.is_finite()));
+}
+
+#[test]
+fn members_follow_the_entry_path_first() {
+    let mut sim = quiet();
+    sim.spawn_squadron(Squadron {
+        path: Some(0),
+        entry_x: 0.1,
+        ..squadron()
+    });
+    run(&mut sim, 1.0);
+    let enemy = &sim.enemies[0];
+    assert_eq!(enemy.movement, Movement::Path(0));
+    assert!(!enemy.squad.unwrap().holding);
+
+    run(&mut sim, 10.0);
+    assert_eq!(positions(&sim), vec![(350.0, 100.0), (400.0, 100.0), (450.0, 100.0)]);
+}
+
+#[test]
+fn the_formation_closes_up_as_members_die() {
+    let mut sim = quiet();
+    sim.spawn_squadron(Squadron {
+        rows: 2,
+        ..squadron()
+    });
+    run(&mut sim, 3.0);
+    assert_eq!(sim.enemies.len(), 6);
+
+    // Without the middle of the top row the other two close in
+    kill(&mut sim, 1);
+    run(&mut sim, 1.0);
+    assert_eq!(positions(&sim)[..2], [(375.0, 100.0), (425.0, 100.0)]);
+
+    // Without the top row the bottom one moves up
+    kill(&mut sim, 0);
+    kill(&mut sim, 2);
+    run(&mut sim, 1.0);
+    assert_eq!(positions(&sim), vec![(350.0, 100.0), (400.0, 100.0), (450.0, 100.0)]);
+}
+
+#[test]
+fn members_peel_off_to_dive_at_the_player() {
+    let mut sim = quiet();
+    sim.spawn_squadron(Squadron {
+        dive_interval: 2.0,
+        ..squadron()
+    });
+    run(&mut sim, 2.1);
+    let divers: Vec<&Enemy> = sim.enemies.iter().filter(|enemy| enemy.squad.is_none()).collect();
+    assert_eq!(divers.len(), 1);
+    assert_eq!(divers[0].movement, Movement::Dive { speed: 250.0, delay: 0.0 });
+    assert!(divers[0].vy > 0.0);
+
+    // The other two close up behind it
+    run(&mut sim, 0.5);
+    let members: Vec<(f32, f32)> = sim
+        .enemies
+        .iter()
+        .filter(|enemy| enemy.squad.is_some())
+        .map(|enemy| (enemy.x.round(), enemy.y.round()))
+        .collect();
+    assert_eq!(members, vec![(375.0, 100.0), (425.0, 100.0)]);
+}
+
+#[test]
+fn formations_are_gone_with_their_members() {
+    let mut sim = quiet();
+    let id = sim.spawn_squadron(squadron());
+    run(&mut sim, 1.0);
+    for enemy in &mut sim.enemies {
+        enemy.health = 0.0;
+    }
+    run(&mut sim, FIXED_TIMESTEP * 2.0);
+    assert!(sim.formations.is_empty());
+    assert!(!sim.ids.is_alive(id));
+}
+
+#[test]
+fn stages_send_in_squadrons() {
+    let stage = Stage {
+        steps: vec![Step::Squadron(squadron()), Step::WaitUntilCleared],
+        endless: false,
+    };
+    let mut sim = Simulation::with_config(WIDTH, HEIGHT, 42, GameConfig {
+        stage: Some(stage),
+        ..GameConfig::default()
+    });
+    sim.config.enemy_fire.chance = 0.0;
+    sim.update(FIXED_TIMESTEP);
+    assert_eq!(sim.formations.len(), 1);
+    assert_eq!(sim.enemies.len(), 1);
+
+    // Not cleared until the members still to come are gone too
+    for enemy in &mut sim.enemies {
+        enemy.health = 0.0;
+    }
+    sim.update(FIXED_TIMESTEP);
+    assert!(sim.enemies.is_empty());
+    assert!(!sim.stage.complete);
+    for _ in 0..300 {
+        for enemy in &mut sim.enemies {
+            enemy.health = 0.0;
+        }
+        sim.update(FIXED_TIMESTEP);
+    }
+    assert!(sim.stage.complete);
+
+    let stage = Stage::from_json(r#"{"steps": [{"squadron": {"enemy": "tank", "columns": 4, "path": 1}}]}"#).unwrap();
+    assert_eq!(
+        stage.steps[0],
+        Step::Squadron(Squadron {
+            enemy: EnemyType::Tank,
+            rows: 2,
+            columns: 4,
+            y: 80.0,
+            path: Some(1),
+            interval: 0.25,
+            dive_interval: 3.0,
+            ..squadron()
+        })
+    );
+}
+
+#[test]
+fn random_squadrons_come_one_at_a_time() {
+    let mut sim = sim();
+    sim.config.spawn.squadron_interval = 1.0;
+    sim.config.spawn.enemy_interval = 1000.0;
+    sim.player.health = 1e9;
+    for _ in 0..90 {
+        sim.update(FIXED_TIMESTEP);
+    }
+    assert_eq!(sim.formations.len(), 1);
+    for _ in 0..90 {
+        sim.update(FIXED_TIMESTEP);
+    }
+    assert_eq!(sim.formations.len(), 1);
+}
+
+#[test]
+fn squadrons_are_validated() {
+    let err = GameConfig::from_json(r#"{"squadron": {"enemy": "basic", "rows": 0}}"#).unwrap_err();
+    assert_eq!(err.to_string(), "invalid game config: squadron.rows must be at least 1");
+
+    let err = GameConfig::from_json(r#"{"spawn": {"squadron_interval": -1}}"#).unwrap_err();
+    assert_eq!(err.to_string(), "invalid game config: spawn.squadron_interval must be zero or more");
+
+    let err = GameConfig::from_json(r#"{"stage": {"steps": [{"squadron": {"enemy": "fast", "path": 7}}]}}"#)
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid game config: stage.steps[0].squadron.path must be the number of a path in paths"
+    );
+}
+
+#[test]
+fn saves_keep_formations_flying() {
+    let mut sim = sim();
+    sim.spawn_squadron(Squadron {
+        rows: 2,
+        path: Some(1),
+        entry_x: 0.9,
+        dive_interval: 1.5,
+        sway: 30.0,
+        ..squadron()
+    });
+    for _ in 0..200 {
+        sim.update(FIXED_TIMESTEP);
+    }
+
+    let mut loaded = Simulation::from_state(&sim.save_state()).unwrap();
+    assert_eq!(loaded.formations, sim.formations);
+    for _ in 0..400 {
+        sim.update(FIXED_TIMESTEP);
+        loaded.update(FIXED_TIMESTEP);
+    }
+    assert_eq!(loaded.save_state(), sim.save_state());
+
+    // The entry path has to be in the config
+    let mut sim = quiet();
+    sim.spawn_squadron(Squadron {
+        path: Some(99),
+        ..squadron()
+    });
+    assert!(matches!(Simulation::from_state(&sim.save_state()), Err(StateError::InvalidPath(99))));
+}
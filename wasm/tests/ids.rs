@@ -0,0 +1,105 @@
+mod common;
+
+use common::*;
+use particle_system::entities::*;
+use particle_system::ids::IdAllocator;
+use particle_system::render::{CHANNELS, CHANNEL_ENEMIES};
+use particle_system::sim::FIXED_TIMESTEP;
+use particle_system::{GameEvent, InputFrame, Simulation};
+
+#[test]
+fn reused_slot_gets_a_new_generation() {
+    let mut ids = IdAllocator::new();
+    let first = ids.alloc();
+    let second = ids.alloc();
+    assert_ne!(first, second);
+
+    ids.free(first);
+    ids.free(first);
+    assert!(!ids.is_alive(first));
+    assert_eq!(ids.len(), 1);
+
+    let reused = ids.alloc();
+    assert_eq!(reused.index, first.index);
+    assert_eq!(reused.generation, first.generation + 1);
+    assert!(ids.is_alive(reused));
+    assert_ne!(ids.alloc().index, first.index);
+}
+
+#[test]
+fn entity_keeps_its_id_in_render_data() {
+    let mut sim = sim();
+    sim.add_enemy(enemy(100.0, 100.0, EnemyType::Basic));
+    let id = sim.add_enemy(enemy(300.0, 100.0, EnemyType::Tank)).unwrap();
+
+    let schema = &CHANNELS[CHANNEL_ENEMIES];
+    let field = |name| schema.fields.iter().position(|&f| f == name).unwrap();
+    let id_of = |data: &[f32], i: usize| {
+        let entity = &data[i * schema.stride()..];
+        (entity[field("id")], entity[field("generation")])
+    };
+
+    let before = sim.render_data();
+    sim.enemies.swap_remove(0);
+    let after = sim.render_data();
+
+    let expected = (id.index as f32, id.generation as f32);
+    assert_eq!(id_of(before.channel(CHANNEL_ENEMIES), 1), expected);
+    assert_eq!(id_of(after.channel(CHANNEL_ENEMIES), 0), expected);
+}
+
+#[test]
+fn removed_entities_free_their_ids() {
+    let mut sim = sim();
+    let killed = sim.add_enemy(enemy(100.0, 120.0, EnemyType::Basic)).unwrap();
+    let bullet = sim.add_bullet(player_bullet(100.0, 120.0, 25.0)).unwrap();
+    let off_screen = sim.add_power_up(power_up(100.0, HEIGHT + 100.0, PowerUpType::Health)).unwrap();
+
+    sim.check_collisions();
+    sim.cleanup();
+
+    assert!(sim.ids.is_empty());
+    for id in [killed, bullet, off_screen] {
+        assert!(!sim.ids.is_alive(id));
+    }
+    let events = sim.drain_events();
+    assert!(matches!(events[..], [GameEvent::EnemyKilled { id, .. }] if id == killed));
+}
+
+#[test]
+fn live_entities_and_allocator_agree() {
+    let mut sim = sim();
+    for tick in 0..1200 {
+        let input = InputFrame {
+            move_x: if tick % 90 < 45 { 1.0 } else { -1.0 },
+            fire: true,
+            black_hole: tick % 400 == 20,
+            ..InputFrame::default()
+        };
+        sim.queue_input(sim.tick, input);
+        sim.update(FIXED_TIMESTEP);
+
+        let live = sim.enemies.len()
+            + sim.bullets.len()
+            + sim.enemy_bullets.len()
+            + sim.power_ups.len()
+            + sim.black_holes.len();
+        assert_eq!(sim.ids.len(), live);
+    }
+}
+
+#[test]
+fn ids_survive_save_and_load() {
+    let mut sim = sim();
+    let id = sim.add_enemy(enemy(100.0, 100.0, EnemyType::Fast)).unwrap();
+    sim.add_bullet(player_bullet(200.0, -100.0, 25.0));
+    sim.cleanup();
+
+    let mut loaded = Simulation::from_state(&sim.save_state()).unwrap();
+
+    assert_eq!(loaded.enemies[0].id, id);
+    assert_eq!(
+        loaded.add_bullet(player_bullet(0.0, 0.0, 25.0)),
+        sim.add_bullet(player_bullet(0.0, 0.0, 25.0))
+    );
+}
@@ -0,0 +1,118 @@
+mod common;
+
+use common::*;
+use particle_system::InputFrame;
+
+const DT: f32 = 1.0 / 60.0;
+
+fn fire() -> InputFrame {
+    InputFrame {
+        fire: true,
+        ..InputFrame::default()
+    }
+}
+
+#[test]
+fn input_is_applied_at_its_tick() {
+    let mut sim = sim();
+    sim.queue_input(2, fire());
+
+    sim.update(DT);
+    sim.update(DT);
+    assert!(sim.bullets.is_empty());
+
+    sim.update(DT);
+    assert_eq!(sim.bullets.len(), 1);
+    assert_eq!(sim.tick, 3);
+}
+
+#[test]
+fn held_input_persists_until_replaced() {
+    let mut sim = sim();
+    let start_x = sim.player.x;
+    sim.queue_input(
+        0,
+        InputFrame {
+            move_x: 1.0,
+            ..InputFrame::default()
+        },
+    );
+    sim.queue_input(10, InputFrame::default());
+
+    for _ in 0..20 {
+        sim.update(DT);
+    }
+
+    let moved = sim.player.x - start_x;
+    assert!((moved - 10.0 * DT * 200.0).abs() < 1e-3);
+}
+
+#[test]
+fn ability_press_only_lasts_one_tick() {
+    let mut sim = sim();
+    sim.queue_input(
+        0,
+        InputFrame {
+            black_hole: true,
+            ..InputFrame::default()
+        },
+    );
+
+    sim.update(DT);
+    assert_eq!(sim.black_holes.len(), 1);
+    assert!(!sim.input.black_hole);
+
+    // Cooldown is over, but the button is no longer pressed
+    sim.player.black_hole_cooldown = 0.0;
+    sim.update(DT);
+    assert_eq!(sim.black_holes.len(), 1);
+}
+
+#[test]
+fn frames_queued_out_of_order_apply_in_tick_order() {
+    let mut sim = sim();
+    sim.queue_input(1, InputFrame::default());
+    sim.queue_input(0, fire());
+
+    sim.update(DT);
+    assert!(sim.input.fire);
+
+    sim.update(DT);
+    assert!(!sim.input.fire);
+}
+
+#[test]
+fn late_input_applies_on_next_tick() {
+    let mut sim = sim();
+    sim.update(DT);
+    sim.update(DT);
+
+    sim.queue_input(0, fire());
+    sim.update(DT);
+
+    assert_eq!(sim.bullets.len(), 1);
+}
+
+#[test]
+fn frames_for_the_same_tick_merge() {
+    let mut sim = sim();
+    let start_x = sim.player.x;
+    // Many render frames before the tick runs, the press in the middle
+    for i in 0..100 {
+        sim.queue_input(0, InputFrame {
+            move_x: if i == 99 { 1.0 } else { -1.0 },
+            black_hole: i == 50,
+            ..InputFrame::default()
+        });
+    }
+
+    sim.update(DT);
+    assert!(sim.player.x > start_x);
+    assert_eq!(sim.black_holes.len(), 1);
+
+    // Nothing left over for later ticks
+    sim.player.black_hole_cooldown = 0.0;
+    sim.update(DT);
+    assert_eq!(sim.black_holes.len(), 1);
+    assert_eq!(sim.input.move_x, 1.0);
+}
@@ -0,0 +1,256 @@
+mod common;
+
+use common::*;
+use particle_system::entities::*;
+use particle_system::movement::{Movement, Path};
+use particle_system::sim::FIXED_TIMESTEP;
+use particle_system::stage::{SpawnGroup, Stage, Step};
+use particle_system::state::StateError;
+use particle_system::{GameConfig, Simulation};
+
+// A sim with one enemy of the type moving the given way
+fn mover(enemy_type: EnemyType, x: f32, y: f32, movement: Movement) -> Simulation {
+    let mut sim = sim();
+    sim.config.enemy_fire.chance = 0.0;
+    sim.spawn_enemy_at(enemy_type, x, y, movement);
+    sim
+}
+
+fn run(sim: &mut Simulation, seconds: f32) {
+    for _ in 0..(seconds / FIXED_TIMESTEP).round() as u32 {
+        sim.update_enemies(FIXED_TIMESTEP);
+    }
+}
+
+#[test]
+fn sine_weaves_around_the_spawn_point() {
+    let mut sim = mover(EnemyType::Basic, 300.0, 0.0, Movement::Sine {
+        amplitude: 40.0,
+        frequency: 0.5,
+    });
+
+    // A quarter of the way through a weave it is furthest out
+    run(&mut sim, 0.5);
+    assert!((sim.enemies[0].x - 340.0).abs() < 0.1);
+    run(&mut sim, 1.0);
+    assert!((sim.enemies[0].x - 260.0).abs() < 0.1);
+    run(&mut sim, 0.5);
+    let enemy = &sim.enemies[0];
+    assert!((enemy.x - 300.0).abs() < 0.1);
+    assert!((enemy.y - 2.0 * sim.config.enemies.basic.speed).abs() < 0.1);
+}
+
+#[test]
+fn divers_wait_then_go_for_the_player() {
+    let mut sim = mover(EnemyType::Fast, 100.0, 0.0, Movement::Dive {
+        speed: 300.0,
+        delay: 1.0,
+    });
+    run(&mut sim, 0.9);
+    assert_eq!(sim.enemies[0].vx, 0.0);
+
+    run(&mut sim, 0.2);
+    let enemy = &sim.enemies[0];
+    let (dx, dy) = (sim.player.x - enemy.x, sim.player.y - enemy.y);
+    assert!((enemy.vx * dy - enemy.vy * dx).abs() < 1.0 * dx.hypot(dy));
+    assert!((enemy.vx.hypot(enemy.vy) - 300.0).abs() < 1e-2);
+
+    // Committed once past the player
+    sim.enemies[0].y = sim.player.y + 10.0;
+    let velocity = (sim.enemies[0].vx, sim.enemies[0].vy);
+    sim.player.x = 0.0;
+    run(&mut sim, 0.1);
+    assert_eq!((sim.enemies[0].vx, sim.enemies[0].vy), velocity);
+}
+
+#[test]
+fn strafers_hold_a_line_then_leave() {
+    let mut sim = mover(EnemyType::Tank, 400.0, 0.0, Movement::Strafe {
+        y: 150.0,
+        speed: 200.0,
+        hold: 4.0,
+    });
+
+    // Thirty pixels a second takes five seconds to come down
+    run(&mut sim, 5.0);
+    assert!((sim.enemies[0].y - 150.0).abs() < 0.01);
+
+    // Along the line, turning round at the edge
+    run(&mut sim, 1.5);
+    let enemy = &sim.enemies[0];
+    assert!((enemy.y - 150.0).abs() < 0.01);
+    assert!(enemy.x > 600.0 && enemy.vx > 0.0);
+    run(&mut sim, 1.0);
+    assert!(sim.enemies[0].vx < 0.0);
+    assert!(sim.enemies[0].x <= WIDTH);
+
+    run(&mut sim, 2.0);
+    let enemy = &sim.enemies[0];
+    assert_eq!((enemy.vx, enemy.vy), (0.0, sim.config.enemies.tank.speed));
+    assert!(enemy.y > 150.0);
+}
+
+#[test]
+fn paths_are_followed_then_left_along() {
+    let mut sim = sim();
+    sim.config.paths = vec![Path {
+        points: vec![[0.0, 0.0], [0.0, 100.0], [100.0, 100.0], [100.0, 200.0]],
+        duration: 2.0,
+    }];
+    sim.config.enemy_fire.chance = 0.0;
+    sim.spawn_enemy_at(EnemyType::Basic, 200.0, 0.0, Movement::Path(0));
+
+    run(&mut sim, 1.0);
+    let (x, y) = sim.config.paths[0].point_at(0.5);
+    assert!((sim.enemies[0].x - 200.0 - x).abs() < 1e-2);
+    assert!((sim.enemies[0].y - y).abs() < 1e-2);
+
+    run(&mut sim, 1.0);
+    let enemy = &sim.enemies[0];
+    assert!((enemy.x - 300.0).abs() < 1e-2 && (enemy.y - 200.0).abs() < 1e-2);
+
+    // Out the end heading straight down at the speed it finished with
+    run(&mut sim, 1.0);
+    let enemy = &sim.enemies[0];
+    assert!(enemy.vx.abs() < enemy.vy * 0.01);
+    assert!((enemy.x - 300.0).abs() < 2.0 && enemy.y > 300.0);
+}
+
+#[test]
+fn types_and_waves_pick_their_movement() {
+    let config = GameConfig::from_json(r#"{"enemies": {"fast": {"movement": {"dive": {"speed": 250}}}}}"#).unwrap();
+    let mut sim = Simulation::with_config(WIDTH, HEIGHT, 42, config.clone());
+    sim.config.spawn.tank_chance = 0.0;
+    sim.config.spawn.fast_chance = 1.0;
+    sim.spawn_enemy();
+    assert_eq!(sim.enemies[0].movement, Movement::Dive { speed: 250.0, delay: 0.0 });
+
+    let group = |movement| {
+        Step::Spawn(SpawnGroup {
+            enemy: EnemyType::Fast,
+            count: 1,
+            x: 0.5,
+            y: -50.0,
+            spacing: 0.0,
+            interval: 0.0,
+            movement,
+        })
+    };
+    let stage = Stage {
+        steps: vec![group(None), group(Some(Movement::Path(1)))],
+        endless: false,
+    };
+    let mut sim = Simulation::with_config(WIDTH, HEIGHT, 42, GameConfig {
+        stage: Some(stage),
+        ..config
+    });
+    sim.update(FIXED_TIMESTEP);
+    let movements: Vec<Movement> = sim.enemies.iter().map(|e| e.movement).collect();
+    assert_eq!(movements, vec![Movement::Dive { speed: 250.0, delay: 0.0 }, Movement::Path(1)]);
+}
+
+#[test]
+fn enemies_leaving_the_sides_are_removed() {
+    let mut sim = sim();
+    let mut leaving = enemy(-120.0, 100.0, EnemyType::Basic);
+    leaving.vx = -10.0;
+    let mut entering = enemy(-120.0, 200.0, EnemyType::Basic);
+    entering.vx = 10.0;
+    sim.add_enemy(leaving);
+    sim.add_enemy(entering);
+
+    sim.cleanup();
+    assert_eq!(sim.enemies.len(), 1);
+    assert_eq!(sim.enemies[0].y, 200.0);
+}
+
+#[test]
+fn ticks_with_no_time_leave_enemies_where_they_are() {
+    let mut sim = sim();
+    sim.config.enemy_fire.chance = 0.0;
+    for (x, movement) in [
+        (200.0, Movement::Sine {
+            amplitude: 40.0,
+            frequency: 0.5,
+        }),
+        (400.0, Movement::Path(0)),
+        (600.0, Movement::Strafe {
+            y: 150.0,
+            speed: 100.0,
+            hold: 0.0,
+        }),
+    ] {
+        sim.spawn_enemy_at(EnemyType::Basic, x, 100.0, movement);
+    }
+    run(&mut sim, 0.5);
+    let before: Vec<(f32, f32)> = sim.enemies.iter().map(|enemy| (enemy.x, enemy.y)).collect();
+
+    sim.update_enemies(0.0);
+    sim.cleanup();
+    let after: Vec<(f32, f32)> = sim.enemies.iter().map(|enemy| (enemy.x, enemy.y)).collect();
+    assert_eq!(after, before);
+    assert!(sim.enemies.iter().all(|enemy| enemy.vx.is_finite() && enemy.vy.is_finite()));
+}
+
+#[test]
+fn movements_are_validated() {
+    let err = GameConfig::from_json(r#"{"enemies": {"tank": {"movement": {"path": 2}}}}"#).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid game config: enemies.tank.movement.path must be the number of a path in paths"
+    );
+
+    let err = GameConfig::from_json(r#"{"paths": [{"points": [[0, 0], [1, 1], [2, 2]], "duration": 1}]}"#)
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid game config: paths[0].points must be a start point and three more for each segment"
+    );
+
+    let err = Stage::from_json(
+        r#"{"steps": [{"spawn": {"enemy": "basic", "x": 0.5, "movement": {"sine": {"amplitude": 10, "frequency": 0}}}}]}"#,
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid game config: stage.steps[0].spawn.movement.sine.frequency must be a positive number"
+    );
+
+    let err = GameConfig::from_json(
+        r#"{"stage": {"steps": [{"spawn": {"enemy": "basic", "x": 0.5, "movement": {"path": 5}}}]}}"#,
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid game config: stage.steps[0].spawn.movement.path must be the number of a path in paths"
+    );
+}
+
+#[test]
+fn saves_keep_enemies_on_course() {
+    let mut sim = sim();
+    sim.spawn_enemy_at(EnemyType::Basic, 100.0, -50.0, Movement::Path(0));
+    sim.spawn_enemy_at(EnemyType::Tank, 400.0, -50.0, Movement::Strafe {
+        y: 100.0,
+        speed: 80.0,
+        hold: 3.0,
+    });
+    sim.spawn_enemy_at(EnemyType::Fast, 600.0, -50.0, Movement::Sine {
+        amplitude: 30.0,
+        frequency: 1.0,
+    });
+    for _ in 0..200 {
+        sim.update(FIXED_TIMESTEP);
+    }
+
+    let mut loaded = Simulation::from_state(&sim.save_state()).unwrap();
+    for _ in 0..300 {
+        sim.update(FIXED_TIMESTEP);
+        loaded.update(FIXED_TIMESTEP);
+    }
+    assert_eq!(loaded.save_state(), sim.save_state());
+
+    // Paths have to be in the config
+    let sim = mover(EnemyType::Basic, 100.0, -50.0, Movement::Path(99));
+    assert!(matches!(Simulation::from_state(&sim.save_state()), Err(StateError::InvalidPath(99))));
+}
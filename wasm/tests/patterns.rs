@@ -0,0 +1,164 @@
+mod common;
+
+use common::*;
+use particle_system::entities::*;
+use particle_system::movement::Movement;
+use particle_system::pattern::{Aim, Emitter, Pattern, Shape};
+use particle_system::sim::FIXED_TIMESTEP;
+use particle_system::{GameConfig, Simulation};
+
+fn pattern(shape: Shape) -> Pattern {
+    Pattern {
+        shape,
+        aim: Aim::Down,
+        spin: 0.0,
+        spread: 0.0,
+        burst: 1,
+        burst_interval: 0.0,
+        cooldown: 1.0,
+        bullet_speed: 100.0,
+    }
+}
+
+fn degrees(pattern: &Pattern, rotation: f32, aim: f32) -> Vec<f32> {
+    let mut out = Vec::new();
+    pattern.directions(rotation, aim.to_radians(), &mut out);
+    out.iter().map(|a| (a.to_degrees() * 1000.0).round() / 1000.0).collect()
+}
+
+#[test]
+fn shapes_spread_around_the_aim() {
+    assert_eq!(degrees(&pattern(Shape::Single), 0.0, 30.0), vec![0.0]);
+    assert_eq!(
+        degrees(&pattern(Shape::Fan { count: 3, angle: 90.0 }), 0.0, 0.0),
+        vec![-45.0, 0.0, 45.0]
+    );
+    assert_eq!(
+        degrees(&pattern(Shape::Ring { count: 4 }), 10.0, 0.0),
+        vec![10.0, 100.0, 190.0, 280.0]
+    );
+
+    let aimed = Pattern {
+        aim: Aim::Player,
+        ..pattern(Shape::Fan { count: 2, angle: 20.0 })
+    };
+    assert_eq!(degrees(&aimed, 0.0, 30.0), vec![20.0, 40.0]);
+}
+
+#[test]
+fn emitters_fire_bursts_then_cool_down() {
+    let burst = Pattern {
+        burst: 3,
+        burst_interval: 0.1,
+        ..pattern(Shape::Single)
+    };
+    let mut emitter = Emitter::default();
+    let volleys: Vec<u32> = (0..100).filter(|_| emitter.update(&burst, FIXED_TIMESTEP, 1.0)).collect();
+    // Three quick shots, a second's rest, three more
+    assert_eq!(volleys.len(), 6);
+
+    // A faster fire rate shortens the rest, not the burst
+    let mut emitter = Emitter::default();
+    let volleys = (0..100).filter(|_| emitter.update(&burst, FIXED_TIMESTEP, 2.0)).count();
+    assert_eq!(volleys, 9);
+}
+
+#[test]
+fn spinning_patterns_turn_between_volleys() {
+    let spiral = Pattern {
+        spin: 90.0,
+        cooldown: 0.25,
+        ..pattern(Shape::Single)
+    };
+    let mut emitter = Emitter::default();
+    let mut angles = Vec::new();
+    for _ in 0..120 {
+        if emitter.update(&spiral, FIXED_TIMESTEP, 1.0) {
+            angles.push(degrees(&spiral, emitter.rotation, 0.0)[0]);
+        }
+    }
+    assert_eq!(angles.len(), 8);
+    assert!(angles.windows(2).all(|w| w[1] > w[0]));
+    assert!((angles[1] - angles[0] - 22.5).abs() < 1.0);
+}
+
+#[test]
+fn enemy_types_fire_their_pattern() {
+    let config = GameConfig::from_json(
+        r#"{"enemies": {"basic": {"pattern": {
+            "shape": {"fan": {"count": 3, "angle": 40}}, "aim": "player", "cooldown": 1, "bullet_speed": 200
+        }}}}"#,
+    )
+    .unwrap();
+    let mut sim = Simulation::with_config(WIDTH, HEIGHT, 42, config);
+    sim.spawn_enemy_at(EnemyType::Basic, 100.0, 100.0, Movement::Straight);
+    sim.enemies[0].vy = 0.0;
+
+    // Nothing until a full cooldown has passed
+    for _ in 0..59 {
+        sim.update_enemies(FIXED_TIMESTEP);
+    }
+    assert!(sim.enemy_bullets.is_empty());
+    for _ in 0..2 {
+        sim.update_enemies(FIXED_TIMESTEP);
+    }
+    assert_eq!(sim.enemy_bullets.len(), 3);
+
+    // The middle bullet heads for the player
+    let middle = sim.enemy_bullets.get(1);
+    let (dx, dy) = (sim.player.x - middle.x, sim.player.y - middle.y);
+    let cross = middle.vx * dy - middle.vy * dx;
+    assert!(cross.abs() < 1e-2 * dx.hypot(dy) * 200.0);
+    assert!((middle.vx.hypot(middle.vy) - 200.0).abs() < 1e-3);
+}
+
+#[test]
+fn types_without_a_pattern_keep_the_plain_shot() {
+    let mut sim = sim();
+    sim.config.enemy_fire.chance = 1.0;
+    sim.add_enemy(enemy(100.0, 100.0, EnemyType::Basic));
+    sim.update_enemies(FIXED_TIMESTEP);
+
+    assert_eq!(sim.enemy_bullets.len(), 1);
+    let bullet = sim.enemy_bullets.get(0);
+    assert_eq!((bullet.vx, bullet.vy), (0.0, sim.config.enemy_fire.bullet_speed));
+}
+
+#[test]
+fn patterns_are_validated() {
+    let err = GameConfig::from_json(
+        r#"{"enemies": {"fast": {"pattern": {"shape": "single", "burst": 0, "cooldown": 1, "bullet_speed": 100}}}}"#,
+    )
+    .unwrap_err();
+    assert_eq!(err.to_string(), "invalid game config: enemies.fast.pattern.burst must be at least 1");
+
+    let err = GameConfig::from_json(
+        r#"{"enemies": {"tank": {"pattern": {"shape": {"ring": {"count": 0}}, "cooldown": 1, "bullet_speed": 100}}}}"#,
+    )
+    .unwrap_err();
+    assert_eq!(err.to_string(), "invalid game config: enemies.tank.pattern.shape.ring.count must be at least 1");
+}
+
+#[test]
+fn saves_keep_emitters_mid_burst() {
+    let mut config = GameConfig::default();
+    config.enemies.tank.pattern = Some(Pattern {
+        spin: 45.0,
+        burst: 4,
+        burst_interval: 0.2,
+        cooldown: 0.5,
+        ..pattern(Shape::Ring { count: 6 })
+    });
+    let mut sim = Simulation::with_config(WIDTH, HEIGHT, 42, config);
+    sim.spawn_enemy_at(EnemyType::Tank, 300.0, 50.0, Movement::Straight);
+    for _ in 0..45 {
+        sim.update(FIXED_TIMESTEP);
+    }
+
+    let mut loaded = Simulation::from_state(&sim.save_state()).unwrap();
+    for _ in 0..120 {
+        sim.update(FIXED_TIMESTEP);
+        loaded.update(FIXED_TIMESTEP);
+    }
+    assert_eq!(loaded.save_state(), sim.save_state());
+}
@@ -0,0 +1,72 @@
+mod common;
+
+use common::*;
+use particle_system::entities::*;
+use particle_system::pool::Pool;
+use particle_system::sim::MAX_PLAYER_BULLETS;
+
+#[test]
+fn removal_swaps_the_last_item_in() {
+    let mut pool = Pool::new(8);
+    for i in 0..5 {
+        assert!(pool.push(i));
+    }
+
+    assert_eq!(pool.swap_remove(1), 1);
+    assert_eq!(&pool[..], &[0, 4, 2, 3]);
+
+    let mut removed = Vec::new();
+    pool.remove_descending(&[3, 0], |item| removed.push(item));
+    assert_eq!(removed, vec![3, 0]);
+    assert_eq!(&pool[..], &[2, 4]);
+
+    pool.retain(|&item| item > 2, |item| removed.push(item));
+    assert_eq!(&pool[..], &[4]);
+    assert_eq!(removed, vec![3, 0, 2]);
+}
+
+#[test]
+fn full_pool_drops_new_items() {
+    let mut pool = Pool::new(2);
+    assert!(pool.push('a'));
+    assert!(pool.push('b'));
+    assert!(pool.is_full());
+    assert!(!pool.push('c'));
+    assert_eq!(&pool[..], &['a', 'b']);
+}
+
+#[test]
+fn spawns_past_the_limit_use_no_ids() {
+    let mut sim = sim();
+    for _ in 0..MAX_PLAYER_BULLETS {
+        assert!(sim.add_bullet(player_bullet(10.0, 10.0, 25.0)).is_some());
+    }
+    let live = sim.ids.len();
+
+    assert_eq!(sim.add_bullet(player_bullet(10.0, 10.0, 25.0)), None);
+    sim.player.shoot_cooldown = 0.0;
+    sim.shoot();
+
+    assert_eq!(sim.bullets.len(), MAX_PLAYER_BULLETS);
+    assert_eq!(sim.ids.len(), live);
+}
+
+#[test]
+fn kills_keep_the_other_enemies() {
+    let mut sim = sim();
+    let survivors = [
+        sim.add_enemy(enemy(100.0, 100.0, EnemyType::Basic)).unwrap(),
+        sim.add_enemy(enemy(300.0, 100.0, EnemyType::Tank)).unwrap(),
+    ];
+    sim.add_enemy(enemy(500.0, 100.0, EnemyType::Fast));
+    sim.add_enemy(enemy(700.0, 100.0, EnemyType::Fast));
+    sim.add_bullet(player_bullet(500.0, 100.0, 25.0));
+    sim.add_bullet(player_bullet(700.0, 100.0, 25.0));
+
+    sim.check_collisions();
+
+    let mut left: Vec<_> = sim.enemies.iter().map(|e| e.id).collect();
+    left.sort_by_key(|id| id.index);
+    assert_eq!(left, survivors);
+    assert!(sim.bullets.is_empty());
+}
@@ -0,0 +1,74 @@
+mod common;
+
+use common::*;
+use particle_system::entities::*;
+
+#[test]
+fn level_increases_with_score() {
+    let mut sim = sim();
+
+    sim.score = 999;
+    sim.update(0.0);
+    assert_eq!(sim.level, 1);
+
+    sim.score = 1000;
+    sim.update(0.0);
+    assert_eq!(sim.level, 2);
+
+    sim.update(0.0);
+    assert_eq!(sim.level, 2);
+
+    sim.score = 2000;
+    sim.update(0.0);
+    assert_eq!(sim.level, 3);
+}
+
+#[test]
+fn growth_level_follows_kills() {
+    let mut sim = sim();
+
+    for i in 0..60 {
+        sim.add_enemy(enemy(100.0, 100.0, EnemyType::Basic));
+        sim.add_bullet(player_bullet(100.0, 100.0, 25.0));
+        sim.check_collisions();
+        assert_eq!(sim.player.growth_level, ((i + 1) / 10).min(5));
+    }
+}
+
+#[test]
+fn game_over_freezes_simulation() {
+    let mut sim = sim();
+    sim.game_over = true;
+    sim.add_enemy(enemy(100.0, 100.0, EnemyType::Basic));
+    sim.enemies[0].vy = 50.0;
+
+    sim.update(1.0);
+
+    assert_eq!(sim.enemies[0].y, 100.0);
+    assert_eq!(sim.game_time, 0.0);
+}
+
+#[test]
+fn reset_restores_a_fresh_run() {
+    let mut sim = sim();
+    sim.score = 5000;
+    sim.level = 4;
+    sim.game_over = true;
+    sim.add_enemy(enemy(100.0, 100.0, EnemyType::Basic));
+    sim.explosions.push(Explosion {
+        x: 100.0,
+        y: 100.0,
+        size: 30.0,
+        life: 0.5,
+        max_life: 0.5,
+    });
+
+    sim.reset();
+
+    assert_eq!(sim.score, 0);
+    assert_eq!(sim.level, 1);
+    assert!(!sim.game_over);
+    assert!(sim.enemies.is_empty());
+    assert!(sim.explosions.is_empty());
+    assert_eq!(sim.player.health, 100.0);
+}
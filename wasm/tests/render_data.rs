@@ -0,0 +1,112 @@
+mod common;
+
+use common::*;
+use particle_system::entities::*;
+use particle_system::render::*;
+use particle_system::sim::{MAX_ENEMIES, MAX_ENEMY_BULLETS, MAX_PLAYER_BULLETS};
+
+fn field(channel: usize, name: &str) -> usize {
+    CHANNELS[channel].fields.iter().position(|f| *f == name).unwrap()
+}
+
+#[test]
+fn channels_use_their_schema_stride() {
+    let mut sim = sim();
+    sim.add_enemy(enemy(100.0, 100.0, EnemyType::Tank));
+    sim.add_enemy(enemy(200.0, 100.0, EnemyType::Basic));
+    sim.add_bullet(player_bullet(50.0, 50.0, 25.0));
+    sim.add_enemy_bullet(enemy_bullet(60.0, 60.0));
+    sim.add_power_up(power_up(70.0, 70.0, PowerUpType::Health));
+    sim.activate_black_hole();
+
+    let data = sim.render_data();
+
+    for (id, schema) in CHANNELS.iter().enumerate() {
+        assert_eq!(data.channel(id).len(), data.count(id) * schema.stride());
+    }
+    assert_eq!(data.count(CHANNEL_PLAYER), 1);
+    assert_eq!(data.count(CHANNEL_ENEMIES), 2);
+    assert_eq!(data.count(CHANNEL_PLAYER_BULLETS), 1);
+    assert_eq!(data.count(CHANNEL_ENEMY_BULLETS), 1);
+    assert_eq!(data.count(CHANNEL_POWER_UPS), 1);
+    assert_eq!(data.count(CHANNEL_EXPLOSIONS), 0);
+    assert_eq!(data.count(CHANNEL_BLACK_HOLES), 1);
+}
+
+#[test]
+fn hidden_fields_are_exported() {
+    let mut sim = sim();
+    sim.shield_level = 2;
+    sim.shield_active = true;
+    let mut tank = enemy(100.0, 100.0, EnemyType::Tank);
+    tank.health = 10.0;
+    tank.vx = -12.0;
+    sim.add_enemy(tank);
+
+    let data = sim.render_data();
+
+    let player = data.channel(CHANNEL_PLAYER);
+    assert_eq!(player[field(CHANNEL_PLAYER, "shield_level")], 2.0);
+    assert_eq!(player[field(CHANNEL_PLAYER, "shield_active")], 1.0);
+
+    let enemies = data.channel(CHANNEL_ENEMIES);
+    assert_eq!(enemies[field(CHANNEL_ENEMIES, "health")], 10.0);
+    assert_eq!(enemies[field(CHANNEL_ENEMIES, "max_health")], 50.0);
+    assert_eq!(enemies[field(CHANNEL_ENEMIES, "vx")], -12.0);
+    assert_eq!(enemies[field(CHANNEL_ENEMIES, "type")], 2.0);
+}
+
+#[test]
+fn schema_json_describes_every_channel() {
+    let json = schema_json();
+
+    assert!(json.starts_with(&format!("{{\"version\":{},", RENDER_SCHEMA_VERSION)));
+    for (id, schema) in CHANNELS.iter().enumerate() {
+        assert!(json.contains(&format!(
+            "{{\"id\":{},\"name\":\"{}\",\"stride\":{},",
+            id,
+            schema.name,
+            schema.stride()
+        )));
+    }
+}
+
+#[test]
+fn reused_buffers_keep_their_allocation() {
+    let mut sim = sim();
+    let mut data = RenderData::with_capacity(1024);
+    let ptr = data.channel(CHANNEL_PLAYER_BULLETS).as_ptr();
+
+    for _ in 0..100 {
+        sim.add_bullet(player_bullet(50.0, 50.0, 25.0));
+        sim.write_render_data(&mut data);
+    }
+
+    assert_eq!(data.channel(CHANNEL_PLAYER_BULLETS).as_ptr(), ptr);
+    assert_eq!(data.count(CHANNEL_PLAYER_BULLETS), 100);
+}
+
+#[test]
+fn full_buffers_fit_every_pool_at_its_limit() {
+    let mut sim = sim();
+    for i in 0..MAX_PLAYER_BULLETS + 10 {
+        sim.add_bullet(player_bullet((i % 800) as f32, 300.0, 1.0));
+    }
+    for i in 0..MAX_ENEMY_BULLETS + 10 {
+        sim.add_enemy_bullet(enemy_bullet((i % 800) as f32, 300.0));
+    }
+    for i in 0..MAX_ENEMIES + 10 {
+        sim.add_enemy(enemy((i % 800) as f32, 100.0, EnemyType::Basic));
+    }
+
+    let mut data = RenderData::with_full_capacity();
+    let ptrs: Vec<*const f32> = (0..CHANNEL_COUNT).map(|id| data.channel(id).as_ptr()).collect();
+    sim.write_render_data(&mut data);
+
+    assert_eq!(data.count(CHANNEL_PLAYER_BULLETS), MAX_PLAYER_BULLETS);
+    assert_eq!(data.count(CHANNEL_ENEMY_BULLETS), MAX_ENEMY_BULLETS);
+    assert_eq!(data.count(CHANNEL_ENEMIES), MAX_ENEMIES);
+    for id in 0..CHANNEL_COUNT {
+        assert_eq!(data.channel(id).as_ptr(), ptrs[id], "{} moved", CHANNELS[id].name);
+    }
+}
@@ -0,0 +1,148 @@
+use particle_system::replay::ReplayError;
+use particle_system::sim::FIXED_TIMESTEP;
+use particle_system::{GameConfig, InputFrame, Replay, Simulation};
+
+fn play(sim: &mut Simulation, frames: u32, frame_time: f32) {
+    for frame in 0..frames {
+        let input = InputFrame {
+            move_x: (frame as f32 * 0.05).sin().round(),
+            move_y: if frame % 200 < 100 { -0.5 } else { 0.5 },
+            fire: frame % 7 != 0,
+            black_hole: frame % 500 == 250,
+        };
+        sim.queue_input(sim.tick, input);
+        sim.advance(frame_time);
+    }
+}
+
+fn run_to_end(sim: &mut Simulation) {
+    while !sim.playback_finished() && !sim.game_over {
+        sim.advance(FIXED_TIMESTEP);
+    }
+}
+
+#[test]
+fn replay_reproduces_fixed_step_run() {
+    let mut sim = Simulation::new(800.0, 600.0, 99);
+    sim.set_fixed_timestep(true);
+    play(&mut sim, 1500, 1.0 / 144.0);
+
+    let bytes = sim.replay().to_bytes();
+    let mut playback = Simulation::from_replay(&Replay::from_bytes(&bytes).unwrap()).unwrap();
+    run_to_end(&mut playback);
+
+    assert_eq!(playback.tick, sim.tick);
+    assert_eq!(playback.score, sim.score);
+    assert_eq!(playback.rng_state(), sim.rng_state());
+    assert_eq!(playback.render_data(), sim.render_data());
+}
+
+#[test]
+fn replay_reproduces_variable_step_run() {
+    let mut sim = Simulation::new(800.0, 600.0, 5);
+    for frame in 0..600 {
+        sim.queue_input(
+            sim.tick,
+            InputFrame {
+                fire: true,
+                move_x: if frame % 90 < 45 { 1.0 } else { -1.0 },
+                ..InputFrame::default()
+            },
+        );
+        sim.advance(if frame % 3 == 0 { 0.02 } else { 0.013 });
+    }
+
+    let mut playback = Simulation::from_replay(sim.replay()).unwrap();
+    while !playback.playback_finished() {
+        // Frame time is ignored during playback
+        playback.advance(1.0);
+    }
+
+    assert_eq!(playback.render_data(), sim.render_data());
+}
+
+#[test]
+fn replay_keeps_presses_on_consecutive_ticks() {
+    let mut config = GameConfig::default();
+    config.black_hole.cooldown = 0.01;
+    let mut sim = Simulation::with_config(800.0, 600.0, 3, config);
+    sim.set_fixed_timestep(true);
+    let press = InputFrame {
+        black_hole: true,
+        ..InputFrame::default()
+    };
+    for _ in 0..2 {
+        sim.queue_input(sim.tick, press);
+        sim.advance(FIXED_TIMESTEP);
+    }
+    assert_eq!(sim.black_holes.len(), 2);
+
+    let mut playback = Simulation::from_replay(sim.replay()).unwrap();
+    run_to_end(&mut playback);
+    assert_eq!(playback.black_holes.len(), 2);
+    assert_eq!(playback.render_data(), sim.render_data());
+}
+
+#[test]
+fn replay_round_trips_through_bytes() {
+    let mut sim = Simulation::new(1024.0, 768.0, 12345);
+    play(&mut sim, 300, FIXED_TIMESTEP);
+
+    let bytes = sim.replay().to_bytes();
+    let decoded = Replay::from_bytes(&bytes).unwrap();
+
+    assert_eq!(&decoded, sim.replay());
+    assert_eq!(decoded.ticks.len(), 300);
+    // Mostly unchanged ticks encode as a single byte
+    let header = Replay { ticks: Vec::new(), ..decoded }.to_bytes().len();
+    assert!(bytes.len() - header < 300 * 3);
+}
+
+#[test]
+fn reset_starts_a_new_recording() {
+    let mut sim = Simulation::new(800.0, 600.0, 1);
+    play(&mut sim, 100, FIXED_TIMESTEP);
+
+    sim.reset();
+
+    assert!(sim.replay().ticks.is_empty());
+    assert_eq!(sim.replay().seed, sim.seed());
+}
+
+#[test]
+fn invalid_replays_are_rejected() {
+    let bytes = Simulation::new(800.0, 600.0, 1).replay().to_bytes();
+
+    assert_eq!(Replay::from_bytes(b"nope"), Err(ReplayError::BadMagic));
+    assert_eq!(
+        Replay::from_bytes(&bytes[..bytes.len() - 1]),
+        Err(ReplayError::Truncated)
+    );
+
+    let mut future = bytes.clone();
+    future[4] = 99;
+    assert_eq!(
+        Replay::from_bytes(&future),
+        Err(ReplayError::UnsupportedVersion(99))
+    );
+}
+
+#[test]
+fn live_input_is_ignored_during_playback() {
+    let mut sim = Simulation::new(800.0, 600.0, 3);
+    play(&mut sim, 200, FIXED_TIMESTEP);
+
+    let mut playback = Simulation::from_replay(sim.replay()).unwrap();
+    while !playback.playback_finished() {
+        playback.queue_input(
+            playback.tick,
+            InputFrame {
+                move_x: -1.0,
+                ..InputFrame::default()
+            },
+        );
+        playback.advance(FIXED_TIMESTEP);
+    }
+
+    assert_eq!(playback.render_data(), sim.render_data());
+}
@@ -0,0 +1,98 @@
+mod common;
+
+use common::*;
+use particle_system::entities::*;
+use particle_system::sim::FIXED_TIMESTEP;
+use particle_system::state::StateError;
+use particle_system::{InputFrame, Simulation};
+
+fn play(sim: &mut Simulation, ticks: u32) {
+    for tick in 0..ticks {
+        let input = InputFrame {
+            move_x: if tick % 80 < 40 { 1.0 } else { -1.0 },
+            fire: true,
+            black_hole: tick % 300 == 10,
+            ..InputFrame::default()
+        };
+        sim.queue_input(sim.tick, input);
+        sim.update(FIXED_TIMESTEP);
+    }
+}
+
+#[test]
+fn loaded_state_continues_identically() {
+    let mut sim = sim();
+    play(&mut sim, 900);
+
+    let mut loaded = Simulation::new(320.0, 240.0, 0);
+    loaded.load_state(&sim.save_state()).unwrap();
+
+    assert_eq!(loaded.render_data(), sim.render_data());
+    assert_eq!(loaded.rng_state(), sim.rng_state());
+
+    play(&mut sim, 600);
+    play(&mut loaded, 600);
+
+    assert_eq!(loaded.render_data(), sim.render_data());
+    assert_eq!(loaded.score, sim.score);
+    assert_eq!(loaded.tick, sim.tick);
+}
+
+#[test]
+fn state_includes_shield_and_black_holes() {
+    let mut sim = sim();
+    sim.shield_level = 2;
+    sim.shield_active = true;
+    sim.shield_timer = 4.5;
+    sim.activate_black_hole();
+    sim.black_holes[0].consumed_enemies.push((1.0, 2.0));
+    sim.black_holes[0].consumed_enemies.push((3.0, 4.0));
+    sim.add_enemy(enemy(10.0, 20.0, EnemyType::Tank));
+    sim.add_power_up(power_up(30.0, 40.0, PowerUpType::Shield));
+
+    let loaded = Simulation::from_state(&sim.save_state()).unwrap();
+
+    assert_eq!(loaded.shield_level, 2);
+    assert!(loaded.shield_active);
+    assert_eq!(loaded.shield_timer, 4.5);
+    assert_eq!(loaded.player.black_hole_cooldown, 10.0);
+    assert_eq!(loaded.black_holes[0].consumed_enemies, vec![(1.0, 2.0), (3.0, 4.0)]);
+    assert!(loaded.enemies[0].enemy_type == EnemyType::Tank);
+    assert!(matches!(loaded.power_ups[0].power_type, PowerUpType::Shield));
+}
+
+#[test]
+fn rejected_state_leaves_game_untouched() {
+    let mut sim = sim();
+    play(&mut sim, 200);
+    let before = sim.render_data();
+    let mut bytes = sim.save_state();
+
+    assert_eq!(sim.load_state(b"garbage"), Err(StateError::BadMagic));
+    assert_eq!(
+        sim.load_state(&bytes[..bytes.len() - 3]),
+        Err(StateError::Truncated)
+    );
+
+    bytes[4] = 0;
+    bytes[5] = 0;
+    assert_eq!(sim.load_state(&bytes), Err(StateError::UnsupportedVersion(0)));
+
+    assert_eq!(sim.render_data(), before);
+}
+
+#[test]
+fn replay_after_load_starts_from_the_loaded_state() {
+    let mut sim = sim();
+    play(&mut sim, 300);
+
+    let mut resumed = Simulation::from_state(&sim.save_state()).unwrap();
+    play(&mut resumed, 400);
+
+    let mut playback = Simulation::from_replay(resumed.replay()).unwrap();
+    while !playback.playback_finished() && !playback.game_over {
+        playback.update(FIXED_TIMESTEP);
+    }
+
+    assert_eq!(playback.render_data(), resumed.render_data());
+}
@@ -0,0 +1,103 @@
+mod common;
+
+use common::*;
+use particle_system::entities::*;
+
+fn shielded(level: u32) -> particle_system::Simulation {
+    let mut sim = sim();
+    sim.shield_level = level;
+    sim.shield_active = true;
+    sim.shield_timer = 10.0;
+    sim
+}
+
+#[test]
+fn shield_absorbs_enemy_bullet() {
+    let mut sim = shielded(2);
+    let (px, py) = (sim.player.x, sim.player.y);
+    sim.add_enemy_bullet(enemy_bullet(px, py));
+
+    sim.check_collisions();
+
+    assert!(sim.enemy_bullets.is_empty());
+    assert_eq!(sim.player.health, 100.0);
+    assert_eq!(sim.shield_level, 1);
+    assert!(sim.shield_active);
+}
+
+#[test]
+fn shield_breaks_when_last_level_is_used() {
+    let mut sim = shielded(1);
+    let (px, py) = (sim.player.x, sim.player.y);
+    sim.add_enemy_bullet(enemy_bullet(px, py));
+
+    sim.check_collisions();
+
+    assert_eq!(sim.shield_level, 0);
+    assert!(!sim.shield_active);
+    assert_eq!(sim.shield_timer, 0.0);
+}
+
+#[test]
+fn shield_destroys_ramming_enemy() {
+    let mut sim = shielded(1);
+    let (px, py) = (sim.player.x, sim.player.y);
+    sim.add_enemy(enemy(px, py, EnemyType::Basic));
+
+    sim.check_collisions();
+
+    assert!(sim.enemies.is_empty());
+    assert_eq!(sim.player.health, 100.0);
+    assert_eq!(sim.shield_level, 0);
+}
+
+#[test]
+fn tank_costs_two_shield_levels() {
+    let mut sim = shielded(3);
+    let (px, py) = (sim.player.x, sim.player.y);
+    sim.add_enemy(enemy(px, py, EnemyType::Tank));
+
+    sim.check_collisions();
+
+    assert!(sim.enemies.is_empty());
+    assert_eq!(sim.shield_level, 1);
+    assert_eq!(sim.player.health, 100.0);
+}
+
+#[test]
+fn weak_shield_does_not_stop_tank() {
+    let mut sim = shielded(1);
+    let (px, py) = (sim.player.x, sim.player.y);
+    sim.add_enemy(enemy(px, py, EnemyType::Tank));
+
+    sim.check_collisions();
+
+    assert_eq!(sim.enemies.len(), 1);
+    assert_eq!(sim.shield_level, 1);
+    assert_eq!(sim.player.health, 80.0);
+}
+
+#[test]
+fn shield_expires_after_timer() {
+    let mut sim = shielded(3);
+
+    sim.update_shield(9.5);
+    assert!(sim.shield_active);
+
+    sim.update_shield(1.0);
+    assert!(!sim.shield_active);
+    assert_eq!(sim.shield_timer, 0.0);
+}
+
+#[test]
+fn shield_stacks_up_to_three() {
+    let mut sim = sim();
+    let (px, py) = (sim.player.x, sim.player.y);
+    for _ in 0..5 {
+        sim.add_power_up(power_up(px, py, PowerUpType::Shield));
+    }
+
+    sim.check_collisions();
+
+    assert_eq!(sim.shield_level, 3);
+}
@@ -0,0 +1,194 @@
+mod common;
+
+use common::*;
+use particle_system::entities::*;
+use particle_system::events::GameEvent;
+use particle_system::sim::FIXED_TIMESTEP;
+use particle_system::movement::Movement;
+use particle_system::stage::{SpawnGroup, Stage, Step};
+use particle_system::{GameConfig, Simulation};
+
+fn group(enemy: EnemyType, count: u32, x: f32) -> SpawnGroup {
+    SpawnGroup {
+        enemy,
+        count,
+        x,
+        y: -50.0,
+        spacing: 0.0,
+        interval: 0.0,
+        movement: None,
+    }
+}
+
+fn staged(stage: Stage) -> Simulation {
+    let config = GameConfig {
+        stage: Some(stage),
+        ..GameConfig::default()
+    };
+    Simulation::with_config(WIDTH, HEIGHT, 7, config)
+}
+
+// Runs ticks and returns how many of them completed the stage
+fn run(sim: &mut Simulation, ticks: u32) -> usize {
+    let mut completed = 0;
+    for _ in 0..ticks {
+        sim.update(FIXED_TIMESTEP);
+        completed += sim.drain_events().iter().filter(|e| **e == GameEvent::StageComplete).count();
+    }
+    completed
+}
+
+#[test]
+fn stages_parse_from_json() {
+    let stage = Stage::from_json(
+        r#"{ "steps": [
+            { "spawn": { "enemy": "basic", "count": 5, "x": 0.2, "spacing": 60 } },
+            { "wait": 2.0 },
+            { "spawn": { "enemy": "tank", "x": 0.5, "movement": "drift" } },
+            "wait_until_cleared"
+        ], "endless": true }"#,
+    )
+    .unwrap();
+
+    assert!(stage.endless);
+    assert_eq!(
+        stage.steps,
+        vec![
+            Step::Spawn(SpawnGroup {
+                spacing: 60.0,
+                ..group(EnemyType::Basic, 5, 0.2)
+            }),
+            Step::Wait(2.0),
+            Step::Spawn(SpawnGroup {
+                movement: Some(Movement::Drift),
+                ..group(EnemyType::Tank, 1, 0.5)
+            }),
+            Step::WaitUntilCleared,
+        ]
+    );
+}
+
+#[test]
+fn invalid_stages_name_the_step() {
+    let err = Stage::from_json(r#"{"steps": [{"wait": 1}, {"spawn": {"enemy": "fast", "x": 1.5}}]}"#).unwrap_err();
+    assert_eq!(err.to_string(), "invalid game config: stage.steps[1].spawn.x must be between 0 and 1");
+
+    let err = Stage::from_json(r#"{"steps": [{"spawn": {"enemy": "boss", "x": 0.5}}]}"#).unwrap_err();
+    assert!(err.to_string().starts_with("could not parse game config"));
+
+    // Stages in a config are checked with it
+    let err = GameConfig::from_json(r#"{"stage": {"steps": [{"wait": -1}]}}"#).unwrap_err();
+    assert_eq!(err.to_string(), "invalid game config: stage.steps[0].wait must be zero or more");
+}
+
+#[test]
+fn groups_spawn_spaced_out_over_time() {
+    let mut sim = staged(Stage {
+        steps: vec![Step::Spawn(SpawnGroup {
+            spacing: 50.0,
+            interval: 0.5,
+            ..group(EnemyType::Fast, 3, 0.25)
+        })],
+        endless: false,
+    });
+
+    run(&mut sim, 1);
+    assert_eq!(sim.enemies.len(), 1);
+    assert_eq!(sim.enemies[0].enemy_type, EnemyType::Fast);
+    assert_eq!(sim.enemies[0].vx, 0.0);
+
+    // One more every half second, each further along
+    run(&mut sim, 60);
+    let xs: Vec<f32> = sim.enemies.iter().map(|e| e.x).collect();
+    assert_eq!(xs, vec![200.0, 250.0, 300.0]);
+    assert!(sim.stage.complete);
+}
+
+#[test]
+fn waits_hold_the_timeline() {
+    let mut sim = staged(Stage {
+        steps: vec![
+            Step::Spawn(group(EnemyType::Basic, 1, 0.1)),
+            Step::Wait(1.0),
+            Step::Spawn(group(EnemyType::Basic, 1, 0.5)),
+            Step::WaitUntilCleared,
+            Step::Spawn(group(EnemyType::Tank, 1, 0.9)),
+        ],
+        endless: false,
+    });
+
+    run(&mut sim, 50);
+    assert_eq!(sim.enemies.len(), 1);
+    run(&mut sim, 20);
+    assert_eq!(sim.enemies.len(), 2);
+
+    // The tank waits for the screen to clear
+    run(&mut sim, 60);
+    assert_eq!(sim.enemies.len(), 2);
+    sim.enemies.clear();
+    assert_eq!(run(&mut sim, 1), 1);
+    assert_eq!(sim.enemies.len(), 1);
+    assert_eq!(sim.enemies[0].enemy_type, EnemyType::Tank);
+}
+
+#[test]
+fn stages_replace_random_spawning() {
+    let mut sim = staged(Stage {
+        steps: vec![Step::Wait(0.5)],
+        endless: false,
+    });
+    assert_eq!(run(&mut sim, 600), 1);
+    assert!(sim.stage.complete);
+    assert!(sim.enemies.is_empty());
+}
+
+#[test]
+fn endless_stages_fall_back_to_random_spawning() {
+    let mut sim = staged(Stage {
+        steps: vec![Step::Wait(0.5)],
+        endless: true,
+    });
+    assert_eq!(run(&mut sim, 600), 1);
+    assert!(!sim.enemies.is_empty());
+}
+
+#[test]
+fn starting_a_stage_restarts_the_run() {
+    let mut sim = sim();
+    run(&mut sim, 300);
+    let stage = Stage {
+        steps: vec![Step::Spawn(group(EnemyType::Tank, 1, 0.5))],
+        endless: false,
+    };
+    sim.start_stage(Some(stage.clone()));
+    assert_eq!(sim.tick, 0);
+    assert_eq!(sim.config.stage, Some(stage));
+    assert!(sim.enemies.is_empty());
+
+    run(&mut sim, 1);
+    assert_eq!(sim.enemies.len(), 1);
+    assert_eq!(sim.replay().config, sim.config);
+}
+
+#[test]
+fn saves_continue_mid_stage() {
+    let mut sim = staged(Stage {
+        steps: vec![
+            Step::Spawn(SpawnGroup {
+                interval: 0.4,
+                movement: Some(Movement::Drift),
+                ..group(EnemyType::Basic, 6, 0.3)
+            }),
+            Step::Wait(1.5),
+            Step::Spawn(group(EnemyType::Fast, 2, 0.7)),
+        ],
+        endless: true,
+    });
+    run(&mut sim, 45);
+
+    let mut loaded = Simulation::from_state(&sim.save_state()).unwrap();
+    assert_eq!(loaded.stage, sim.stage);
+    run(&mut sim, 300);
+    run(&mut loaded, 300);
+    assert_eq!(loaded.save_state(), sim.save_state());
+}
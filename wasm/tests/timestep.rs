@@ -0,0 +1,176 @@
+mod common;
+
+use common::*;
+use particle_system::entities::*;
+use particle_system::render::CHANNEL_PLAYER_BULLETS;
+use particle_system::sim::{FIXED_TIMESTEP, MAX_CATCH_UP_STEPS};
+use particle_system::InputFrame;
+
+fn fixed_sim() -> particle_system::Simulation {
+    let mut sim = sim();
+    sim.set_fixed_timestep(true);
+    sim
+}
+
+#[test]
+fn small_frames_accumulate_into_ticks() {
+    let mut sim = fixed_sim();
+
+    sim.advance(FIXED_TIMESTEP * 0.6);
+    assert_eq!(sim.game_time, 0.0);
+    assert!((sim.interpolation_alpha() - 0.6).abs() < 1e-4);
+
+    sim.advance(FIXED_TIMESTEP * 0.6);
+    assert_eq!(sim.game_time, FIXED_TIMESTEP);
+    assert!((sim.interpolation_alpha() - 0.2).abs() < 1e-4);
+}
+
+#[test]
+fn long_frame_is_capped() {
+    let mut sim = fixed_sim();
+
+    sim.advance(10.0);
+
+    let expected = FIXED_TIMESTEP * MAX_CATCH_UP_STEPS as f32;
+    assert!((sim.game_time - expected).abs() < 1e-5);
+    assert!(sim.interpolation_alpha() < 1.0);
+}
+
+#[test]
+fn fixed_step_prevents_tunneling() {
+    let mut variable = sim();
+    let mut fixed = fixed_sim();
+
+    for sim in [&mut variable, &mut fixed] {
+        sim.add_enemy(enemy(400.0, 100.0, EnemyType::Basic));
+        sim.add_bullet(player_bullet(400.0, 130.0, 25.0));
+        sim.advance(0.5);
+    }
+
+    assert_eq!(variable.score, 0);
+    assert_eq!(fixed.score, 100);
+}
+
+#[test]
+fn previous_positions_trail_by_one_tick() {
+    let mut sim = fixed_sim();
+    sim.add_bullet(player_bullet(400.0, 300.0, 25.0));
+
+    sim.advance(FIXED_TIMESTEP * 1.5);
+
+    let bullet = sim.bullets.get(0);
+    assert_eq!(bullet.prev_y, 300.0);
+    assert!((bullet.y - (300.0 - 300.0 * FIXED_TIMESTEP)).abs() < 1e-3);
+
+    // prev_y is exported alongside y for the renderer to blend
+    let data = sim.render_data();
+    let bullets = data.channel(CHANNEL_PLAYER_BULLETS);
+    assert_eq!(bullets[3], 300.0);
+}
+
+#[test]
+fn variable_mode_renders_current_state() {
+    let mut sim = sim();
+
+    sim.advance(0.1);
+
+    assert_eq!(sim.interpolation_alpha(), 1.0);
+    assert_eq!(sim.game_time, 0.1);
+}
+
+#[test]
+fn pause_freezes_every_timer() {
+    let mut sim = fixed_sim();
+    sim.shield_active = true;
+    sim.shield_timer = 5.0;
+    sim.player.black_hole_cooldown = 3.0;
+    for _ in 0..30 {
+        sim.advance(FIXED_TIMESTEP);
+    }
+
+    sim.pause();
+    let before = (
+        sim.tick,
+        sim.game_time,
+        sim.shield_timer,
+        sim.player.black_hole_cooldown,
+        sim.enemy_spawn_timer,
+        sim.power_up_spawn_timer,
+        sim.interpolation_alpha(),
+    );
+    for _ in 0..120 {
+        sim.advance(FIXED_TIMESTEP);
+    }
+    let after = (
+        sim.tick,
+        sim.game_time,
+        sim.shield_timer,
+        sim.player.black_hole_cooldown,
+        sim.enemy_spawn_timer,
+        sim.power_up_spawn_timer,
+        sim.interpolation_alpha(),
+    );
+    assert_eq!(before, after);
+
+    sim.resume();
+    sim.advance(FIXED_TIMESTEP);
+    assert_eq!(sim.tick, before.0 + 1);
+}
+
+#[test]
+fn time_scale_slows_the_game() {
+    let mut sim = fixed_sim();
+    sim.set_time_scale(0.5);
+
+    for _ in 0..60 {
+        sim.advance(FIXED_TIMESTEP);
+    }
+
+    assert_eq!(sim.tick, 30);
+    assert!((sim.game_time - 0.5).abs() < 1e-4);
+}
+
+#[test]
+fn zero_time_scale_runs_no_ticks() {
+    let mut sim = sim();
+    sim.set_time_scale(0.0);
+    sim.queue_input(0, InputFrame {
+        fire: true,
+        ..InputFrame::default()
+    });
+
+    sim.advance(FIXED_TIMESTEP);
+    assert_eq!(sim.tick, 0);
+    assert!(sim.bullets.is_empty());
+
+    // The input waits for its tick
+    sim.set_time_scale(1.0);
+    sim.advance(FIXED_TIMESTEP);
+    assert_eq!(sim.tick, 1);
+    assert_eq!(sim.bullets.len(), 1);
+}
+
+#[test]
+fn time_scale_is_clamped() {
+    let mut sim = sim();
+    sim.set_time_scale(100.0);
+    assert_eq!(sim.time_scale(), particle_system::sim::MAX_TIME_SCALE);
+    sim.set_time_scale(-1.0);
+    assert_eq!(sim.time_scale(), 0.0);
+    sim.set_time_scale(f32::NAN);
+    assert_eq!(sim.time_scale(), 0.0);
+}
+
+#[test]
+fn long_frame_auto_pauses() {
+    let mut sim = fixed_sim();
+    sim.set_auto_pause(0.25);
+
+    sim.advance(FIXED_TIMESTEP);
+    assert_eq!(sim.tick, 1);
+
+    // Tab was in the background for two seconds
+    sim.advance(2.0);
+    assert!(sim.is_paused());
+    assert_eq!(sim.tick, 1);
+}
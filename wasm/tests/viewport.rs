@@ -0,0 +1,24 @@
+use particle_system::viewport::ViewTransform;
+
+#[test]
+fn wide_screen_gets_side_bars() {
+    let view = ViewTransform::fit(1280.0, 720.0, 1920.0, 720.0);
+    assert_eq!(view.scale, 1.0);
+    assert_eq!((view.offset_x, view.offset_y), (320.0, 0.0));
+    assert_eq!(view.to_screen(0.0, 0.0), (320.0, 0.0));
+    assert_eq!(view.to_screen(1280.0, 720.0), (1600.0, 720.0));
+}
+
+#[test]
+fn tall_screen_gets_top_and_bottom_bars() {
+    let view = ViewTransform::fit(1280.0, 720.0, 640.0, 720.0);
+    assert_eq!(view.scale, 0.5);
+    assert_eq!((view.offset_x, view.offset_y), (0.0, 180.0));
+}
+
+#[test]
+fn screen_to_world_undoes_world_to_screen() {
+    let view = ViewTransform::fit(1280.0, 720.0, 2560.0, 1600.0);
+    let (sx, sy) = view.to_screen(100.0, 50.0);
+    assert_eq!(view.to_world(sx, sy), (100.0, 50.0));
+}